@@ -8,6 +8,7 @@ pub enum AnomalyType {
     Restarts,
     Latency,
     Login,
+    Suspicious,
 }
 
 impl AnomalyType {
@@ -19,6 +20,7 @@ impl AnomalyType {
             "restarts" => Some(AnomalyType::Restarts),
             "latency" => Some(AnomalyType::Latency),
             "login" => Some(AnomalyType::Login),
+            "suspicious" => Some(AnomalyType::Suspicious),
             _ => None,
         }
     }
@@ -31,6 +33,7 @@ impl AnomalyType {
             AnomalyType::Restarts => "restarts",
             AnomalyType::Latency => "latency",
             AnomalyType::Login => "login",
+            AnomalyType::Suspicious => "suspicious",
         }
     }
 }
@@ -0,0 +1,736 @@
+//! Kubernetes API server audit events.
+//!
+//! Each cluster's API server sees the traffic a real one does for the
+//! topology's namespaces and services: kubelets renewing their node leases,
+//! updating their pods' status and reading the pods' Secrets and ConfigMaps;
+//! controllers reconciling Deployments and EndpointSlices; the scheduler and
+//! the ReplicaSet controller following pod churn (`lifecycle.rs`, so the pod
+//! names match the other signals); each service's own service account reading
+//! its config; the monitoring and DNS services watching the cluster; a CI
+//! deployer patching Deployments; and SREs using kubectl, now and then with a
+//! legitimate `exec`. Every request is logged at `RequestReceived` and
+//! `ResponseComplete`, with `ResponseStarted` in between for watches (still
+//! open when the window ends, so they stop there) and exec sessions.
+//!
+//! `--anomaly suspicious` adds what a security dashboard should flag: Secret
+//! reads by service accounts that never read them (allowed, or denied with
+//! 403), `exec` into running pods from outside the cluster, and RBAC changes
+//! that hand out `cluster-admin`.
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, SecondsFormat};
+use rand::{seq::SliceRandom, Rng};
+use serde_json::json;
+
+use super::types::{AuditEvent, ObjectRef, ResponseStatus, UserInfo};
+use crate::anomaly::{AnomalyState, AnomalyType};
+use crate::config::AUDIT_REQUESTS_PER_SEC;
+use crate::lifecycle::{pods_at, PodRef};
+use crate::metrics::kube::{node_ip, pod_ip, uuid_from};
+use crate::topology::topology;
+use crate::utils::{mix64, rng_uuid, str_hash, weighted_choice};
+
+/// Suspicious requests per cluster per second while the anomaly is active.
+const SUSPICIOUS_PER_SEC: f64 = 0.3;
+const K8S_BUILD: &str = "v1.29.4 (linux/amd64) kubernetes/55019c8";
+const KUBECTL_MAC: &str = "kubectl/v1.29.2 (darwin/arm64) kubernetes/4b8e819";
+const KUBECTL_LINUX: &str = "kubectl/v1.29.2 (linux/amd64) kubernetes/4b8e819";
+const SRE_USERS: &[&str] = &["alice@example.com", "bob@example.com", "oncall@example.com"];
+/// The binding that authorizes allowed suspicious requests by service accounts.
+const LEGACY_BINDING: &str = "RBAC: allowed by ClusterRoleBinding \"legacy-admin\" of ClusterRole \"cluster-admin\" to Group \"system:serviceaccounts\"";
+/// Cluster-wide readers: (service, verb, resource).
+const WATCHERS: &[(&str, &str, &str)] = &[
+    ("prometheus", "list", "pods"),
+    ("prometheus", "watch", "endpoints"),
+    ("prometheus", "list", "nodes"),
+    ("coredns", "watch", "endpointslices"),
+    ("coredns", "watch", "services"),
+    ("nginx-ingress", "watch", "ingresses"),
+];
+
+/// Normal traffic, by weight.
+const NORMAL_REQUESTS: &[(&str, u32)] = &[
+    ("lease", 300),
+    ("pod_status", 140),
+    ("kubelet_read", 80),
+    ("controller", 140),
+    ("watch", 80),
+    ("own_config", 100),
+    ("deploy", 15),
+    ("sre", 40),
+    ("sre_exec", 1),
+];
+
+/// Who sends a request, as the API server authenticates it.
+struct Actor {
+    username: String,
+    uid: String,
+    groups: Vec<String>,
+    source_ip: String,
+    user_agent: String,
+    /// How RBAC allowed it (`authorization.k8s.io/reason`).
+    allowed_by: String,
+}
+
+/// One API request, before it is split into its audit stages.
+struct Request {
+    actor: Actor,
+    verb: &'static str,
+    object: ObjectRef,
+    code: u16,
+    level: &'static str,
+    request_object: Option<serde_json::Value>,
+}
+
+fn service_account(
+    namespace: &str,
+    name: &str,
+    source_ip: String,
+    user_agent: String,
+    allowed_by: String,
+) -> Actor {
+    let username = format!("system:serviceaccount:{}:{}", namespace, name);
+    Actor {
+        uid: uuid_from(str_hash(&username)),
+        username,
+        groups: vec![
+            "system:serviceaccounts".to_string(),
+            format!("system:serviceaccounts:{}", namespace),
+            "system:authenticated".to_string(),
+        ],
+        source_ip,
+        user_agent,
+        allowed_by,
+    }
+}
+
+/// The service account a service's pods run as, calling from `pod`.
+fn pod_account(pod: &PodRef, user_agent: Option<&str>) -> Actor {
+    let spec = &topology().pods[pod.svc_idx];
+    service_account(
+        spec.namespace,
+        spec.service,
+        pod_ip(pod.cluster, &pod.name),
+        user_agent.map(str::to_string).unwrap_or_else(|| {
+            format!("{}/v0.0.0 (linux/amd64) kubernetes/$Format", spec.container)
+        }),
+        format!(
+            "RBAC: allowed by RoleBinding \"{s}/{ns}\" of Role \"{s}\" to ServiceAccount \"{s}/{ns}\"",
+            s = spec.service,
+            ns = spec.namespace
+        ),
+    )
+}
+
+fn control_plane_ip(cluster: &str) -> String {
+    let h = mix64(str_hash(cluster));
+    format!("172.16.{}.{}", h % 256, 10 + (h >> 8) % 3)
+}
+
+fn controller(cluster: &str, name: &str) -> Actor {
+    service_account(
+        "kube-system",
+        name,
+        control_plane_ip(cluster),
+        format!(
+            "kube-controller-manager/{}/system:serviceaccount:kube-system:{}",
+            K8S_BUILD, name
+        ),
+        format!(
+            "RBAC: allowed by ClusterRoleBinding \"system:controller:{n}\" of ClusterRole \"system:controller:{n}\" to ServiceAccount \"{n}/kube-system\"",
+            n = name
+        ),
+    )
+}
+
+fn scheduler(cluster: &str) -> Actor {
+    Actor {
+        username: "system:kube-scheduler".to_string(),
+        uid: String::new(),
+        groups: vec!["system:authenticated".to_string()],
+        source_ip: control_plane_ip(cluster),
+        user_agent: format!("kube-scheduler/{}/scheduler", K8S_BUILD),
+        allowed_by: "RBAC: allowed by ClusterRoleBinding \"system:kube-scheduler\" of ClusterRole \"system:kube-scheduler\" to User \"system:kube-scheduler\"".to_string(),
+    }
+}
+
+fn kubelet(cluster: &str, node: &str) -> Actor {
+    Actor {
+        username: format!("system:node:{}", node),
+        uid: String::new(),
+        groups: vec![
+            "system:nodes".to_string(),
+            "system:authenticated".to_string(),
+        ],
+        source_ip: node_ip(cluster, node),
+        user_agent: format!("kubelet/{}", K8S_BUILD),
+        // Kubelets go through the Node authorizer, which logs no reason.
+        allowed_by: String::new(),
+    }
+}
+
+fn person(username: &str, groups: &[&str], source_ip: String, user_agent: &str) -> Actor {
+    let mut all: Vec<String> = groups.iter().map(|g| g.to_string()).collect();
+    all.push("system:authenticated".to_string());
+    Actor {
+        username: username.to_string(),
+        uid: String::new(),
+        groups: all,
+        source_ip,
+        user_agent: user_agent.to_string(),
+        allowed_by: groups
+            .first()
+            .map(|g| {
+                format!(
+                    "RBAC: allowed by ClusterRoleBinding \"{g}\" of ClusterRole \"{g}\" to Group \"{g}\"",
+                    g = g
+                )
+            })
+            .unwrap_or_default(),
+    }
+}
+
+fn sre(rng: &mut impl Rng) -> Actor {
+    person(
+        SRE_USERS[rng.gen_range(0..SRE_USERS.len())],
+        &["sre"],
+        format!("198.51.100.{}", rng.gen_range(10..60)),
+        KUBECTL_MAC,
+    )
+}
+
+/// `resource` (with an optional `/subresource`) in `namespace`; the API group
+/// and version follow from the resource.
+fn object(resource: &str, namespace: &str, name: &str) -> ObjectRef {
+    let (resource, subresource) = resource.split_once('/').unwrap_or((resource, ""));
+    let (api_group, api_version) = match resource {
+        "deployments" | "replicasets" => ("apps", "v1"),
+        "endpointslices" => ("discovery.k8s.io", "v1"),
+        "leases" => ("coordination.k8s.io", "v1"),
+        "ingresses" => ("networking.k8s.io", "v1"),
+        "roles" | "rolebindings" | "clusterroles" | "clusterrolebindings" => {
+            ("rbac.authorization.k8s.io", "v1")
+        }
+        _ => ("", "v1"),
+    };
+    ObjectRef {
+        resource: resource.to_string(),
+        namespace: namespace.to_string(),
+        name: name.to_string(),
+        api_group: api_group.to_string(),
+        api_version: api_version.to_string(),
+        subresource: subresource.to_string(),
+    }
+}
+
+fn request(actor: Actor, verb: &'static str, object: ObjectRef, code: u16) -> Request {
+    Request {
+        actor,
+        verb,
+        object,
+        code,
+        level: "Metadata",
+        request_object: None,
+    }
+}
+
+/// `/api/v1/namespaces/payments/secrets/payments-api-credentials`.
+fn request_uri(verb: &str, o: &ObjectRef, rng: &mut impl Rng) -> String {
+    let mut uri = match o.api_group.as_str() {
+        "" => format!("/api/{}", o.api_version),
+        group => format!("/apis/{}/{}", group, o.api_version),
+    };
+    if !o.namespace.is_empty() {
+        uri.push_str(&format!("/namespaces/{}", o.namespace));
+    }
+    uri.push_str(&format!("/{}", o.resource));
+    if !o.name.is_empty() {
+        uri.push_str(&format!("/{}", o.name));
+    }
+    if !o.subresource.is_empty() {
+        uri.push_str(&format!("/{}", o.subresource));
+    }
+    match (verb, o.subresource.as_str()) {
+        ("watch", _) => uri.push_str(&format!(
+            "?allowWatchBookmarks=true&resourceVersion={}&timeout=7m{}s&timeoutSeconds={}&watch=true",
+            rng.gen_range(1_000_000..90_000_000),
+            rng.gen_range(0..60),
+            rng.gen_range(300..600)
+        )),
+        ("list", _) => uri.push_str("?limit=500"),
+        (_, "exec") => uri.push_str(&format!(
+            "?command=%2Fbin%2Fsh&container={}&stdin=true&stdout=true&tty=true",
+            exec_container(o)
+        )),
+        _ => {}
+    }
+    uri
+}
+
+/// Container of the pod an exec request targets.
+fn exec_container(o: &ObjectRef) -> &'static str {
+    topology()
+        .pods
+        .iter()
+        .find(|p| p.namespace == o.namespace && o.name.starts_with(p.service))
+        .map(|p| p.container)
+        .unwrap_or("app")
+}
+
+fn response_status(req: &Request) -> ResponseStatus {
+    let o = &req.object;
+    let what = match o.name.as_str() {
+        "" => o.resource.clone(),
+        name => format!("{} \"{}\"", o.resource, name),
+    };
+    let (reason, message) = match req.code {
+        403 => (
+            "Forbidden",
+            format!(
+                "{} is forbidden: User \"{}\" cannot {} resource \"{}\" in API group \"{}\"{}",
+                what,
+                req.actor.username,
+                req.verb,
+                o.resource,
+                o.api_group,
+                match o.namespace.as_str() {
+                    "" => " at the cluster scope".to_string(),
+                    ns => format!(" in the namespace \"{}\"", ns),
+                }
+            ),
+        ),
+        404 => ("NotFound", format!("{} not found", what)),
+        409 => (
+            "Conflict",
+            format!(
+                "Operation cannot be fulfilled on {}: the object has been modified; please apply your changes to the latest version and try again",
+                what
+            ),
+        ),
+        _ => ("", String::new()),
+    };
+    ResponseStatus {
+        metadata: BTreeMap::new(),
+        code: req.code,
+        status: if req.code >= 400 { "Failure" } else { "" }.to_string(),
+        reason: reason.to_string(),
+        message,
+    }
+}
+
+fn rfc3339(ts_us: i64) -> String {
+    DateTime::from_timestamp_micros(ts_us)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// The audit events of `req`, whose last stage is logged at `last_us`.
+fn stages(cluster: &str, req: Request, last_us: i64, rng: &mut impl Rng) -> Vec<AuditEvent> {
+    let exec = req.object.subresource == "exec";
+    let started_us = rng.gen_range(500..5_000);
+    let duration_us = match (req.verb, exec) {
+        ("watch", _) => started_us,
+        (_, true) => rng.gen_range(5_000_000..90_000_000),
+        ("list", _) => rng.gen_range(8_000..250_000),
+        _ => rng.gen_range(800..40_000),
+    };
+    let received_us = last_us - duration_us;
+    let mut at = vec![("RequestReceived", received_us)];
+    if req.verb == "watch" || exec {
+        at.push(("ResponseStarted", received_us + started_us));
+    }
+    if req.verb != "watch" {
+        at.push(("ResponseComplete", last_us));
+    }
+
+    let audit_id = rng_uuid(rng).to_string();
+    let uri = request_uri(req.verb, &req.object, rng);
+    let status = response_status(&req);
+    let mut annotations = BTreeMap::new();
+    if req.code == 403 {
+        annotations.insert(
+            "authorization.k8s.io/decision".to_string(),
+            "forbid".to_string(),
+        );
+        annotations.insert("authorization.k8s.io/reason".to_string(), String::new());
+    } else {
+        annotations.insert(
+            "authorization.k8s.io/decision".to_string(),
+            "allow".to_string(),
+        );
+        annotations.insert(
+            "authorization.k8s.io/reason".to_string(),
+            req.actor.allowed_by.clone(),
+        );
+    }
+
+    at.into_iter()
+        .map(|(stage, ts_us)| {
+            let received = stage == "RequestReceived";
+            AuditEvent {
+                _timestamp: ts_us,
+                cluster: cluster.to_string(),
+                kind: "Event".to_string(),
+                api_version: "audit.k8s.io/v1".to_string(),
+                level: req.level.to_string(),
+                audit_id: audit_id.clone(),
+                stage: stage.to_string(),
+                request_uri: uri.clone(),
+                verb: req.verb.to_string(),
+                user: UserInfo {
+                    username: req.actor.username.clone(),
+                    uid: req.actor.uid.clone(),
+                    groups: req.actor.groups.clone(),
+                },
+                source_ips: vec![req.actor.source_ip.clone()],
+                user_agent: req.actor.user_agent.clone(),
+                object_ref: req.object.clone(),
+                response_status: (!received).then(|| status.clone()),
+                request_object: req.request_object.clone(),
+                request_received_timestamp: rfc3339(received_us),
+                stage_timestamp: rfc3339(ts_us),
+                annotations: if received {
+                    BTreeMap::new()
+                } else {
+                    annotations.clone()
+                },
+            }
+        })
+        .collect()
+}
+
+/// A request from the cluster's everyday traffic.
+fn normal_request(cluster: &str, pods: &[PodRef], rng: &mut impl Rng) -> Option<Request> {
+    let pod = pods.choose(rng)?;
+    let spec = &topology().pods[pod.svc_idx];
+    let ns = spec.namespace;
+    Some(match weighted_choice(NORMAL_REQUESTS, rng) {
+        "lease" => request(
+            kubelet(cluster, pod.node),
+            "update",
+            object("leases", "kube-node-lease", pod.node),
+            200,
+        ),
+        "pod_status" => request(
+            kubelet(cluster, pod.node),
+            "patch",
+            object("pods/status", ns, &pod.name),
+            200,
+        ),
+        "kubelet_read" => {
+            let (resource, suffix) = [("secrets", "credentials"), ("configmaps", "config")]
+                .choose(rng)
+                .copied()
+                .unwrap_or(("configmaps", "config"));
+            request(
+                kubelet(cluster, pod.node),
+                "get",
+                object(resource, ns, &format!("{}-{}", spec.service, suffix)),
+                200,
+            )
+        }
+        "controller" => {
+            let replica_set = pod.name.rsplit_once('-').map(|(rs, _)| rs).unwrap_or("");
+            let (name, resource, object_name) = match rng.gen_range(0..3) {
+                0 => (
+                    "replicaset-controller",
+                    "replicasets/status",
+                    replica_set.to_string(),
+                ),
+                1 => (
+                    "deployment-controller",
+                    "deployments/status",
+                    spec.service.to_string(),
+                ),
+                _ => (
+                    "endpointslice-controller",
+                    "endpointslices",
+                    format!("{}-{:05x}", spec.service, str_hash(spec.service) & 0xfffff),
+                ),
+            };
+            // Optimistic concurrency: a few updates lose the race.
+            let code = if rng.gen_bool(0.03) { 409 } else { 200 };
+            request(
+                controller(cluster, name),
+                "update",
+                object(resource, ns, &object_name),
+                code,
+            )
+        }
+        "watch" => {
+            let (service, verb, resource) = *WATCHERS.choose(rng)?;
+            let watcher = pods
+                .iter()
+                .filter(|p| topology().pods[p.svc_idx].service == service)
+                .collect::<Vec<_>>()
+                .choose(rng)
+                .copied()?;
+            request(
+                pod_account(watcher, None),
+                verb,
+                object(resource, "", ""),
+                200,
+            )
+        }
+        "own_config" => {
+            let resource = if rng.gen_bool(0.3) {
+                "secrets"
+            } else {
+                "configmaps"
+            };
+            let suffix = if resource == "secrets" {
+                "credentials"
+            } else {
+                "config"
+            };
+            request(
+                pod_account(pod, None),
+                "get",
+                object(resource, ns, &format!("{}-{}", spec.service, suffix)),
+                200,
+            )
+        }
+        "deploy" => {
+            let runner = format!(
+                "10.{}.{}.{}",
+                60,
+                rng.gen_range(0..4),
+                rng.gen_range(2..250)
+            );
+            request(
+                service_account(
+                    "ci",
+                    "ci-deployer",
+                    runner,
+                    KUBECTL_LINUX.to_string(),
+                    "RBAC: allowed by ClusterRoleBinding \"ci-deployer\" of ClusterRole \"edit\" to ServiceAccount \"ci-deployer/ci\"".to_string(),
+                ),
+                "patch",
+                object("deployments", ns, spec.service),
+                200,
+            )
+        }
+        "sre" => {
+            let (verb, resource, name) = match rng.gen_range(0..4) {
+                0 => ("list", "pods", String::new()),
+                1 => ("get", "pods", pod.name.clone()),
+                2 => ("get", "pods/log", pod.name.clone()),
+                _ => ("get", "deployments", spec.service.to_string()),
+            };
+            // Typos and pods that are already gone.
+            let code = if rng.gen_bool(0.05) { 404 } else { 200 };
+            request(sre(rng), verb, object(resource, ns, &name), code)
+        }
+        _ => request(sre(rng), "create", object("pods/exec", ns, &pod.name), 101),
+    })
+}
+
+/// A request `--anomaly suspicious` adds.
+fn suspicious_request(cluster: &str, pods: &[PodRef], rng: &mut impl Rng) -> Option<Request> {
+    let intruder = pods.choose(rng)?;
+    let target = pods.choose(rng)?;
+    let intruder_ns = topology().pods[intruder.svc_idx].namespace;
+    let target_spec = &topology().pods[target.svc_idx];
+    let tool = ["curl/8.5.0", "python-requests/2.31.0", KUBECTL_LINUX].choose(rng)?;
+    let outsider = || format!("203.0.113.{}", mix64(str_hash(cluster)) % 200 + 20);
+
+    let mut req = match rng.gen_range(0..3) {
+        // A workload's service account reads Secrets it never touches.
+        0 => {
+            let (verb, name) = if rng.gen_bool(0.5) {
+                ("list", String::new())
+            } else {
+                ("get", format!("{}-credentials", target_spec.service))
+            };
+            let namespace = match rng.gen_range(0..3) {
+                0 => "kube-system",
+                1 if target_spec.namespace != intruder_ns => target_spec.namespace,
+                _ => "",
+            };
+            let code = if rng.gen_bool(0.7) { 403 } else { 200 };
+            request(
+                pod_account(intruder, Some(tool)),
+                verb,
+                object("secrets", namespace, &name),
+                code,
+            )
+        }
+        // A shell in a running pod, from outside the cluster.
+        1 => {
+            let actor = if rng.gen_bool(0.5) {
+                person(
+                    "temp-contractor@example.com",
+                    &["contractors"],
+                    outsider(),
+                    KUBECTL_LINUX,
+                )
+            } else {
+                pod_account(intruder, Some(KUBECTL_LINUX))
+            };
+            request(
+                actor,
+                "create",
+                object("pods/exec", target_spec.namespace, &target.name),
+                101,
+            )
+        }
+        // RBAC changes that hand out cluster-admin.
+        _ => {
+            let actor = pod_account(intruder, Some(tool));
+            let subject = json!({
+                "kind": "ServiceAccount",
+                "name": topology().pods[intruder.svc_idx].service,
+                "namespace": intruder_ns,
+            });
+            let (verb, resource, namespace, name, body) = match rng.gen_range(0..3) {
+                0 => (
+                    "create",
+                    "clusterrolebindings",
+                    "",
+                    "",
+                    json!({
+                        "kind": "ClusterRoleBinding",
+                        "apiVersion": "rbac.authorization.k8s.io/v1",
+                        "metadata": {"name": format!("debug-{:04x}", rng.gen::<u16>())},
+                        "roleRef": {"apiGroup": "rbac.authorization.k8s.io", "kind": "ClusterRole", "name": "cluster-admin"},
+                        "subjects": [subject],
+                    }),
+                ),
+                1 => (
+                    "create",
+                    "rolebindings",
+                    target_spec.namespace,
+                    "",
+                    json!({
+                        "kind": "RoleBinding",
+                        "apiVersion": "rbac.authorization.k8s.io/v1",
+                        "metadata": {"name": "admin-access", "namespace": target_spec.namespace},
+                        "roleRef": {"apiGroup": "rbac.authorization.k8s.io", "kind": "ClusterRole", "name": "cluster-admin"},
+                        "subjects": [subject],
+                    }),
+                ),
+                _ => (
+                    "patch",
+                    "clusterroles",
+                    "",
+                    "view",
+                    json!([{
+                        "op": "add",
+                        "path": "/rules/-",
+                        "value": {"apiGroups": ["*"], "resources": ["*"], "verbs": ["*"]},
+                    }]),
+                ),
+            };
+            let code = match (rng.gen_bool(0.4), verb) {
+                (true, _) => 403,
+                (false, "create") => 201,
+                _ => 200,
+            };
+            Request {
+                level: "RequestResponse",
+                request_object: Some(body),
+                ..request(actor, verb, object(resource, namespace, name), code)
+            }
+        }
+    };
+    // What lets a workload do any of this: a forgotten, overly broad binding.
+    if req.code != 403 && req.actor.username.starts_with("system:serviceaccount:") {
+        req.actor.allowed_by = LEGACY_BINDING.to_string();
+    }
+    Some(req)
+}
+
+/// Requests for pod churn in the window: the ReplicaSet controller creates
+/// the replacement and deletes the old pod, the scheduler binds the new one.
+fn churn_requests(cluster: &str, old: &[PodRef], new: &[PodRef]) -> Vec<(i64, Request)> {
+    let mut out = Vec::new();
+    for (old, new) in old.iter().zip(new).filter(|(o, n)| o.name != n.name) {
+        let ns = topology().pods[new.svc_idx].namespace;
+        let start_us = new.started_secs * 1_000_000;
+        out.push((
+            start_us - 30_000,
+            request(
+                controller(cluster, "replicaset-controller"),
+                "create",
+                object("pods", ns, ""),
+                201,
+            ),
+        ));
+        out.push((
+            start_us,
+            request(
+                scheduler(cluster),
+                "create",
+                object("pods/binding", ns, &new.name),
+                201,
+            ),
+        ));
+        out.push((
+            start_us,
+            request(
+                controller(cluster, "replicaset-controller"),
+                "delete",
+                object("pods", ns, &old.name),
+                200,
+            ),
+        ));
+    }
+    out
+}
+
+/// Audit events of every cluster's API server for the requests completed in
+/// the `window_us` before `ts_us`. `budget` caps the requests, split across
+/// clusters; without one each cluster gets `AUDIT_REQUESTS_PER_SEC`.
+pub fn generate_audit_events(
+    ts_us: i64,
+    window_us: i64,
+    budget: Option<usize>,
+    anomaly: Option<&AnomalyState>,
+    seed: u64,
+    rng: &mut impl Rng,
+) -> Vec<AuditEvent> {
+    let suspicious = anomaly
+        .map(|a| a.is_active() && a.anomaly_type == AnomalyType::Suspicious)
+        .unwrap_or(false);
+    let window_secs = window_us as f64 / 1_000_000.0;
+    let clusters = topology().clusters.len();
+    let (old_pods, new_pods) = (pods_at(ts_us - window_us, seed), pods_at(ts_us, seed));
+
+    let mut out = Vec::new();
+    for (idx, c) in topology().clusters.iter().enumerate() {
+        let in_cluster = |pods: &[PodRef]| -> Vec<PodRef> {
+            pods.iter()
+                .filter(|p| p.cluster_idx == idx)
+                .cloned()
+                .collect()
+        };
+        let (old, pods) = (in_cluster(&old_pods), in_cluster(&new_pods));
+        let mut requests = churn_requests(c.name, &old, &pods);
+
+        let n = match budget {
+            Some(b) => b / clusters + usize::from(idx < b % clusters),
+            None => {
+                let expected = AUDIT_REQUESTS_PER_SEC * window_secs;
+                expected as usize + usize::from(rng.gen_bool(expected.fract()))
+            }
+        };
+        for _ in 0..n {
+            let at = ts_us - rng.gen_range(0..window_us.max(1));
+            requests.extend(normal_request(c.name, &pods, rng).map(|r| (at, r)));
+        }
+        if suspicious {
+            for _ in 0..window_secs.ceil() as usize {
+                if rng.gen_bool(SUSPICIOUS_PER_SEC) {
+                    let at = ts_us - rng.gen_range(0..window_us.max(1));
+                    requests.extend(suspicious_request(c.name, &pods, rng).map(|r| (at, r)));
+                }
+            }
+        }
+        for (at, req) in requests {
+            out.extend(stages(c.name, req, at, rng));
+        }
+    }
+    out.sort_by_key(|e| e._timestamp);
+    out
+}
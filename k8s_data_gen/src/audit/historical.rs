@@ -0,0 +1,37 @@
+use super::generate::generate_audit_events;
+use crate::config::{stream_audit, AUDIT_REQUESTS_PER_SEC, HISTORICAL_OUT_AUDIT, INTERVAL_SECONDS};
+use crate::historical::{print_done, write_sharded, HistoricalOpts};
+use crate::topology::topology;
+
+pub fn run_historical_audit(
+    opts: &HistoricalOpts,
+    seed: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_path = opts.out_or(HISTORICAL_OUT_AUDIT);
+    let interval_secs = opts.interval_secs_or(INTERVAL_SECONDS);
+    let clusters = topology().clusters.len();
+    let total_requests = (opts.intervals(interval_secs) as f64
+        * interval_secs as f64
+        * AUDIT_REQUESTS_PER_SEC
+        * clusters as f64) as usize;
+
+    println!(
+        "Historical audit logs: {} every {}s, {} clusters → {}",
+        opts.describe(),
+        interval_secs,
+        clusters,
+        output_path
+    );
+    println!(
+        "Approx events: ~{} ({} requests, 2-3 stages each)",
+        total_requests * 2,
+        total_requests
+    );
+
+    let (written, files) = write_sharded(opts, &output_path, interval_secs, seed, |ts_us, rng| {
+        generate_audit_events(ts_us, interval_secs * 1_000_000, None, None, seed, rng)
+    })?;
+
+    print_done(written, "events", &files, Some(&stream_audit()));
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use reqwest::Client;
+
+use super::generate::generate_audit_events;
+use crate::anomaly::{AnomalyState, AnomalyType};
+use crate::client::http::post_live;
+use crate::config::{api_base, org, stream_audit};
+use crate::rate::{print_rate_header, RateSchedule, TokenBucket};
+use crate::utils::{print_anomaly_header, seeded_rng};
+
+/// Posts each second's audit events to `/_json`. The rate caps API requests,
+/// not events: each request is logged at two or three stages.
+pub async fn run_live_audit(
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_url = format!("{}/api/{}/{}/_json", api_base(), org(), stream_audit());
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    let mut rng = seeded_rng(seed);
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let mut anomaly_state = anomaly_type.map(AnomalyState::new);
+    let mut bucket = rate.map(TokenBucket::new);
+
+    println!("Live audit → {}", api_url);
+    print_rate_header(&bucket, "~2 API requests per cluster per second");
+    print_anomaly_header(&anomaly_state);
+    println!("Press Ctrl+C to stop.\n");
+
+    loop {
+        interval.tick().await;
+        if let Some(ref mut s) = anomaly_state {
+            s.tick(&mut rng);
+        }
+
+        let budget = bucket.as_mut().map(|b| {
+            let n = b.refill();
+            b.consume(n);
+            n
+        });
+        let now_us = Utc::now().timestamp_micros();
+        let events = generate_audit_events(
+            now_us,
+            1_000_000,
+            budget,
+            anomaly_state.as_ref(),
+            seed,
+            &mut rng,
+        );
+        if events.is_empty() {
+            continue;
+        }
+        post_live(&client, &api_url, &events, &anomaly_state).await;
+    }
+}
@@ -0,0 +1,9 @@
+pub mod backfill;
+pub mod generate;
+pub mod historical;
+pub mod live;
+pub mod types;
+
+pub use backfill::run_backfill_audit;
+pub use historical::run_historical_audit;
+pub use live::run_live_audit;
@@ -0,0 +1,80 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+/// One stage of one API request, as the API server's audit backend logs it
+/// (`audit.k8s.io/v1` `Event`).
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEvent {
+    /// `stageTimestamp`, in µs.
+    #[serde(rename = "_timestamp")]
+    pub _timestamp: i64,
+    pub cluster: String,
+    pub kind: String,
+    pub api_version: String,
+    /// `Metadata`, or `RequestResponse` for requests logged with their bodies.
+    pub level: String,
+    /// Shared by the stages of one request.
+    #[serde(rename = "auditID")]
+    pub audit_id: String,
+    /// `RequestReceived`, `ResponseStarted` or `ResponseComplete`.
+    pub stage: String,
+    #[serde(rename = "requestURI")]
+    pub request_uri: String,
+    pub verb: String,
+    pub user: UserInfo,
+    #[serde(rename = "sourceIPs")]
+    pub source_ips: Vec<String>,
+    pub user_agent: String,
+    pub object_ref: ObjectRef,
+    /// Absent at `RequestReceived`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_status: Option<ResponseStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_object: Option<serde_json::Value>,
+    pub request_received_timestamp: String,
+    pub stage_timestamp: String,
+    /// Authorization decision and reason; absent at `RequestReceived`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub annotations: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UserInfo {
+    pub username: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub uid: String,
+    pub groups: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectRef {
+    pub resource: String,
+    /// Empty for cluster-scoped resources.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub namespace: String,
+    /// Empty for `list`, `watch` and `create`.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// Empty for the core group.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub api_group: String,
+    pub api_version: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub subresource: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResponseStatus {
+    pub metadata: BTreeMap<String, String>,
+    pub code: u16,
+    /// `Failure` for errors; empty on success.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub status: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub reason: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub message: String,
+}
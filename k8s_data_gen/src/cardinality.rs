@@ -0,0 +1,339 @@
+//! High-cardinality stress labels (`--cardinality SPEC`).
+//!
+//! Adds extra labels to every metric and log record to reproduce cardinality
+//! explosions: a `user_id` or `request_path` label with 100k+ unique values.
+//! Each label has a fixed number of distinct values and a distribution the
+//! value of each record is drawn from.
+//!
+//! Spec syntax, one `NAME=VALUES[:DIST]` per label, comma-separated:
+//!
+//! ```text
+//! user_id=100000                          100k values, uniform
+//! user_id=100000:zipf                     Zipf (s = 1): a few hot values, a long tail
+//! request_path=500:zipf:1.3,tenant=20     two labels; Zipf exponent 1.3
+//! ```
+//!
+//! Values are `{name}-{rank}`, rank 1 being the most frequent under Zipf. The
+//! run estimates the distinct (pod, labels) sets it generates with HyperLogLog
+//! sketches and reports them as series counts: every minute in live runs, at
+//! the end of `historical`.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use rand::Rng;
+use rand_distr::{Distribution, Zipf};
+
+use crate::logs::K8sLogRecord;
+use crate::metrics::K8sMetricRecord;
+use crate::utils::{mix64, str_hash};
+
+/// Seconds between two reports in live runs.
+const REPORT_EVERY_SECS: u64 = 60;
+
+/// Bits of a hash that pick a [`Sketch`] register: 2^14 registers, about 0.8%
+/// standard error.
+const SKETCH_BITS: u32 = 14;
+
+/// Label names the exporters add next to the record fields (remote_write,
+/// scrape, kube-state-metrics style series).
+const SERIES_LABELS: &[&str] = &["__name__", "le", "deployment", "uid"];
+
+/// Names an extra label must not shadow: every top-level key of the metric
+/// and log records, plus [`SERIES_LABELS`].
+fn reserved() -> HashSet<String> {
+    let mut names: HashSet<String> = K8sMetricRecord::field_names().into_iter().collect();
+    names.extend(K8sLogRecord::field_names());
+    names.extend(SERIES_LABELS.iter().map(|n| n.to_string()));
+    names
+}
+
+#[derive(Clone, Copy)]
+pub enum Stream {
+    Metrics,
+    Logs,
+}
+
+enum Dist {
+    Uniform,
+    Zipf { s: f64, dist: Zipf<f64> },
+}
+
+struct Label {
+    name: String,
+    values: u64,
+    dist: Dist,
+}
+
+impl Label {
+    /// A rank in `1..=values`.
+    fn draw(&self, rng: &mut impl Rng) -> u64 {
+        match &self.dist {
+            Dist::Uniform => rng.gen_range(1..=self.values),
+            Dist::Zipf { dist, .. } => (dist.sample(rng) as u64).clamp(1, self.values),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self.dist {
+            Dist::Uniform => format!("{}={} (uniform)", self.name, self.values),
+            Dist::Zipf { s, .. } => format!("{}={} (zipf, s={})", self.name, self.values, s),
+        }
+    }
+}
+
+/// HyperLogLog estimate of the distinct hashes inserted. Registers are
+/// updated lock-free, so generator threads never wait on each other, and the
+/// size is fixed however many values a run produces.
+struct Sketch {
+    registers: Box<[AtomicU8]>,
+}
+
+impl Sketch {
+    fn new() -> Self {
+        Sketch {
+            registers: (0..1 << SKETCH_BITS).map(|_| AtomicU8::new(0)).collect(),
+        }
+    }
+
+    /// `hash` must be well mixed (`mix64`).
+    fn insert(&self, hash: u64) {
+        let register = &self.registers[(hash >> (64 - SKETCH_BITS)) as usize];
+        // Position of the first set bit after the index bits; the extra low
+        // bit caps it for an all-zero rest.
+        let rank = ((hash << SKETCH_BITS) | 1 << (SKETCH_BITS - 1)).leading_zeros() as u8 + 1;
+        // Most inserts change nothing; a plain load keeps the cache line shared.
+        if register.load(Ordering::Relaxed) < rank {
+            register.fetch_max(rank, Ordering::Relaxed);
+        }
+    }
+
+    fn estimate(&self) -> u64 {
+        let m = self.registers.len() as f64;
+        let (sum, zeros) = self.registers.iter().fold((0.0, 0u32), |(sum, zeros), r| {
+            let r = r.load(Ordering::Relaxed);
+            (sum + 2f64.powi(-(r as i32)), zeros + (r == 0) as u32)
+        });
+        let raw = 0.7213 / (1.0 + 1.079 / m) * m * m / sum;
+        // Small counts: linear counting over the empty registers.
+        let estimate = if raw <= 2.5 * m && zeros > 0 {
+            m * (m / zeros as f64).ln()
+        } else {
+            raw
+        };
+        estimate.round() as u64
+    }
+}
+
+/// What one stream generated so far.
+struct Seen {
+    /// Distinct (cluster, pod, labels) sets.
+    label_sets: Sketch,
+    /// Distinct ranks per label, in profile order.
+    values: Vec<Sketch>,
+}
+
+impl Seen {
+    fn new(labels: usize) -> Self {
+        Seen {
+            label_sets: Sketch::new(),
+            values: (0..labels).map(|_| Sketch::new()).collect(),
+        }
+    }
+}
+
+pub struct CardinalityProfile {
+    labels: Vec<Label>,
+    /// Indexed by `Stream`.
+    seen: [Seen; 2],
+}
+
+impl CardinalityProfile {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let bad = |why: String| format!("invalid cardinality '{}': {}", spec, why);
+        let reserved = reserved();
+        let mut labels: Vec<Label> = Vec::new();
+        for part in spec.split(',').map(str::trim) {
+            let (name, rest) = part
+                .split_once('=')
+                .ok_or_else(|| bad(format!("'{}' is not NAME=VALUES[:DIST]", part)))?;
+            let name = name.trim();
+            let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !valid {
+                return Err(bad(format!("'{}' is not a valid label name", name)));
+            }
+            if reserved.contains(name) || name.starts_with("__") {
+                return Err(bad(format!("label '{}' is reserved", name)));
+            }
+            if labels.iter().any(|l| l.name == name) {
+                return Err(bad(format!("label '{}' given twice", name)));
+            }
+
+            let mut fields = rest.split(':');
+            let values = match fields.next().map(|v| v.trim().parse::<u64>()) {
+                Some(Ok(v)) if v > 0 => v,
+                _ => return Err(bad(format!("'{}' needs a positive value count", name))),
+            };
+            let dist = match (fields.next(), fields.next()) {
+                (None | Some("uniform"), None) => Dist::Uniform,
+                (Some("zipf"), s) => {
+                    let s = match s.map(str::parse::<f64>) {
+                        None => 1.0,
+                        Some(Ok(s)) if s > 0.0 && s.is_finite() => s,
+                        Some(_) => {
+                            return Err(bad(format!("'{}': zipf exponent must be > 0", name)))
+                        }
+                    };
+                    let dist = Zipf::new(values, s).map_err(|e| bad(e.to_string()))?;
+                    Dist::Zipf { s, dist }
+                }
+                _ => return Err(bad(format!("'{}': use uniform, zipf or zipf:S", name))),
+            };
+            if fields.next().is_some() {
+                return Err(bad(format!("'{}' has trailing fields", part)));
+            }
+            labels.push(Label {
+                name: name.to_string(),
+                values,
+                dist,
+            });
+        }
+
+        let seen = std::array::from_fn(|_| Seen::new(labels.len()));
+        Ok(CardinalityProfile { labels, seen })
+    }
+
+    /// `user_id=100000 (zipf, s=1), tenant=20 (uniform)`.
+    pub fn describe(&self) -> String {
+        self.labels
+            .iter()
+            .map(Label::describe)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Label sets one pod can produce: the product of the value counts.
+    fn max_label_sets(&self) -> f64 {
+        self.labels.iter().map(|l| l.values as f64).product()
+    }
+
+    fn draw(
+        &self,
+        stream: Stream,
+        cluster: &str,
+        pod: &str,
+        rng: &mut impl Rng,
+    ) -> BTreeMap<String, String> {
+        let ranks: Vec<u64> = self.labels.iter().map(|l| l.draw(rng)).collect();
+        let key = ranks
+            .iter()
+            .fold(mix64(str_hash(cluster) ^ str_hash(pod)), |h, r| {
+                mix64(h.rotate_left(17) ^ r)
+            });
+
+        let seen = &self.seen[stream as usize];
+        seen.label_sets.insert(key);
+        for (values, r) in seen.values.iter().zip(&ranks) {
+            values.insert(mix64(*r));
+        }
+
+        self.labels
+            .iter()
+            .zip(ranks)
+            .map(|(l, r)| (l.name.clone(), format!("{}-{}", l.name, r)))
+            .collect()
+    }
+
+    fn report(&self) -> String {
+        let mut out = format!(
+            "Cardinality report ({}; up to {} label sets per pod):",
+            self.describe(),
+            self.max_label_sets()
+        );
+        for (stream, seen) in [
+            (Stream::Metrics, &self.seen[0]),
+            (Stream::Logs, &self.seen[1]),
+        ] {
+            let sets = seen.label_sets.estimate() as usize;
+            if sets == 0 {
+                continue;
+            }
+            match stream {
+                Stream::Metrics => {
+                    let families = crate::metrics::pod_family_count();
+                    out.push_str(&format!(
+                        "\n  metrics: ~{} (pod × labels) sets × {} families = ~{} series",
+                        sets,
+                        families,
+                        sets * families
+                    ));
+                }
+                Stream::Logs => {
+                    out.push_str(&format!("\n  logs: ~{} (pod × labels) sets", sets));
+                }
+            }
+            for (l, values) in self.labels.iter().zip(&seen.values) {
+                out.push_str(&format!(
+                    "\n    {}: ~{} of {} values seen",
+                    l.name,
+                    values.estimate().min(l.values),
+                    l.values
+                ));
+            }
+        }
+        out
+    }
+}
+
+static CARDINALITY: OnceLock<CardinalityProfile> = OnceLock::new();
+
+/// Install the `--cardinality` profile for this run; without one, records get
+/// no extra labels.
+pub fn init_cardinality(spec: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(spec) = spec else {
+        return Ok(());
+    };
+    let profile = CardinalityProfile::parse(spec)?;
+    println!("Cardinality: {}", profile.describe());
+    CARDINALITY
+        .set(profile)
+        .map_err(|_| "cardinality profile already initialised".into())
+}
+
+/// Extra labels for one record of `pod`; empty without a profile.
+pub fn extra_labels(
+    stream: Stream,
+    cluster: &str,
+    pod: &str,
+    rng: &mut impl Rng,
+) -> BTreeMap<String, String> {
+    match CARDINALITY.get() {
+        Some(p) => p.draw(stream, cluster, pod, rng),
+        None => BTreeMap::new(),
+    }
+}
+
+/// Prints the series counts generated so far, if a profile is installed.
+pub fn print_report() {
+    if let Some(p) = CARDINALITY.get() {
+        println!("\n{}", p.report());
+    }
+}
+
+/// Prints the report every `REPORT_EVERY_SECS` for the rest of a live run.
+pub fn spawn_live_report() {
+    if CARDINALITY.get().is_none() {
+        return;
+    }
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(Duration::from_secs(REPORT_EVERY_SECS));
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            print_report();
+        }
+    });
+}
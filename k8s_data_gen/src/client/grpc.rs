@@ -1,15 +1,21 @@
 use base64::Engine as _;
+use chrono::Utc;
 use opentelemetry_proto::tonic::{
-    collector::trace::v1::{trace_service_client::TraceServiceClient, ExportTraceServiceRequest},
+    collector::{
+        logs::v1::{logs_service_client::LogsServiceClient, ExportLogsServiceRequest},
+        metrics::v1::{metrics_service_client::MetricsServiceClient, ExportMetricsServiceRequest},
+        trace::v1::{trace_service_client::TraceServiceClient, ExportTraceServiceRequest},
+    },
+    logs::v1::ResourceLogs,
+    metrics::v1::ResourceMetrics,
     trace::v1::ResourceSpans,
 };
 use tonic::transport::Channel;
 
+use crate::anomaly::AnomalyState;
 use crate::config::{password, username};
 
-pub async fn grpc_client(
-    endpoint: &str,
-) -> Result<TraceServiceClient<Channel>, Box<dyn std::error::Error>> {
+async fn connect(endpoint: &str) -> Result<Channel, Box<dyn std::error::Error>> {
     let tls = tonic::transport::ClientTlsConfig::new().with_webpki_roots();
     let ch = Channel::from_shared(endpoint.to_string())
         .map_err(|e| format!("invalid endpoint '{}': {}", endpoint, e))?
@@ -18,24 +24,133 @@ pub async fn grpc_client(
         .connect()
         .await
         .map_err(|e| format!("connect to '{}' failed: {}", endpoint, e))?;
-    Ok(TraceServiceClient::new(ch))
+    Ok(ch)
 }
 
-pub async fn send_grpc_traces(
-    client: &mut TraceServiceClient<Channel>,
-    resource_spans: Vec<ResourceSpans>,
+pub async fn grpc_client(
+    endpoint: &str,
+) -> Result<TraceServiceClient<Channel>, Box<dyn std::error::Error>> {
+    Ok(TraceServiceClient::new(connect(endpoint).await?))
+}
+
+pub async fn grpc_metrics_client(
+    endpoint: &str,
+) -> Result<MetricsServiceClient<Channel>, Box<dyn std::error::Error>> {
+    Ok(MetricsServiceClient::new(connect(endpoint).await?))
+}
+
+pub async fn grpc_logs_client(
+    endpoint: &str,
+) -> Result<LogsServiceClient<Channel>, Box<dyn std::error::Error>> {
+    Ok(LogsServiceClient::new(connect(endpoint).await?))
+}
+
+/// Wraps `msg` with the organization/authorization/stream-name metadata every
+/// export needs. `stream_name` is omitted when the server should pick it
+/// (metrics are routed by metric name).
+fn request<T>(
+    msg: T,
     org: &str,
-    stream_name: &str,
-) -> Result<(), Box<dyn std::error::Error>> {
+    stream_name: Option<&str>,
+) -> Result<tonic::Request<T>, Box<dyn std::error::Error>> {
     let auth = format!(
         "Basic {}",
         base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", username(), password()))
     );
-    let mut req = tonic::Request::new(ExportTraceServiceRequest { resource_spans });
+    let mut req = tonic::Request::new(msg);
     let md = req.metadata_mut();
     md.insert("organization", org.parse()?);
     md.insert("authorization", auth.parse()?);
-    md.insert("stream-name", stream_name.parse()?);
+    if let Some(stream_name) = stream_name {
+        md.insert("stream-name", stream_name.parse()?);
+    }
+    Ok(req)
+}
+
+pub async fn send_grpc_traces(
+    client: &mut TraceServiceClient<Channel>,
+    resource_spans: Vec<ResourceSpans>,
+    org: &str,
+    stream_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let req = request(
+        ExportTraceServiceRequest { resource_spans },
+        org,
+        Some(stream_name),
+    )?;
     client.export(req).await?;
     Ok(())
 }
+
+pub async fn send_grpc_metrics(
+    client: &mut MetricsServiceClient<Channel>,
+    resource_metrics: Vec<ResourceMetrics>,
+    org: &str,
+    stream_name: Option<&str>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let req = request(
+        ExportMetricsServiceRequest { resource_metrics },
+        org,
+        stream_name,
+    )?;
+    client.export(req).await?;
+    Ok(())
+}
+
+pub async fn send_grpc_logs(
+    client: &mut LogsServiceClient<Channel>,
+    resource_logs: Vec<ResourceLogs>,
+    org: &str,
+    stream_name: &str,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let req = request(
+        ExportLogsServiceRequest { resource_logs },
+        org,
+        Some(stream_name),
+    )?;
+    client.export(req).await?;
+    Ok(())
+}
+
+/// Prints the live-mode progress line for one export. Returns `false` on
+/// failure so the caller can reconnect.
+pub fn report_export(
+    result: Result<(), Box<dyn std::error::Error>>,
+    count: usize,
+    unit: &str,
+    anomaly_state: &Option<AnomalyState>,
+) -> bool {
+    match result {
+        Ok(_) => {
+            let anomaly_active = anomaly_state
+                .as_ref()
+                .map(|a| a.is_active())
+                .unwrap_or(false);
+            let anomaly_remaining = anomaly_state
+                .as_ref()
+                .map(|a| a.remaining_secs)
+                .unwrap_or(0);
+            let suffix = if anomaly_active {
+                format!(" [ANOMALY ACTIVE: {}s remaining]", anomaly_remaining)
+            } else {
+                String::new()
+            };
+            println!(
+                "[{}] ✓ {} {} (gRPC){}",
+                Utc::now().format("%Y-%m-%d %H:%M:%S"),
+                count,
+                unit,
+                suffix
+            );
+            true
+        }
+        Err(e) => {
+            eprintln!(
+                "[{}] ✗ gRPC error: {}",
+                Utc::now().format("%Y-%m-%d %H:%M:%S"),
+                e
+            );
+            false
+        }
+    }
+}
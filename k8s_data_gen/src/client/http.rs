@@ -1,10 +1,11 @@
-use std::{fmt, fs::File, io::BufReader};
+use std::fmt;
 
 use chrono::Utc;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 
 use crate::anomaly::AnomalyState;
 use crate::config::{password, username};
+use crate::output::{open_input, OutputFormat};
 
 pub async fn post_live<T: serde::Serialize>(
     client: &Client,
@@ -62,6 +63,63 @@ pub async fn post_otlp(
     body: &serde_json::Value,
     record_count: usize,
     anomaly_state: &Option<AnomalyState>,
+) {
+    let builder = otlp_request(client, url, stream_name).json(body);
+    send_reporting(builder, record_count, anomaly_state).await;
+}
+
+/// [`post_otlp`] with a protobuf-encoded `Export*ServiceRequest` body.
+pub async fn post_otlp_protobuf(
+    client: &Client,
+    url: &str,
+    stream_name: Option<&str>,
+    body: Vec<u8>,
+    record_count: usize,
+    anomaly_state: &Option<AnomalyState>,
+) {
+    let builder = otlp_request(client, url, stream_name)
+        .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+        .body(body);
+    send_reporting(builder, record_count, anomaly_state).await;
+}
+
+/// Prometheus remote_write 1.0 request; `body` is a snappy-compressed
+/// `WriteRequest`.
+pub fn remote_write_request(client: &Client, url: &str, body: Vec<u8>) -> RequestBuilder {
+    client
+        .post(url)
+        .basic_auth(username(), Some(password()))
+        .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+        .header(reqwest::header::CONTENT_ENCODING, "snappy")
+        .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+        .body(body)
+}
+
+/// [`post_otlp`] for a remote_write body.
+pub async fn post_remote_write(
+    client: &Client,
+    url: &str,
+    body: Vec<u8>,
+    record_count: usize,
+    anomaly_state: &Option<AnomalyState>,
+) {
+    let builder = remote_write_request(client, url, body);
+    send_reporting(builder, record_count, anomaly_state).await;
+}
+
+fn otlp_request(client: &Client, url: &str, stream_name: Option<&str>) -> RequestBuilder {
+    let builder = client.post(url).basic_auth(username(), Some(password()));
+    match stream_name {
+        Some(name) => builder.header("stream-name", name),
+        None => builder,
+    }
+}
+
+/// Sends a live-mode request and prints the progress line.
+async fn send_reporting(
+    builder: RequestBuilder,
+    record_count: usize,
+    anomaly_state: &Option<AnomalyState>,
 ) {
     let anomaly_active = anomaly_state
         .as_ref()
@@ -72,15 +130,6 @@ pub async fn post_otlp(
         .map(|a| a.remaining_secs)
         .unwrap_or(0);
 
-    let builder = client
-        .post(url)
-        .basic_auth(username(), Some(password()))
-        .json(body);
-    let builder = match stream_name {
-        Some(name) => builder.header("stream-name", name),
-        None => builder,
-    };
-
     match builder.send().await {
         Ok(resp) if resp.status().is_success() => {
             let suffix = if anomaly_active {
@@ -109,8 +158,34 @@ pub async fn post_otlp(
     }
 }
 
-/// Stream a JSON array file element-by-element in a blocking thread, sending
+/// POST one batch, failing on a non-2xx response. For bulk paths (backfill)
+/// where a rejected batch should stop the run rather than be logged and skipped.
+pub async fn post_batch(
+    client: &Client,
+    url: &str,
+    stream_name: Option<&str>,
+    body: &serde_json::Value,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let builder = client
+        .post(url)
+        .basic_auth(username(), Some(password()))
+        .json(body);
+    let builder = match stream_name {
+        Some(name) => builder.header("stream-name", name),
+        None => builder,
+    };
+    let resp = builder.send().await?;
+    let status = resp.status();
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("HTTP {} from {}: {}", status, url, text).into());
+    }
+    Ok(())
+}
+
+/// Stream a historical file element-by-element in a blocking thread, sending
 /// batches through `tx`. Only O(batch_size) records are in memory at once.
+/// Reads JSON arrays and NDJSON, plain or gzip/zstd compressed (auto-detected).
 pub fn stream_json_array<T>(
     file_path: String,
     batch_size: usize,
@@ -152,13 +227,30 @@ where
             }
         }
 
-        let reader = BufReader::new(File::open(&file_path).map_err(|e| e.to_string())?);
+        let (reader, format) =
+            open_input(&file_path).map_err(|e| format!("{}: {}", file_path, e))?;
         let mut de = serde_json::Deserializer::from_reader(reader);
-        de.deserialize_seq(Batcher::<T> {
+        let batcher = Batcher::<T> {
             tx,
             buf: Vec::with_capacity(batch_size),
             cap: batch_size,
-        })
-        .map_err(|e| e.to_string())
+        };
+        match format {
+            OutputFormat::Json => de.deserialize_seq(batcher).map_err(|e| e.to_string()),
+            OutputFormat::Ndjson => {
+                let Batcher { tx, mut buf, cap } = batcher;
+                for v in de.into_iter::<T>() {
+                    buf.push(v.map_err(|e| e.to_string())?);
+                    if buf.len() >= cap {
+                        let batch = std::mem::replace(&mut buf, Vec::with_capacity(cap));
+                        tx.blocking_send(batch).map_err(|e| e.to_string())?;
+                    }
+                }
+                if !buf.is_empty() {
+                    tx.blocking_send(buf).map_err(|e| e.to_string())?;
+                }
+                Ok(())
+            }
+        }
     })
 }
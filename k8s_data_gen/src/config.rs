@@ -1,29 +1,130 @@
+use std::sync::OnceLock;
+
+use o2gen_profile::{load_profile, resolve, Profile};
+
 pub const DEFAULT_API_BASE: &str = "http://localhost:5080";
 pub const DEFAULT_GRPC_ENDPOINT: &str = "http://localhost:5081";
 pub const DEFAULT_ORG: &str = "default";
 pub const DEFAULT_STREAM_LOGS: &str = "k8s_logs";
 pub const DEFAULT_STREAM_METRICS: &str = "k8s_metrics";
 pub const DEFAULT_STREAM_TRACES: &str = "k8s_traces_grpc";
+pub const DEFAULT_STREAM_EVENTS: &str = "k8s_events";
+pub const DEFAULT_STREAM_AUDIT: &str = "k8s_audit";
+/// `ingest --stream` value for trace files; the spans go to `stream_traces()`.
+pub const INGEST_STREAM_TRACES: &str = "k8s_traces";
 pub const USERNAME: &str = "root@example.com";
 pub const PASSWORD: &str = "Complexpass#123";
 pub const INTERVAL_SECONDS: i64 = 10;
-pub const PODS_PER_TICK: usize = 10;
-pub const CHUNK_SIZE: usize = 5_000;
+/// Historical/backfill traces: `TRACES_PER_INTERVAL` per deployment every interval.
+pub const TRACE_INTERVAL_SECONDS: i64 = 30;
+pub const TRACES_PER_INTERVAL: usize = 5;
+/// Default `historical` output files (override with `--out`).
+pub const HISTORICAL_OUT_LOGS: &str = "../output_k8s.json";
+pub const HISTORICAL_OUT_METRICS: &str = "../output_k8s_metrics.json";
+pub const HISTORICAL_OUT_TRACES: &str = "../output_k8s_traces.json";
+pub const HISTORICAL_OUT_AUDIT: &str = "../output_k8s_audit.json";
 pub const INGEST_BATCH_SIZE: usize = 2_000;
 pub const NODE_MEMORY_MB: f64 = 4096.0;
+/// Node size for the `--kube-metrics` node families.
+pub const NODE_CPU_CORES: u32 = 4;
+/// `--trace-logs`: database spans slower than this log a slow-query warning
+/// (healthy queries stay well below; `--anomaly latency` crosses it).
+pub const SLOW_QUERY_MS: f64 = 100.0;
+/// API requests per cluster per second in `historical` and `backfill` audit
+/// logs (each logged at two or three stages).
+pub const AUDIT_REQUESTS_PER_SEC: f64 = 2.0;
+
+/// Pod churn is drawn per window of this length (see `lifecycle.rs`).
+pub const LIFECYCLE_WINDOW_SECS: i64 = 3600;
+/// ~one rollout per service every 3 days.
+pub const ROLLOUT_PROB_PER_WINDOW: f64 = 1.0 / 72.0;
+/// ~one reschedule per replica every 2 days.
+pub const RESCHEDULE_PROB_PER_WINDOW: f64 = 1.0 / 48.0;
+/// Delay between consecutive replicas switching to a new ReplicaSet.
+pub const ROLLOUT_STEP_SECS: i64 = 40;
+/// Bounded search for the last churn event (60 days).
+pub const LIFECYCLE_LOOKBACK_WINDOWS: usize = 24 * 60;
+
+static PROFILE: OnceLock<Profile> = OnceLock::new();
+
+/// Select the connection profile for this run (see the `o2gen_profile`
+/// crate). Must be called before the first endpoint/credential lookup.
+pub fn init_profile(name: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    PROFILE
+        .set(load_profile(name)?)
+        .map_err(|_| "profile already initialised".into())
+}
+
+fn profile() -> &'static Profile {
+    PROFILE.get_or_init(Profile::default)
+}
 
 pub fn api_base() -> String {
-    std::env::var("O2_API_BASE").unwrap_or_else(|_| DEFAULT_API_BASE.to_string())
+    resolve(
+        "O2_API_BASE",
+        profile().endpoint.as_deref(),
+        DEFAULT_API_BASE,
+    )
 }
 
 pub fn grpc_endpoint() -> String {
-    std::env::var("O2_GRPC_ENDPOINT").unwrap_or_else(|_| DEFAULT_GRPC_ENDPOINT.to_string())
+    resolve(
+        "O2_GRPC_ENDPOINT",
+        profile().grpc_endpoint.as_deref(),
+        DEFAULT_GRPC_ENDPOINT,
+    )
+}
+
+pub fn org() -> String {
+    resolve("O2_ORG", profile().org.as_deref(), DEFAULT_ORG)
 }
 
 pub fn username() -> String {
-    std::env::var("O2_USERNAME").unwrap_or_else(|_| USERNAME.to_string())
+    resolve("O2_USERNAME", profile().username.as_deref(), USERNAME)
 }
 
 pub fn password() -> String {
-    std::env::var("O2_PASSWORD").unwrap_or_else(|_| PASSWORD.to_string())
+    resolve("O2_PASSWORD", profile().password.as_deref(), PASSWORD)
+}
+
+pub fn stream_logs() -> String {
+    resolve(
+        "O2_STREAM_LOGS",
+        profile().stream("logs"),
+        DEFAULT_STREAM_LOGS,
+    )
+}
+
+/// `ingest --stream` value that sends a file to the metrics exporters (OTLP
+/// metrics land in per-field streams, not in a stream of this name).
+pub fn stream_metrics() -> String {
+    resolve(
+        "O2_STREAM_METRICS",
+        profile().stream("metrics"),
+        DEFAULT_STREAM_METRICS,
+    )
+}
+
+pub fn stream_traces() -> String {
+    resolve(
+        "O2_STREAM_TRACES",
+        profile().stream("traces"),
+        DEFAULT_STREAM_TRACES,
+    )
+}
+
+pub fn stream_events() -> String {
+    resolve(
+        "O2_STREAM_EVENTS",
+        profile().stream("events"),
+        DEFAULT_STREAM_EVENTS,
+    )
+}
+
+pub fn stream_audit() -> String {
+    resolve(
+        "O2_STREAM_AUDIT",
+        profile().stream("audit"),
+        DEFAULT_STREAM_AUDIT,
+    )
 }
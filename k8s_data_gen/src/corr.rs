@@ -20,7 +20,7 @@ use chrono::Utc;
 use reqwest::Client;
 use serde::Serialize;
 
-use crate::config::{api_base, password, username, DEFAULT_ORG};
+use crate::config::{api_base, org, password, username};
 
 const LOG_STREAM: &str = "corr_logs";
 const METRIC_STREAM: &str = "corr_metrics";
@@ -67,7 +67,7 @@ async fn send_logs(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
         let url = format!(
             "{}/api/{}/{}/_json",
             api_base(),
-            DEFAULT_ORG,
+            org(),
             LOG_STREAM
         );
         let now = Utc::now().timestamp_micros();
@@ -110,7 +110,7 @@ async fn send_logs(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
 // ── Metric records ────────────────────────────────────────────────────────────
 
 async fn send_metrics(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
-    let url = format!("{}/api/{}/v1/metrics", api_base(), DEFAULT_ORG);
+    let url = format!("{}/api/{}/v1/metrics", api_base(), org());
 
     for svc in SERVICES {
         let now_ns = Utc::now().timestamp_nanos_opt().unwrap_or(0);
@@ -180,7 +180,7 @@ async fn send_metrics(client: &Client) -> Result<(), Box<dyn std::error::Error>>
 // ── Trace spans ───────────────────────────────────────────────────────────────
 
 async fn send_traces(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
-    let url = format!("{}/api/{}/v1/traces", api_base(), DEFAULT_ORG);
+    let url = format!("{}/api/{}/v1/traces", api_base(), org());
 
     for svc in SERVICES {
         let now_ns = Utc::now().timestamp_nanos_opt().unwrap_or(0);
@@ -0,0 +1,508 @@
+//! Kubernetes Events (`--k8s-events`).
+//!
+//! `core/v1` Events the way a Kubernetes event exporter ships them
+//! (`involvedObject`, `reason`, `message`, `type`, `source`, `count`,
+//! `firstTimestamp`/`lastTimestamp`), for their own stream (`k8s_events`).
+//! Metrics runs derive them from the same pod churn and metric records they
+//! emit, so the events agree with the numbers:
+//!
+//!   - a new pod (rollout or reschedule, `lifecycle.rs`): now and then
+//!     `FailedScheduling` first, then `Scheduled`, `Pulled`, `Created` and
+//!     `Started`; the pod it replaces gets `Killing`
+//!   - a record with restarts (the `restarts` anomaly's crash loop):
+//!     `Pulled`, `Created` and `Started` again, and `BackOff`
+//!   - a working set over the memory limit (2 × the service's base, as in
+//!     `--kube-metrics`; the `memory` anomaly): `OOMKilling` on the node
+//!   - latency over the readiness probe's timeout or an error rate high enough
+//!     to fail it (the `latency` and `errors` anomalies): `Unhealthy`
+//!
+//! Repeats of an event (same object and reason) update the one event, raising
+//! `count` and `lastTimestamp` as the API server's event aggregation does; the
+//! exporter ships each update. Live runs and `run`'s backfill keep that state
+//! for the whole run. `historical` generates intervals out of order, so there
+//! counts start over every interval.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use chrono::{DateTime, SecondsFormat};
+use reqwest::Client;
+use serde::Serialize;
+
+use crate::anomaly::AnomalyState;
+use crate::client::http::post_live;
+use crate::config::{api_base, org, stream_events, NODE_MEMORY_MB};
+use crate::lifecycle::{pods_at, PodRef};
+use crate::metrics::kube::{node_uid, pod_image, pod_ip, pod_uid, uuid_from};
+use crate::metrics::K8sMetricRecord;
+use crate::topology::topology;
+use crate::utils::{mix64, str_hash};
+
+/// kube-apiserver's default `--event-ttl`: an event not repeated for this long
+/// is gone, and the next occurrence starts a new one.
+const EVENT_TTL_SECS: i64 = 3600;
+/// A repeated event is shipped at most this often (the kubelet's probe period).
+const UPDATE_EVERY_SECS: i64 = 10;
+/// Readiness probes time out after this long.
+const PROBE_TIMEOUT_MS: f64 = 1000.0;
+/// Error rate above which the readiness endpoint answers 503.
+const PROBE_ERROR_RATE: f64 = 0.20;
+/// Share of new pods that wait for a node first.
+const FAILED_SCHEDULING_PROB: f64 = 0.15;
+
+static K8S_EVENTS: OnceLock<bool> = OnceLock::new();
+
+/// Turn Kubernetes Events on or off for this run (`--k8s-events`).
+pub fn init_k8s_events(enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+    K8S_EVENTS
+        .set(enabled)
+        .map_err(|_| "k8s events already initialised".into())
+}
+
+pub fn k8s_events_enabled() -> bool {
+    *K8S_EVENTS.get_or_init(|| false)
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InvolvedObject {
+    pub kind: String,
+    /// Empty for cluster-scoped objects (nodes).
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub namespace: String,
+    pub name: String,
+    pub uid: String,
+    pub api_version: String,
+    /// `spec.containers{NAME}` for events about one container.
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub field_path: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventMetadata {
+    pub name: String,
+    pub namespace: String,
+    pub uid: String,
+    pub creation_timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct EventSource {
+    pub component: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct K8sEventRecord {
+    /// `lastTimestamp`, in µs.
+    #[serde(rename = "_timestamp")]
+    pub _timestamp: i64,
+    pub cluster: String,
+    pub metadata: EventMetadata,
+    pub involved_object: InvolvedObject,
+    pub reason: String,
+    pub message: String,
+    /// `Normal` or `Warning`.
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub source: EventSource,
+    pub first_timestamp: String,
+    pub last_timestamp: String,
+    pub count: u32,
+    pub reporting_component: String,
+    pub reporting_instance: String,
+}
+
+/// One occurrence of an event, before aggregation.
+struct Occurrence<'a> {
+    ts_us: i64,
+    cluster: &'a str,
+    object: InvolvedObject,
+    component: &'static str,
+    host: &'a str,
+    warning: bool,
+    reason: &'static str,
+    message: String,
+}
+
+/// One aggregated event.
+struct Series {
+    name: String,
+    uid: String,
+    first_us: i64,
+    last_us: i64,
+    /// When the last update went out; `None` before the first.
+    shipped_us: Option<i64>,
+    count: u32,
+}
+
+impl Series {
+    fn start(o: &Occurrence) -> Self {
+        // Kubernetes names events `<object>.<creation time in ns, hex>`.
+        let name = format!("{}.{:x}", o.object.name, o.ts_us * 1000);
+        Series {
+            uid: uuid_from(str_hash(o.cluster) ^ str_hash(&name)),
+            name,
+            first_us: o.ts_us,
+            last_us: o.ts_us,
+            shipped_us: None,
+            count: 0,
+        }
+    }
+}
+
+fn rfc3339(ts_us: i64) -> String {
+    DateTime::from_timestamp_micros(ts_us)
+        .unwrap_or_default()
+        .to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn pod_object(cluster: &str, namespace: &str, pod: &str, container: &str) -> InvolvedObject {
+    InvolvedObject {
+        kind: "Pod".to_string(),
+        namespace: namespace.to_string(),
+        name: pod.to_string(),
+        uid: pod_uid(cluster, pod),
+        api_version: "v1".to_string(),
+        field_path: if container.is_empty() {
+            String::new()
+        } else {
+            format!("spec.containers{{{}}}", container)
+        },
+    }
+}
+
+fn node_object(cluster: &str, node: &str) -> InvolvedObject {
+    InvolvedObject {
+        kind: "Node".to_string(),
+        namespace: String::new(),
+        name: node.to_string(),
+        uid: node_uid(cluster, node),
+        api_version: "v1".to_string(),
+        field_path: String::new(),
+    }
+}
+
+/// Aggregates event occurrences into events. Keep one per run.
+#[derive(Default)]
+pub struct EventRecorder {
+    /// Keyed by (involved object UID, reason).
+    series: HashMap<(String, &'static str), Series>,
+}
+
+impl EventRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Events between `prev_us` and `now_us`: churn of the pods `pods_at`
+    /// runs, and what `records` (metric records at `now_us`) show.
+    pub fn events(
+        &mut self,
+        prev_us: i64,
+        now_us: i64,
+        seed: u64,
+        records: &[K8sMetricRecord],
+    ) -> Vec<K8sEventRecord> {
+        let mut out = Vec::new();
+        if prev_us < now_us {
+            // Same topology, so both lists hold the same replicas in order.
+            for (old, new) in pods_at(prev_us, seed).iter().zip(&pods_at(now_us, seed)) {
+                if old.name != new.name {
+                    self.churn(old, new, now_us, &mut out);
+                }
+            }
+        }
+        for r in records {
+            self.pod_state(r, &mut out);
+        }
+        let ttl_us = EVENT_TTL_SECS * 1_000_000;
+        self.series.retain(|_, s| now_us - s.last_us < ttl_us);
+        out.sort_by_key(|e| e._timestamp);
+        out
+    }
+
+    /// Counts `times` occurrences; returns the update the exporter ships, or
+    /// `None` if the event was shipped less than `UPDATE_EVERY_SECS` ago.
+    fn record(&mut self, o: Occurrence, times: u32) -> Option<K8sEventRecord> {
+        let ttl_us = EVENT_TTL_SECS * 1_000_000;
+        let s = match self.series.entry((o.object.uid.clone(), o.reason)) {
+            Entry::Occupied(e) if o.ts_us - e.get().last_us < ttl_us => e.into_mut(),
+            Entry::Occupied(mut e) => {
+                e.insert(Series::start(&o));
+                e.into_mut()
+            }
+            Entry::Vacant(e) => e.insert(Series::start(&o)),
+        };
+        s.count += times;
+        s.last_us = s.last_us.max(o.ts_us);
+        if s.shipped_us
+            .is_some_and(|t| o.ts_us - t < UPDATE_EVERY_SECS * 1_000_000)
+        {
+            return None;
+        }
+        s.shipped_us = Some(o.ts_us);
+
+        let namespace = match o.object.namespace.as_str() {
+            "" => "default".to_string(),
+            ns => ns.to_string(),
+        };
+        Some(K8sEventRecord {
+            _timestamp: s.last_us,
+            cluster: o.cluster.to_string(),
+            metadata: EventMetadata {
+                name: s.name.clone(),
+                namespace,
+                uid: s.uid.clone(),
+                creation_timestamp: rfc3339(s.first_us),
+            },
+            involved_object: o.object,
+            reason: o.reason.to_string(),
+            message: o.message,
+            event_type: if o.warning { "Warning" } else { "Normal" }.to_string(),
+            source: EventSource {
+                component: o.component.to_string(),
+                host: o.host.to_string(),
+            },
+            first_timestamp: rfc3339(s.first_us),
+            last_timestamp: rfc3339(s.last_us),
+            count: s.count,
+            reporting_component: o.component.to_string(),
+            reporting_instance: o.host.to_string(),
+        })
+    }
+
+    /// `new` replaced `old` (same replica) at `new.started_secs`.
+    fn churn(&mut self, old: &PodRef, new: &PodRef, now_us: i64, out: &mut Vec<K8sEventRecord>) {
+        let spec = &topology().pods[new.svc_idx];
+        let cluster = new.cluster;
+        let start_us = new.started_secs * 1_000_000;
+        let h = mix64(str_hash(cluster) ^ str_hash(&new.name));
+        let pod = |container: &str| pod_object(cluster, spec.namespace, &new.name, container);
+        let scheduler = |ts_us: i64, warning, reason, message| Occurrence {
+            ts_us,
+            cluster,
+            object: pod(""),
+            component: "default-scheduler",
+            host: "",
+            warning,
+            reason,
+            message,
+        };
+        let kubelet = |ts_us: i64, pod_name: &str, reason, message| Occurrence {
+            ts_us: ts_us.min(now_us),
+            cluster,
+            object: pod_object(cluster, spec.namespace, pod_name, spec.container),
+            component: "kubelet",
+            host: new.node,
+            warning: false,
+            reason,
+            message,
+        };
+
+        let mut occurrences = Vec::new();
+        if (h >> 11) as f64 / (1u64 << 53) as f64 <= FAILED_SCHEDULING_PROB {
+            let nodes = topology().clusters[new.cluster_idx].nodes.len();
+            let resource = if h & 1 == 0 { "cpu" } else { "memory" };
+            for attempt in (1..=1 + (h >> 1) % 3).rev() {
+                occurrences.push(scheduler(
+                    start_us - attempt as i64 * UPDATE_EVERY_SECS * 1_000_000,
+                    true,
+                    "FailedScheduling",
+                    format!(
+                        "0/{n} nodes are available: {n} Insufficient {r}. preemption: 0/{n} nodes are available: {n} No preemption victims found for incoming pod.",
+                        n = nodes,
+                        r = resource
+                    ),
+                ));
+            }
+        }
+        occurrences.push(scheduler(
+            start_us,
+            false,
+            "Scheduled",
+            format!(
+                "Successfully assigned {}/{} to {}",
+                spec.namespace, new.name, new.node
+            ),
+        ));
+        let pull_us = 300_000 + ((h >> 16) % 4_000_000) as i64;
+        let pulled_us = start_us + pull_us;
+        occurrences.push(kubelet(
+            pulled_us,
+            &new.name,
+            "Pulled",
+            format!(
+                "Successfully pulled image \"{}\" in {:.3}s ({:.3}s including waiting). Image size: {} bytes.",
+                pod_image(spec.namespace, spec.service, &new.name),
+                pull_us as f64 / 1e6,
+                pull_us as f64 / 1e6,
+                40_000_000 + (h >> 24) % 200_000_000
+            ),
+        ));
+        occurrences.push(kubelet(
+            pulled_us + 40_000,
+            &new.name,
+            "Created",
+            format!("Created container {}", spec.container),
+        ));
+        occurrences.push(kubelet(
+            pulled_us + 120_000,
+            &new.name,
+            "Started",
+            format!("Started container {}", spec.container),
+        ));
+        // The old pod stops once its replacement is up.
+        let mut killing = kubelet(
+            pulled_us + 1_000_000,
+            &old.name,
+            "Killing",
+            format!("Stopping container {}", spec.container),
+        );
+        killing.host = old.node;
+        occurrences.push(killing);
+
+        out.extend(occurrences.into_iter().filter_map(|o| self.record(o, 1)));
+    }
+
+    /// Restarts, OOM kills and failing probes one metric record shows.
+    fn pod_state(&mut self, r: &K8sMetricRecord, out: &mut Vec<K8sEventRecord>) {
+        let Some(spec) = topology().pods.iter().find(|p| p.service == r.service) else {
+            return;
+        };
+        let container = spec.container;
+        let kubelet = |warning, reason, message| Occurrence {
+            ts_us: r._timestamp,
+            cluster: &r.cluster,
+            object: pod_object(&r.cluster, &r.namespace, &r.pod, container),
+            component: "kubelet",
+            host: &r.node,
+            warning,
+            reason,
+            message,
+        };
+
+        let mut occurrences = Vec::new();
+        if r.restarts > 0 {
+            let image = pod_image(&r.namespace, &r.service, &r.pod);
+            occurrences.push(kubelet(
+                false,
+                "Pulled",
+                format!("Container image \"{}\" already present on machine", image),
+            ));
+            occurrences.push(kubelet(
+                false,
+                "Created",
+                format!("Created container {}", container),
+            ));
+            occurrences.push(kubelet(
+                false,
+                "Started",
+                format!("Started container {}", container),
+            ));
+            occurrences.push(kubelet(
+                true,
+                "BackOff",
+                format!(
+                    "Back-off restarting failed container {} in pod {}_{}({})",
+                    container,
+                    r.pod,
+                    r.namespace,
+                    pod_uid(&r.cluster, &r.pod)
+                ),
+            ));
+        }
+        for o in occurrences {
+            out.extend(self.record(o, r.restarts));
+        }
+
+        if r.memory_mb as f64 > spec.base_mem as f64 * 2.0 {
+            let h = mix64(str_hash(&r.pod) ^ r._timestamp as u64);
+            let rss_kb = r.memory_mb as u64 * 1024;
+            let oom_score_adj =
+                (1000.0 - 1000.0 * spec.base_mem as f64 / NODE_MEMORY_MB).clamp(2.0, 999.0);
+            let o = Occurrence {
+                ts_us: r._timestamp,
+                cluster: &r.cluster,
+                object: node_object(&r.cluster, &r.node),
+                component: "kernel-monitor",
+                host: &r.node,
+                warning: true,
+                reason: "OOMKilling",
+                message: format!(
+                    "Memory cgroup out of memory: Killed process {} ({}) total-vm:{}kB, anon-rss:{}kB, file-rss:0kB, shmem-rss:0kB, UID:0 pgtables:{}kB oom_score_adj:{}",
+                    1000 + h % 60_000,
+                    container,
+                    rss_kb * 3 / 2,
+                    rss_kb,
+                    rss_kb / 500,
+                    oom_score_adj as u32
+                ),
+            };
+            out.extend(self.record(o, 1));
+        }
+
+        let probe_failure = if r.request_latency_ms > PROBE_TIMEOUT_MS {
+            Some(format!(
+                "Readiness probe failed: Get \"http://{}:8080/healthz\": context deadline exceeded (Client.Timeout exceeded while awaiting headers)",
+                pod_ip(&r.cluster, &r.pod)
+            ))
+        } else if r.error_rate > PROBE_ERROR_RATE {
+            Some("Readiness probe failed: HTTP probe failed with statuscode: 503".to_string())
+        } else {
+            None
+        };
+        if let Some(message) = probe_failure {
+            out.extend(self.record(kubelet(true, "Unhealthy", message), 1));
+        }
+    }
+}
+
+/// `--k8s-events` for a live metrics loop: each tick's events, posted to the
+/// events stream.
+pub struct LiveEvents {
+    client: Client,
+    url: String,
+    seed: u64,
+    recorder: EventRecorder,
+    prev_us: Option<i64>,
+}
+
+impl LiveEvents {
+    /// `None` unless `--k8s-events` is on.
+    pub fn new(seed: u64) -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        if !k8s_events_enabled() {
+            return Ok(None);
+        }
+        let url = format!("{}/api/{}/{}/_json", api_base(), org(), stream_events());
+        println!("Kubernetes events → {}", url);
+        Ok(Some(LiveEvents {
+            client: Client::builder()
+                .danger_accept_invalid_certs(true)
+                .build()?,
+            url,
+            seed,
+            recorder: EventRecorder::new(),
+            prev_us: None,
+        }))
+    }
+
+    /// Posts the events of the tick at `now_us`, whose metric records are
+    /// `records`.
+    pub async fn post(
+        &mut self,
+        now_us: i64,
+        records: &[K8sMetricRecord],
+        anomaly_state: &Option<AnomalyState>,
+    ) {
+        let prev_us = self.prev_us.replace(now_us).unwrap_or(now_us);
+        let events = self.recorder.events(prev_us, now_us, self.seed, records);
+        if !events.is_empty() {
+            post_live(&self.client, &self.url, &events, anomaly_state).await;
+        }
+    }
+}
@@ -22,6 +22,8 @@
 //!   cargo run --bin histogram_edge_sim -- full
 //!       generate + ingest in one shot
 //!
+//!   Add `--profile NAME` to ingest into a target from ~/.config/o2gen/profiles.toml.
+//!
 //! HOW TO REPRODUCE IN OPENOBSERVE:
 //!   1. cargo run --bin histogram_edge_sim -- full
 //!   2. Open dashboard, create a bar/line panel on stream "histogram_edge_sim"
@@ -30,8 +32,10 @@
 //!            GROUP BY zo_sql_key ORDER BY zo_sql_key
 //!   4. Set time range to the window printed by "preview"
 //!   5. Observe: first bar is visibly shorter than all other bars (left-edge drop)
+#![allow(clippy::doc_overindented_list_items)]
 
 use chrono::{TimeZone, Utc};
+use o2gen_profile::{load_profile, resolve, Profile};
 use rand::Rng;
 use reqwest::Client;
 use serde::Serialize;
@@ -43,27 +47,56 @@ use std::{
 
 // ── Config ────────────────────────────────────────────────────────────────────
 //
-// Override any of these via env vars:
-//   OO_API_BASE  — e.g. https://main.o2aks1.internal.zinclabs.dev
-//   OO_ORG       — organisation slug (default: "default")
-//   OO_USERNAME  — basic-auth user
-//   OO_PASSWORD  — basic-auth password
-//   OO_STREAM    — stream name to ingest into (default: "histogram_edge_sim")
-
-fn cfg_api_base() -> String {
-    std::env::var("OO_API_BASE").unwrap_or_else(|_| "http://localhost:5080".into())
+// Connection settings come from `--profile NAME` (see ../o2gen_profile, shared
+// with k8s_data_gen and live_ingest). Override any of them via env vars:
+//   O2_API_BASE          — e.g. https://o2.example.com
+//   O2_ORG               — organisation slug (default: "default")
+//   O2_USERNAME          — basic-auth user
+//   O2_PASSWORD          — basic-auth password
+//   O2_STREAM_HISTOGRAM  — stream name to ingest into (default: "histogram_edge_sim")
+// The older OO_API_BASE, OO_ORG, OO_USERNAME, OO_PASSWORD and OO_STREAM still
+// work; the O2_* name wins when both are set.
+
+/// `o2_var`, else the legacy `oo_var`, else the profile's value, else `default`.
+fn setting(o2_var: &str, oo_var: &str, from_profile: Option<&str>, default: &str) -> String {
+    let legacy = std::env::var(oo_var).ok();
+    resolve(o2_var, legacy.as_deref().or(from_profile), default)
+}
+
+fn cfg_api_base(p: &Profile) -> String {
+    setting(
+        "O2_API_BASE",
+        "OO_API_BASE",
+        p.endpoint.as_deref(),
+        "http://localhost:5080",
+    )
 }
-fn cfg_org() -> String {
-    std::env::var("OO_ORG").unwrap_or_else(|_| "default".into())
+fn cfg_org(p: &Profile) -> String {
+    setting("O2_ORG", "OO_ORG", p.org.as_deref(), "default")
 }
-fn cfg_stream() -> String {
-    std::env::var("OO_STREAM").unwrap_or_else(|_| "histogram_edge_sim".into())
+fn cfg_stream(p: &Profile) -> String {
+    setting(
+        "O2_STREAM_HISTOGRAM",
+        "OO_STREAM",
+        p.stream("histogram_edge_sim"),
+        "histogram_edge_sim",
+    )
 }
-fn cfg_username() -> String {
-    std::env::var("OO_USERNAME").unwrap_or_else(|_| "root@example.com".into())
+fn cfg_username(p: &Profile) -> String {
+    setting(
+        "O2_USERNAME",
+        "OO_USERNAME",
+        p.username.as_deref(),
+        "root@example.com",
+    )
 }
-fn cfg_password() -> String {
-    std::env::var("OO_PASSWORD").unwrap_or_else(|_| "Complexpass#123".into())
+fn cfg_password(p: &Profile) -> String {
+    setting(
+        "O2_PASSWORD",
+        "OO_PASSWORD",
+        p.password.as_deref(),
+        "Complexpass#123",
+    )
 }
 
 // ── Histogram interval ────────────────────────────────────────────────────────
@@ -152,7 +185,7 @@ fn ts_str(us: i64) -> String {
 
 // ── Preview ───────────────────────────────────────────────────────────────────
 
-fn preview(query_start_us: i64, query_end_us: i64) {
+fn preview(query_start_us: i64, query_end_us: i64, stream: &str) {
     let first_bucket_start = snap_down(query_start_us);
     let offset_secs = (query_start_us - first_bucket_start) / 1_000_000;
     let remaining_secs = HISTOGRAM_INTERVAL_SECS - offset_secs;
@@ -201,13 +234,13 @@ fn preview(query_start_us: i64, query_end_us: i64) {
     println!("  SELECT histogram(_timestamp) AS zo_sql_key,");
     println!("         severity AS zo_sql_breakdown,");
     println!("         count(*) AS zo_sql_num");
-    println!("  FROM \"{}\"", cfg_stream());
+    println!("  FROM \"{}\"", stream);
     println!("  GROUP BY zo_sql_key, zo_sql_breakdown");
     println!("  ORDER BY zo_sql_key");
 
     println!("\n── Single-line SQL (total, no breakdown) ─────────────────────────────────");
     println!("  SELECT histogram(_timestamp) AS zo_sql_key, count(*) AS zo_sql_num");
-    println!("  FROM \"{}\"", cfg_stream());
+    println!("  FROM \"{}\"", stream);
     println!("  GROUP BY zo_sql_key ORDER BY zo_sql_key");
 
     println!("\n── Set time range in dashboard ───────────────────────────────────────────");
@@ -279,10 +312,10 @@ fn generate(data_start_us: i64, data_end_us: i64) -> std::io::Result<usize> {
 
 // ── Ingest ────────────────────────────────────────────────────────────────────
 
-async fn ingest() -> Result<(), Box<dyn std::error::Error>> {
-    let api_base = cfg_api_base();
-    let org = cfg_org();
-    let stream = cfg_stream();
+async fn ingest(profile: &Profile) -> Result<(), Box<dyn std::error::Error>> {
+    let api_base = cfg_api_base(profile);
+    let org = cfg_org(profile);
+    let stream = cfg_stream(profile);
     let url = format!("{}/api/{}/{}/_json", api_base, org, stream);
     println!("Ingesting {} → {}", OUTPUT_FILE, url);
 
@@ -299,7 +332,7 @@ async fn ingest() -> Result<(), Box<dyn std::error::Error>> {
     for chunk in records.chunks(INGEST_BATCH_SIZE) {
         let resp = client
             .post(&url)
-            .basic_auth(cfg_username(), Some(cfg_password()))
+            .basic_auth(cfg_username(profile), Some(cfg_password(profile)))
             .json(&chunk)
             .send()
             .await?;
@@ -323,6 +356,17 @@ async fn ingest() -> Result<(), Box<dyn std::error::Error>> {
 async fn main() {
     let args: Vec<String> = env::args().collect();
     let cmd = args.get(1).map(|s| s.as_str()).unwrap_or("preview");
+    let profile_name = args
+        .windows(2)
+        .find(|w| w[0] == "--profile")
+        .map(|w| w[1].as_str());
+    let profile = match load_profile(profile_name) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
 
     // Data runs from midnight today for DATA_WINDOW_HOURS.
     // Records are at 10s intervals aligned from midnight — so within every 30s
@@ -343,10 +387,10 @@ async fn main() {
 
     match cmd {
         "preview" => {
-            preview(query_start_us, query_end_us);
+            preview(query_start_us, query_end_us, &cfg_stream(&profile));
         }
         "generate" => {
-            preview(query_start_us, query_end_us);
+            preview(query_start_us, query_end_us, &cfg_stream(&profile));
             print!("Writing {} ...", OUTPUT_FILE);
             std::io::stdout().flush().unwrap();
             match generate(data_start_us, data_end_us) {
@@ -355,19 +399,19 @@ async fn main() {
             }
         }
         "ingest" => {
-            if let Err(e) = ingest().await {
+            if let Err(e) = ingest(&profile).await {
                 eprintln!("Ingest error: {}", e);
                 std::process::exit(1);
             }
         }
         "full" => {
-            preview(query_start_us, query_end_us);
+            preview(query_start_us, query_end_us, &cfg_stream(&profile));
             print!("Writing {} ...", OUTPUT_FILE);
             std::io::stdout().flush().unwrap();
             match generate(data_start_us, data_end_us) {
                 Ok(n) => {
                     println!(" {} records written.", n);
-                    if let Err(e) = ingest().await {
+                    if let Err(e) = ingest(&profile).await {
                         eprintln!("Ingest error: {}", e);
                         std::process::exit(1);
                     }
@@ -379,7 +423,7 @@ async fn main() {
             }
         }
         _ => {
-            eprintln!("usage: histogram_edge_sim [preview|generate|ingest|full]");
+            eprintln!("usage: histogram_edge_sim [preview|generate|ingest|full] [--profile NAME]");
             std::process::exit(1);
         }
     }
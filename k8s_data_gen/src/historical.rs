@@ -0,0 +1,335 @@
+/// Shared options for `historical` generation: time range, sampling interval,
+/// output path and sharding. Each signal's `historical.rs` walks `end_us` back
+/// to `start_us` (newest record first) in steps of `interval_secs`.
+///
+/// The window is cut into `shards` contiguous time ranges generated in
+/// parallel on the rayon pool. Every interval draws from its own RNG, seeded
+/// from the run seed and the interval's index, so the split does not show in
+/// the output. Shards are written to their own files and then either
+/// concatenated into one file (the default) or kept as N files
+/// (`--shard-files`) so they can be ingested in parallel. Layout and codec
+/// come from `--format` / `--compress` (see `output.rs`).
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::Path,
+    sync::atomic::{AtomicI64, Ordering},
+};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, SecondsFormat};
+use rand::rngs::StdRng;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::output::{copy_file, write_frame, Compression, OutputFormat, Sink};
+use crate::utils::{mix64, parse_duration_secs, seeded_rng};
+
+#[derive(Clone)]
+pub struct HistoricalOpts {
+    pub start_us: i64,
+    pub end_us: i64,
+    /// `--interval`; `None` keeps each signal's default (10s logs/metrics, 30s traces).
+    pub interval_secs: Option<i64>,
+    /// `--out`; `None` keeps the signal's default `../output_k8s*.json` path.
+    pub out: Option<String>,
+    /// `--shards`; number of time shards generated in parallel.
+    pub shards: usize,
+    /// `--shard-files`; keep one file per shard instead of merging.
+    pub shard_files: bool,
+    /// `--format`; JSON array or NDJSON.
+    pub format: OutputFormat,
+    /// `--compress`; codec applied to every file written.
+    pub compress: Compression,
+}
+
+impl HistoricalOpts {
+    pub fn interval_secs_or(&self, default: i64) -> i64 {
+        self.interval_secs.unwrap_or(default)
+    }
+
+    /// `--out`, else `default` with its extension adjusted for `--format`
+    /// and `--compress` (`../output_k8s.json` → `../output_k8s.ndjson.gz`).
+    pub fn out_or(&self, default: &str) -> String {
+        self.out
+            .clone()
+            .unwrap_or_else(|| self.default_path(default))
+    }
+
+    fn default_path(&self, default: &str) -> String {
+        format!(
+            "{}{}{}",
+            default.strip_suffix(".json").unwrap_or(default),
+            self.format.ext(),
+            self.compress.ext()
+        )
+    }
+
+    /// Options for one signal of `--stream all`: there `--out` names a
+    /// directory, and each signal writes its default file name inside it.
+    pub fn in_out_dir(&self, default: &str) -> HistoricalOpts {
+        let out = self.out.as_ref().map(|dir| {
+            let default = self.default_path(default);
+            let name = Path::new(&default).file_name().unwrap_or_default();
+            Path::new(dir).join(name).display().to_string()
+        });
+        HistoricalOpts {
+            out,
+            ..self.clone()
+        }
+    }
+
+    /// Number of samples in the window at `interval_secs`.
+    pub fn intervals(&self, interval_secs: i64) -> i64 {
+        (self.end_us - self.start_us) / (interval_secs * 1_000_000)
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{} → {}",
+            DateTime::from_timestamp_micros(self.start_us).unwrap_or_default(),
+            DateTime::from_timestamp_micros(self.end_us).unwrap_or_default()
+        )
+    }
+
+    /// `--start A --end B` with the window's absolute bounds: together with
+    /// `--seed`, the flags that regenerate this run byte for byte.
+    pub fn window_args(&self) -> String {
+        let at = |us| {
+            DateTime::from_timestamp_micros(us)
+                .unwrap_or_default()
+                .to_rfc3339_opts(SecondsFormat::AutoSi, true)
+        };
+        format!("--start {} --end {}", at(self.start_us), at(self.end_us))
+    }
+}
+
+/// RNG for interval `idx` of the window (0 is the newest). Seeding per
+/// interval rather than per shard keeps the output independent of `--shards`.
+pub fn interval_rng(seed: u64, idx: i64) -> StdRng {
+    seeded_rng(mix64(seed ^ mix64(idx as u64)))
+}
+
+/// `../output_k8s.json.gz` → `../output_k8s.03.json.gz`: the shard index goes
+/// before the file name's extensions, zero-padded to the shard count.
+fn shard_path(output_path: &str, idx: usize, shards: usize) -> String {
+    let width = (shards - 1).to_string().len();
+    let name_start = output_path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let (stem, exts) = match output_path[name_start..].find('.') {
+        Some(dot) => output_path.split_at(name_start + dot),
+        None => (output_path, ""),
+    };
+    format!("{}.{:0w$}{}", stem, idx, exts, w = width)
+}
+
+/// File for records derived from another signal's, next to its file:
+/// `../output_k8s_traces.json.gz` + `logs` → `../output_k8s_traces_logs.json.gz`.
+pub fn companion_path(output_path: &str, suffix: &str) -> String {
+    let name_start = output_path.rfind('/').map(|i| i + 1).unwrap_or(0);
+    let (stem, exts) = match output_path[name_start..].find('.') {
+        Some(dot) => output_path.split_at(name_start + dot),
+        None => (output_path, ""),
+    };
+    format!("{}_{}{}", stem, suffix, exts)
+}
+
+/// Generates every interval of `opts`' window across `opts.shards` parallel
+/// time shards. `gen` returns the records for one interval timestamp, drawing
+/// randomness from that interval's RNG. Returns the record count and the files
+/// written (one merged file, or one per shard with `--shard-files`). On error
+/// the partial files are removed.
+pub fn write_sharded<R, F>(
+    opts: &HistoricalOpts,
+    output_path: &str,
+    interval_secs: i64,
+    seed: u64,
+    gen: F,
+) -> Result<(usize, Vec<String>), Box<dyn std::error::Error>>
+where
+    R: Serialize,
+    F: Fn(i64, &mut StdRng) -> Vec<R> + Sync,
+{
+    write_sharded_with(opts, output_path, interval_secs, seed, || &gen)
+}
+
+/// [`write_sharded`] for generators that keep state: `shard_gen` makes one
+/// per shard, which is then called for the shard's intervals newest-first.
+pub fn write_sharded_with<R, S, G>(
+    opts: &HistoricalOpts,
+    output_path: &str,
+    interval_secs: i64,
+    seed: u64,
+    shard_gen: S,
+) -> Result<(usize, Vec<String>), Box<dyn std::error::Error>>
+where
+    R: Serialize,
+    S: Fn() -> G + Sync,
+    G: FnMut(i64, &mut StdRng) -> Vec<R>,
+{
+    let total_intervals = opts.intervals(interval_secs);
+    let shards = opts.shards.clamp(1, total_intervals.max(1) as usize);
+    let paths: Vec<String> = (0..shards)
+        .map(|i| {
+            if opts.shard_files {
+                shard_path(output_path, i, shards)
+            } else {
+                format!("{}.part{}", output_path, i)
+            }
+        })
+        .collect();
+    println!(
+        "Shards: {} ({} parallel threads)",
+        shards,
+        rayon::current_num_threads()
+    );
+
+    let done = AtomicI64::new(0);
+    let report_every = (total_intervals / 20).max(1);
+    let counts = (0..shards)
+        .into_par_iter()
+        .map(|i| -> io::Result<usize> {
+            let from = total_intervals * i as i64 / shards as i64;
+            let to = total_intervals * (i as i64 + 1) / shards as i64;
+            let mut gen = shard_gen();
+            let mut writer = Sink::create(&paths[i], opts.compress)?;
+            if opts.shard_files {
+                writer.write_all(opts.format.open())?;
+            }
+            let mut written = 0usize;
+            for interval_idx in from..to {
+                let ts_us = opts.end_us - interval_idx * interval_secs * 1_000_000;
+                let mut rng = interval_rng(seed, interval_idx);
+                for record in gen(ts_us, &mut rng) {
+                    if written > 0 {
+                        writer.write_all(opts.format.separator())?;
+                    }
+                    serde_json::to_writer(&mut writer, &record)?;
+                    writer.write_all(opts.format.terminator())?;
+                    written += 1;
+                }
+                let n = done.fetch_add(1, Ordering::Relaxed) + 1;
+                if n % report_every == 0 {
+                    println!(
+                        "Progress: {:.1}% ({}/{} intervals)",
+                        n as f64 / total_intervals as f64 * 100.0,
+                        n,
+                        total_intervals
+                    );
+                }
+            }
+            if opts.shard_files {
+                writer.write_all(opts.format.close())?;
+            }
+            writer.finish()?;
+            Ok(written)
+        })
+        .collect::<io::Result<Vec<usize>>>();
+    let counts = match counts {
+        Ok(counts) => counts,
+        Err(e) => {
+            remove_files(&paths);
+            return Err(e.into());
+        }
+    };
+
+    if opts.shard_files {
+        return Ok((counts.iter().sum(), paths));
+    }
+    if let Err(e) = merge_shards(opts, output_path, &paths, &counts) {
+        remove_files(&paths);
+        remove_files(&[output_path.to_string()]);
+        return Err(e.into());
+    }
+    Ok((counts.iter().sum(), vec![output_path.to_string()]))
+}
+
+/// Stitches the shard bodies together, newest shard first, removing each
+/// part once copied. Bodies are already compressed, so brackets and
+/// separators go in as frames of their own.
+fn merge_shards(
+    opts: &HistoricalOpts,
+    output_path: &str,
+    paths: &[String],
+    counts: &[usize],
+) -> io::Result<()> {
+    let (format, compress) = (opts.format, opts.compress);
+    let mut file = write_frame(
+        BufWriter::new(File::create(output_path)?),
+        format.open(),
+        compress,
+    )?;
+    let mut first = true;
+    for (path, &count) in paths.iter().zip(counts) {
+        if count > 0 {
+            if !first {
+                file = write_frame(file, format.separator(), compress)?;
+            }
+            copy_file(path, &mut file)?;
+            first = false;
+        }
+        std::fs::remove_file(path)?;
+    }
+    let mut file = write_frame(file, format.close(), compress)?;
+    file.flush()
+}
+
+/// Best-effort cleanup after a failed write; files already gone are fine.
+fn remove_files(paths: &[String]) {
+    for path in paths {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Final summary plus the `ingest` command for the files just written.
+pub fn print_done(written: usize, unit: &str, files: &[String], stream: Option<&str>) {
+    match files {
+        [file] => println!("\nDone! {} {} → '{}'", written, unit, file),
+        _ => println!("\nDone! {} {} → {} shard files", written, unit, files.len()),
+    }
+    let stream_flag = stream
+        .map(|s| format!(" --stream {}", s))
+        .unwrap_or_default();
+    println!(
+        "Ingest: cargo run -- ingest {}{}",
+        files.join(" "),
+        stream_flag
+    );
+}
+
+/// Parses a `--start`/`--end` value into µs since epoch.
+///
+/// Absolute: `2026-03-14T09:30:00Z` (any RFC 3339 offset), `2026-03-14T09:30:00`
+/// or `2026-03-14` (UTC). Relative to `now_us`: `now`, `now-2h`, `now+30m`,
+/// `-7d`, `+1h`.
+pub fn parse_time_us(s: &str, now_us: i64) -> Result<i64, String> {
+    let s = s.trim();
+    let rel = s.strip_prefix("now").unwrap_or(s);
+    if rel.is_empty() {
+        return Ok(now_us);
+    }
+    if let Some((sign, dur)) = rel
+        .strip_prefix('-')
+        .map(|d| (-1, d))
+        .or_else(|| rel.strip_prefix('+').map(|d| (1, d)))
+    {
+        let secs = parse_duration_secs(dur)
+            .ok_or_else(|| format!("invalid relative time '{}' (e.g. -2d, now+30m)", s))?;
+        return Ok(now_us + sign * secs as i64 * 1_000_000);
+    }
+    if let Ok(t) = DateTime::parse_from_rfc3339(s) {
+        return Ok(t.timestamp_micros());
+    }
+    if let Ok(t) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S") {
+        return Ok(t.and_utc().timestamp_micros());
+    }
+    if let Ok(d) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(d
+            .and_hms_opt(0, 0, 0)
+            .unwrap_or_default()
+            .and_utc()
+            .timestamp_micros());
+    }
+    Err(format!(
+        "invalid time '{}' (use RFC 3339, YYYY-MM-DD, now, now-2h, -7d, +1h)",
+        s
+    ))
+}
@@ -1,36 +1,245 @@
+use futures::future::try_join_all;
 use reqwest::Client;
 
-use crate::client::http::stream_json_array;
-use crate::config::{api_base, password, username, DEFAULT_STREAM_TRACES, INGEST_BATCH_SIZE};
-use crate::metrics::{otlp::metrics_to_otlp_payload, types::K8sMetricRecord};
-use crate::traces::{otlp::traces_to_otlp_payload, types::K8sTraceRecord};
+use crate::client::grpc::{
+    grpc_client, grpc_logs_client, grpc_metrics_client, send_grpc_logs, send_grpc_metrics,
+    send_grpc_traces,
+};
+use crate::client::http::{remote_write_request, stream_json_array};
+use crate::config::{
+    api_base, grpc_endpoint, password, stream_audit, stream_events, stream_metrics, stream_traces,
+    username, INGEST_BATCH_SIZE, INGEST_STREAM_TRACES,
+};
+use crate::logs::schema::{apply_to_json, schema_changes};
+use crate::logs::templates::template_ids_stripped;
+use crate::logs::{
+    logs_to_otlp_payload, logs_to_otlp_protobuf, logs_to_resource_logs, K8sLogRecord, LogsEncoding,
+};
+use crate::metrics::{K8sMetricRecord, MetricsEncoder, RemoteWriteEncoder};
+use crate::traces::{trace_records_to_resource_spans, traces_to_otlp_payload, K8sTraceRecord};
 
+/// Exporter a file goes through, picked by `--stream`.
+#[derive(Clone, Copy)]
+enum Route {
+    /// `stream_metrics()`: OTLP metrics.
+    Metrics,
+    /// `stream_metrics()` with `--remote-write` (over HTTP).
+    RemoteWrite,
+    /// `INGEST_STREAM_TRACES`: OTLP traces into `stream_traces()`.
+    Traces,
+    /// `stream_audit()` / `stream_events()`: records that are not log
+    /// records, sent to `/_json` as they are.
+    Json,
+    /// Any other stream: log records.
+    Logs,
+}
+
+impl Route {
+    fn of(stream: &str, remote_write: bool) -> Self {
+        if stream == stream_metrics() && remote_write {
+            Route::RemoteWrite
+        } else if stream == stream_metrics() {
+            Route::Metrics
+        } else if stream == INGEST_STREAM_TRACES {
+            Route::Traces
+        } else if stream == stream_audit() || stream == stream_events() {
+            Route::Json
+        } else {
+            Route::Logs
+        }
+    }
+}
+
+/// Uploads each file in `file_paths` (e.g. the shards of one `historical
+/// --shard-files` run) concurrently; batches within a file stay in order.
+/// With `use_grpc` every signal goes through the OTLP gRPC services instead
+/// of HTTP, logs included. `otlp` sends logs to `/v1/logs` in that encoding
+/// instead of `/_json`. `remote_write` sends metrics as Prometheus
+/// remote_write instead of OTLP.
 pub async fn run_ingest(
-    file_path: &str,
+    file_paths: &[String],
     org: &str,
     stream: &str,
+    use_grpc: bool,
+    otlp: Option<LogsEncoding>,
+    remote_write: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
     println!("Ingest mode");
-    println!("  File:   {}", file_path);
+    println!("  File:   {}", file_paths.join(", "));
     println!("  Org:    {}", org);
     println!("  Stream: {}", stream);
 
     let client = Client::builder()
         .danger_accept_invalid_certs(true)
         .build()?;
+    // remote_write is HTTP only; `--grpc` sends metrics as OTLP.
+    let route = Route::of(stream, remote_write && !use_grpc);
+    if matches!(route, Route::Json) && (use_grpc || otlp.is_some()) {
+        println!(
+            "  Note:   --grpc/--otlp carry logs, metrics and traces; {} goes to /_json",
+            stream
+        );
+    }
+    let use_grpc = use_grpc && !matches!(route, Route::Json);
+    // OTLP and gRPC encode `K8sLogRecord`s, which have a fixed schema.
+    if matches!(route, Route::Logs) && (use_grpc || otlp.is_some()) && schema_changes().is_some() {
+        return Err(
+            "--schema-changes reshapes plain JSON only; ingest without --otlp/--grpc to apply it"
+                .into(),
+        );
+    }
+
+    let url = if use_grpc {
+        let endpoint = grpc_endpoint();
+        match route {
+            Route::Metrics | Route::RemoteWrite => {
+                println!("  gRPC:   {} (OTLP metrics)", endpoint)
+            }
+            Route::Traces => println!(
+                "  gRPC:   {} (OTLP traces, stream-name: {})",
+                endpoint,
+                stream_traces()
+            ),
+            Route::Logs => println!("  gRPC:   {} (OTLP logs)", endpoint),
+            Route::Json => unreachable!("audit and event files are sent over HTTP"),
+        }
+        endpoint
+    } else {
+        match route {
+            Route::RemoteWrite => {
+                let url = format!("{}/api/{}/prometheus/api/v1/write", api_base(), org);
+                println!("  URL:    {} (Prometheus remote_write)", url);
+                url
+            }
+            Route::Metrics => {
+                let url = format!("{}/api/{}/v1/metrics", api_base(), org);
+                println!("  URL:    {} (OTLP metrics)", url);
+                url
+            }
+            Route::Traces => {
+                let url = format!("{}/api/{}/v1/traces", api_base(), org);
+                println!(
+                    "  URL:    {} (OTLP traces, stream-name: {})",
+                    url,
+                    stream_traces()
+                );
+                url
+            }
+            Route::Json => {
+                let url = format!("{}/api/{}/{}/_json", api_base(), org, stream);
+                println!("  URL:    {}", url);
+                url
+            }
+            Route::Logs => match otlp {
+                Some(enc) => {
+                    let url = format!("{}/api/{}/v1/logs", api_base(), org);
+                    println!(
+                        "  URL:    {} (OTLP logs, {}, stream-name: {})",
+                        url,
+                        enc.name(),
+                        stream
+                    );
+                    url
+                }
+                None => {
+                    let url = format!("{}/api/{}/{}/_json", api_base(), org, stream);
+                    println!("  URL:    {}", url);
+                    url
+                }
+            },
+        }
+    };
+
+    let (client, url) = (&client, url.as_str());
+    let sent: usize = try_join_all(file_paths.iter().map(|file_path| {
+        // Prefix progress lines with the file name once several run at once.
+        let tag = if file_paths.len() > 1 {
+            format!("[{}] ", file_path)
+        } else {
+            String::new()
+        };
+        async move {
+            if use_grpc {
+                ingest_file_grpc(url, file_path, org, stream, route, tag).await
+            } else {
+                ingest_file(client, url, file_path, stream, route, otlp, tag).await
+            }
+        }
+    }))
+    .await?
+    .into_iter()
+    .sum();
+
+    match route {
+        Route::RemoteWrite => {
+            println!(
+                "\nDone! Ingested {} records via Prometheus remote_write",
+                sent
+            )
+        }
+        Route::Metrics => {
+            println!("\nDone! Ingested {} records as OTLP metrics", sent)
+        }
+        Route::Traces => println!(
+            "\nDone! Ingested {} spans as OTLP traces into {}/{}",
+            sent,
+            org,
+            stream_traces()
+        ),
+        Route::Logs | Route::Json => {
+            println!("\nDone! Ingested {} records into {}/{}", sent, org, stream)
+        }
+    }
+    Ok(())
+}
+
+/// Error for a logs file `--otlp`/`--grpc` cannot read as `K8sLogRecord`s.
+fn not_log_records(file_path: &str, e: String) -> String {
+    format!(
+        "{}: not log records in the generated schema ({}). Files written with \
+         --schema-changes can only be ingested as plain JSON, without --otlp/--grpc",
+        file_path, e
+    )
+}
 
-    match stream {
-        "k8s_metrics" => {
-            let url = format!("{}/api/{}/v1/metrics", api_base(), org);
-            println!("  URL:    {} (OTLP metrics)", url);
+async fn ingest_file(
+    client: &Client,
+    url: &str,
+    file_path: &str,
+    stream: &str,
+    route: Route,
+    otlp: Option<LogsEncoding>,
+    tag: String,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut sent = 0usize;
+    match route {
+        Route::RemoteWrite => {
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<K8sMetricRecord>>(4);
+            let handle = stream_json_array::<K8sMetricRecord>(file_path.to_string(), 100, tx);
+            let mut encoder = RemoteWriteEncoder::new();
+            while let Some(batch) = rx.recv().await {
+                let body = encoder.encode_body(&batch)?;
+                let resp = remote_write_request(client, url, body).send().await?;
+                let status = resp.status();
+                if !status.is_success() {
+                    let text = resp.text().await.unwrap_or_default();
+                    eprintln!("{}Batch failed ({}): {}", tag, status, text);
+                    std::process::exit(1);
+                }
+                sent += batch.len();
+                println!("{}Sent {} records", tag, sent);
+            }
+            handle.await.map_err(|e| e.to_string())??;
+        }
 
+        Route::Metrics => {
             let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<K8sMetricRecord>>(4);
             let handle = stream_json_array::<K8sMetricRecord>(file_path.to_string(), 100, tx);
-            let mut sent = 0usize;
+            let mut encoder = MetricsEncoder::new();
             while let Some(batch) = rx.recv().await {
-                let payload = metrics_to_otlp_payload(&batch);
+                let payload = encoder.encode(&batch);
                 let resp = client
-                    .post(&url)
+                    .post(url)
                     .basic_auth(username(), Some(password()))
                     .json(&payload)
                     .send()
@@ -38,65 +247,91 @@ pub async fn run_ingest(
                 let status = resp.status();
                 if !status.is_success() {
                     let text = resp.text().await.unwrap_or_default();
-                    eprintln!("Batch failed ({}): {}", status, text);
+                    eprintln!("{}Batch failed ({}): {}", tag, status, text);
                     std::process::exit(1);
                 }
                 sent += batch.len();
-                println!("Sent {} records", sent);
+                println!("{}Sent {} records", tag, sent);
             }
             handle.await.map_err(|e| e.to_string())??;
-            println!("\nDone! Ingested {} records as OTLP metrics", sent);
         }
 
-        "k8s_traces" => {
-            let url = format!("{}/api/{}/v1/traces", api_base(), org);
-            println!(
-                "  URL:    {} (OTLP traces, stream-name: {})",
-                url, DEFAULT_STREAM_TRACES
-            );
-
+        Route::Traces => {
+            let trace_stream = stream_traces();
             let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<K8sTraceRecord>>(4);
             let handle = stream_json_array::<K8sTraceRecord>(file_path.to_string(), 200, tx);
-            let mut sent = 0usize;
             while let Some(batch) = rx.recv().await {
                 let payload = traces_to_otlp_payload(&batch);
                 let resp = client
-                    .post(&url)
+                    .post(url)
                     .basic_auth(username(), Some(password()))
-                    .header("stream-name", DEFAULT_STREAM_TRACES)
+                    .header("stream-name", &trace_stream)
                     .json(&payload)
                     .send()
                     .await?;
                 let status = resp.status();
                 if !status.is_success() {
                     let text = resp.text().await.unwrap_or_default();
-                    eprintln!("Batch failed ({}): {}", status, text);
+                    eprintln!("{}Batch failed ({}): {}", tag, status, text);
                     std::process::exit(1);
                 }
                 sent += batch.len();
-                println!("Sent {} spans", sent);
+                println!("{}Sent {} spans", tag, sent);
             }
             handle.await.map_err(|e| e.to_string())??;
-            println!(
-                "\nDone! Ingested {} spans as OTLP traces into {}/{}",
-                sent, org, DEFAULT_STREAM_TRACES
-            );
         }
 
-        _ => {
-            let url = format!("{}/api/{}/{}/_json", api_base(), org, stream);
-            println!("  URL:    {}", url);
+        Route::Logs if otlp.is_some() => {
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<K8sLogRecord>>(4);
+            let handle =
+                stream_json_array::<K8sLogRecord>(file_path.to_string(), INGEST_BATCH_SIZE, tx);
+            while let Some(batch) = rx.recv().await {
+                let builder = client
+                    .post(url)
+                    .basic_auth(username(), Some(password()))
+                    .header("stream-name", stream);
+                let builder = match otlp {
+                    Some(LogsEncoding::Protobuf) => builder
+                        .header(reqwest::header::CONTENT_TYPE, "application/x-protobuf")
+                        .body(logs_to_otlp_protobuf(&batch)),
+                    _ => builder.json(&logs_to_otlp_payload(&batch)),
+                };
+                let resp = builder.send().await?;
+                let status = resp.status();
+                if !status.is_success() {
+                    let text = resp.text().await.unwrap_or_default();
+                    eprintln!("{}Batch failed ({}): {}", tag, status, text);
+                    std::process::exit(1);
+                }
+                sent += batch.len();
+                println!("{}Sent {} records", tag, sent);
+            }
+            handle
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| not_log_records(file_path, e))?;
+        }
 
+        Route::Logs | Route::Json => {
             let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<serde_json::Value>>(4);
             let handle = stream_json_array::<serde_json::Value>(
                 file_path.to_string(),
                 INGEST_BATCH_SIZE,
                 tx,
             );
-            let mut sent = 0usize;
-            while let Some(batch) = rx.recv().await {
+            // Only log records carry template IDs and follow `--schema-changes`.
+            let logs = matches!(route, Route::Logs);
+            while let Some(mut batch) = rx.recv().await {
+                if logs && template_ids_stripped() {
+                    for record in batch.iter_mut().filter_map(|v| v.as_object_mut()) {
+                        record.remove("template_id");
+                    }
+                }
+                if logs {
+                    batch.iter_mut().for_each(apply_to_json);
+                }
                 let resp = client
-                    .post(&url)
+                    .post(url)
                     .basic_auth(username(), Some(password()))
                     .json(&batch)
                     .send()
@@ -104,16 +339,80 @@ pub async fn run_ingest(
                 let status = resp.status();
                 if !status.is_success() {
                     let text = resp.text().await.unwrap_or_default();
-                    eprintln!("Batch failed ({}): {}", status, text);
+                    eprintln!("{}Batch failed ({}): {}", tag, status, text);
                     std::process::exit(1);
                 }
                 sent += batch.len();
-                println!("Sent {} records", sent);
+                println!("{}Sent {} records", tag, sent);
             }
             handle.await.map_err(|e| e.to_string())??;
-            println!("\nDone! Ingested {} records into {}/{}", sent, org, stream);
         }
     }
+    Ok(sent)
+}
 
-    Ok(())
+async fn ingest_file_grpc(
+    endpoint: &str,
+    file_path: &str,
+    org: &str,
+    stream: &str,
+    route: Route,
+    tag: String,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut sent = 0usize;
+    match route {
+        Route::Metrics | Route::RemoteWrite => {
+            let mut client = grpc_metrics_client(endpoint).await?;
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<K8sMetricRecord>>(4);
+            let handle = stream_json_array::<K8sMetricRecord>(file_path.to_string(), 100, tx);
+            let mut encoder = MetricsEncoder::new();
+            while let Some(batch) = rx.recv().await {
+                let resource_metrics = encoder.encode_proto(&batch);
+                send_grpc_metrics(&mut client, resource_metrics, org, None)
+                    .await
+                    .map_err(|e| format!("{}Batch failed: {}", tag, e))?;
+                sent += batch.len();
+                println!("{}Sent {} records", tag, sent);
+            }
+            handle.await.map_err(|e| e.to_string())??;
+        }
+
+        Route::Traces => {
+            let trace_stream = stream_traces();
+            let mut client = grpc_client(endpoint).await?;
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<K8sTraceRecord>>(4);
+            let handle = stream_json_array::<K8sTraceRecord>(file_path.to_string(), 200, tx);
+            while let Some(batch) = rx.recv().await {
+                let resource_spans = trace_records_to_resource_spans(&batch);
+                send_grpc_traces(&mut client, resource_spans, org, &trace_stream)
+                    .await
+                    .map_err(|e| format!("{}Batch failed: {}", tag, e))?;
+                sent += batch.len();
+                println!("{}Sent {} spans", tag, sent);
+            }
+            handle.await.map_err(|e| e.to_string())??;
+        }
+
+        Route::Json => unreachable!("audit and event files are sent over HTTP"),
+
+        Route::Logs => {
+            let mut client = grpc_logs_client(endpoint).await?;
+            let (tx, mut rx) = tokio::sync::mpsc::channel::<Vec<K8sLogRecord>>(4);
+            let handle =
+                stream_json_array::<K8sLogRecord>(file_path.to_string(), INGEST_BATCH_SIZE, tx);
+            while let Some(batch) = rx.recv().await {
+                let resource_logs = logs_to_resource_logs(&batch);
+                send_grpc_logs(&mut client, resource_logs, org, stream)
+                    .await
+                    .map_err(|e| format!("{}Batch failed: {}", tag, e))?;
+                sent += batch.len();
+                println!("{}Sent {} records", tag, sent);
+            }
+            handle
+                .await
+                .map_err(|e| e.to_string())?
+                .map_err(|e| not_log_records(file_path, e))?;
+        }
+    }
+    Ok(sent)
 }
@@ -0,0 +1,152 @@
+//! Replica naming and pod churn.
+//!
+//! Pods are named like a Deployment's: `<service>-<pod-template-hash>-<suffix>`.
+//! A rollout changes the template hash (replicas switch over one by one); a
+//! reschedule (eviction, node drain, crash-loop deletion) gives one replica a new
+//! suffix. Container restarts keep the pod name, as in Kubernetes.
+//!
+//! Each pod is placed on one node of its Deployment's cluster for its whole
+//! lifetime; a replacement pod is placed afresh.
+//!
+//! Pod identity is a pure function of (seed, cluster, service, replica, time).
+//! Rollouts and reschedules are drawn per hour-long window from a hash rather
+//! than from the run's RNG, so logs, metrics and traces — historical or live, generated in any
+//! order — agree on which pod was running at a given timestamp.
+
+use crate::config::{
+    LIFECYCLE_LOOKBACK_WINDOWS, LIFECYCLE_WINDOW_SECS, RESCHEDULE_PROB_PER_WINDOW,
+    ROLLOUT_PROB_PER_WINDOW, ROLLOUT_STEP_SECS,
+};
+use crate::topology::topology;
+use crate::utils::{mix64, str_hash};
+
+/// Alphabet Kubernetes uses for generated name suffixes (no vowels, no 0/1/3).
+const NAME_ALPHABET: &[u8] = b"bcdfghjklmnpqrstvwxz2456789";
+
+const SALT_ROLLOUT: u64 = 1;
+const SALT_TEMPLATE: u64 = 2;
+const SALT_RESCHEDULE: u64 = 3;
+const SALT_POD: u64 = 4;
+const SALT_NODE: u64 = 5;
+
+/// One running replica of a service at a point in time.
+#[derive(Clone)]
+pub struct PodRef {
+    /// Index into `topology().clusters`.
+    pub cluster_idx: usize,
+    /// Index into `topology().pods` (the service spec).
+    pub svc_idx: usize,
+    pub name: String,
+    /// Unix seconds this pod was created (last rollout or reschedule).
+    pub started_secs: i64,
+    pub cluster: &'static str,
+    pub node: &'static str,
+}
+
+/// Every replica of every deployment running at `timestamp_us`.
+pub fn pods_at(timestamp_us: i64, seed: u64) -> Vec<PodRef> {
+    let topo = topology();
+    let mut out = Vec::new();
+    for (cluster_idx, svc_idx) in topo.deployments() {
+        for replica in 0..topo.pods[svc_idx].replicas {
+            out.push(pod_at(cluster_idx, svc_idx, replica, timestamp_us, seed));
+        }
+    }
+    out
+}
+
+pub fn pod_at(
+    cluster_idx: usize,
+    svc_idx: usize,
+    replica: u32,
+    timestamp_us: i64,
+    seed: u64,
+) -> PodRef {
+    let topo = topology();
+    let cluster = &topo.clusters[cluster_idx];
+    let service = topo.pods[svc_idx].service;
+    let svc_h = str_hash(cluster.name) ^ str_hash(service).rotate_left(32);
+    let t = timestamp_us.div_euclid(1_000_000);
+
+    let rollout = last_rollout(seed, svc_h, replica, t);
+    let rollout_at =
+        rollout.map(|w| w * LIFECYCLE_WINDOW_SECS + replica as i64 * ROLLOUT_STEP_SECS);
+    let started = rollout_at.max(last_reschedule(seed, svc_h, replica, t));
+
+    let template = hash(
+        seed,
+        &[SALT_TEMPLATE, svc_h, rollout.unwrap_or(i64::MIN) as u64],
+    );
+    let suffix = hash(
+        seed,
+        &[
+            SALT_POD,
+            svc_h,
+            replica as u64,
+            started.unwrap_or(i64::MIN) as u64,
+        ],
+    );
+
+    let node =
+        cluster.nodes[(hash(seed, &[SALT_NODE, suffix]) % cluster.nodes.len() as u64) as usize];
+
+    PodRef {
+        cluster_idx,
+        svc_idx,
+        name: format!("{}-{}-{}", service, encode(template, 10), encode(suffix, 5)),
+        // No churn inside the lookback (vanishingly rare): treat it as that old.
+        started_secs: started
+            .unwrap_or(t - LIFECYCLE_LOOKBACK_WINDOWS as i64 * LIFECYCLE_WINDOW_SECS),
+        cluster: cluster.name,
+        node,
+    }
+}
+
+/// Window index of the latest rollout that has reached `replica` by time `t`.
+fn last_rollout(seed: u64, svc_h: u64, replica: u32, t: i64) -> Option<i64> {
+    let mut w = (t - replica as i64 * ROLLOUT_STEP_SECS).div_euclid(LIFECYCLE_WINDOW_SECS);
+    for _ in 0..LIFECYCLE_LOOKBACK_WINDOWS {
+        if chance(
+            hash(seed, &[SALT_ROLLOUT, svc_h, w as u64]),
+            ROLLOUT_PROB_PER_WINDOW,
+        ) {
+            return Some(w);
+        }
+        w -= 1;
+    }
+    None
+}
+
+/// Unix seconds of the latest reschedule of `replica` at or before `t`.
+fn last_reschedule(seed: u64, svc_h: u64, replica: u32, t: i64) -> Option<i64> {
+    let mut w = t.div_euclid(LIFECYCLE_WINDOW_SECS);
+    for _ in 0..LIFECYCLE_LOOKBACK_WINDOWS {
+        let h = hash(seed, &[SALT_RESCHEDULE, svc_h, replica as u64, w as u64]);
+        if chance(h, RESCHEDULE_PROB_PER_WINDOW) {
+            let at = w * LIFECYCLE_WINDOW_SECS + (mix64(h) % LIFECYCLE_WINDOW_SECS as u64) as i64;
+            if at <= t {
+                return Some(at);
+            }
+        }
+        w -= 1;
+    }
+    None
+}
+
+fn hash(seed: u64, parts: &[u64]) -> u64 {
+    parts.iter().fold(mix64(seed), |acc, p| mix64(acc ^ p))
+}
+
+fn chance(h: u64, p: f64) -> bool {
+    ((h >> 11) as f64 / (1u64 << 53) as f64) < p
+}
+
+fn encode(mut h: u64, len: usize) -> String {
+    (0..len)
+        .map(|_| {
+            let c = NAME_ALPHABET[(h % NAME_ALPHABET.len() as u64) as usize];
+            h /= NAME_ALPHABET.len() as u64;
+            c as char
+        })
+        .collect()
+}
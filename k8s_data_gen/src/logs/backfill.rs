@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+
+use super::generate::generate_log_record;
+use super::types::HISTORICAL_LOGIN_ERROR_PROB;
+use crate::client::http::post_batch;
+use crate::config::{api_base, org, stream_logs, INGEST_BATCH_SIZE, INTERVAL_SECONDS};
+use crate::lifecycle::pods_at;
+use crate::utils::seeded_rng;
+
+/// Streams historical logs oldest-first from `start_us` straight to the
+/// endpoint until the cursor passes the wall clock, then returns so live mode
+/// can take over without a gap.
+pub async fn run_backfill_logs(start_us: i64, seed: u64) -> Result<(), Box<dyn std::error::Error>> {
+    let api_url = format!("{}/api/{}/{}/_json", api_base(), org(), stream_logs());
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    let mut rng = seeded_rng(seed);
+
+    println!(
+        "Backfill logs → {} from {}",
+        api_url,
+        DateTime::from_timestamp_micros(start_us).unwrap_or_default()
+    );
+
+    let mut ts_us = start_us;
+    let mut batch = Vec::with_capacity(INGEST_BATCH_SIZE);
+    let mut sent = 0usize;
+    // Generation outpaces the clock, so the cursor catches up with "now".
+    while ts_us <= Utc::now().timestamp_micros() {
+        for pod in pods_at(ts_us, seed) {
+            batch.push(generate_log_record(
+                &pod,
+                ts_us,
+                None,
+                HISTORICAL_LOGIN_ERROR_PROB,
+                &mut rng,
+            ));
+        }
+        if batch.len() >= INGEST_BATCH_SIZE {
+            post_batch(&client, &api_url, None, &serde_json::to_value(&batch)?).await?;
+            sent += batch.len();
+            batch.clear();
+            if (sent / INGEST_BATCH_SIZE).is_multiple_of(50) {
+                println!(
+                    "[backfill logs] {} records, up to {}",
+                    sent,
+                    DateTime::from_timestamp_micros(ts_us).unwrap_or_default()
+                );
+            }
+        }
+        ts_us += INTERVAL_SECONDS * 1_000_000;
+    }
+    if !batch.is_empty() {
+        post_batch(&client, &api_url, None, &serde_json::to_value(&batch)?).await?;
+        sent += batch.len();
+    }
+
+    println!("[backfill logs] done: {} records — switching to live", sent);
+    Ok(())
+}
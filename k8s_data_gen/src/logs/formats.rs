@@ -0,0 +1,647 @@
+//! Native log line formats (the `log` field).
+//!
+//! Besides its structured fields, every record carries the raw line its
+//! container would have written, in the service's `log_format` (see
+//! `topology.rs`): nginx and ingress-nginx access logs, PostgreSQL, klog,
+//! logfmt, and JSON app logs whose errors carry multi-line Java or Go stack
+//! traces. Lines are rendered from the record's own time, level, status and
+//! latency, so parsing the raw line gives back what the record says.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde_json::json;
+
+use super::types::K8sLogRecord;
+use crate::topology::{topology, LogFormat, Pod};
+
+const HTTP_METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE"];
+
+/// Requests for services without HTTP operations of their own.
+const STOREFRONT_REQUESTS: &[(&str, &str)] = &[
+    ("GET", "/"),
+    ("GET", "/api/v1/products"),
+    ("GET", "/api/v1/products/8812"),
+    ("GET", "/api/v1/cart"),
+    ("POST", "/api/v1/checkout"),
+    ("GET", "/static/js/app.3f9c1a.js"),
+    ("GET", "/static/css/main.css"),
+    ("GET", "/images/hero.webp"),
+];
+
+/// Client networks: public documentation ranges and the pod network.
+const CLIENT_NETS: &[&str] = &["203.0.113", "198.51.100", "192.0.2", "10.244.1", "10.244.3"];
+
+const USER_AGENTS: &[&str] = &[
+    "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/126.0.0.0 Safari/537.36",
+    "Mozilla/5.0 (Macintosh; Intel Mac OS X 14_5) AppleWebKit/605.1.15 (KHTML, like Gecko) Version/17.5 Safari/605.1.15",
+    "Mozilla/5.0 (iPhone; CPU iPhone OS 17_5 like Mac OS X) AppleWebKit/605.1.15 (KHTML, like Gecko) Mobile/15E148",
+    "okhttp/4.12.0",
+    "curl/8.7.1",
+];
+
+const NGINX_ERRORS: &[&str] = &[
+    "upstream timed out (110: Connection timed out) while reading response header from upstream",
+    "connect() failed (111: Connection refused) while connecting to upstream",
+    "recv() failed (104: Connection reset by peer) while reading response header from upstream",
+    "no live upstreams while connecting to upstream",
+];
+
+const NGINX_WARNS: &[&str] = &[
+    "an upstream response is buffered to a temporary file /var/cache/nginx/proxy_temp/4/12/0000000124",
+    "a client request body is buffered to a temporary file /var/cache/nginx/client_temp/0000000087",
+    "upstream server temporarily disabled while reading response header from upstream",
+];
+
+/// ingress-nginx controller messages (klog), `{target}` being `namespace/service`.
+const INGRESS_WARNS: &[(&str, &str)] = &[
+    (
+        "store.go:622",
+        "error obtaining service endpoints: service {target} does not have any active endpoint",
+    ),
+    (
+        "controller.go:1106",
+        "Service \"{target}\" does not have any active Endpoint.",
+    ),
+];
+
+const INGRESS_ERRORS: &[(&str, &str)] = &[
+    (
+        "controller.go:1210",
+        "Error getting SSL certificate \"{target}-tls\": local SSL certificate {target}-tls was not found. Using default certificate",
+    ),
+    (
+        "queue.go:130",
+        "requeuing {target}, err: error reloading nginx: exit status 1",
+    ),
+];
+
+const PG_STATEMENTS: &[&str] = &[
+    "SELECT id, name, price, stock FROM products WHERE category_id = $1 ORDER BY name LIMIT 50",
+    "SELECT qty FROM inventory WHERE sku = $1 FOR UPDATE",
+    "UPDATE stock SET qty = qty - $1, updated_at = now() WHERE sku = $2",
+    "INSERT INTO order_items (order_id, sku, qty, price) VALUES ($1, $2, $3, $4)",
+    "DELETE FROM reservations WHERE expires_at < now()",
+];
+
+/// PostgreSQL warnings and their optional `HINT` line.
+const PG_WARNINGS: &[(&str, Option<&str>)] = &[
+    ("WARNING:  there is no transaction in progress", None),
+    (
+        "LOG:  checkpoints are occurring too frequently (24 seconds apart)",
+        Some("HINT:  Consider increasing the configuration parameter \"max_wal_size\"."),
+    ),
+    (
+        "WARNING:  skipping vacuum of \"stock\" --- lock not available",
+        None,
+    ),
+];
+
+/// PostgreSQL errors and their optional `DETAIL` line; each is followed by the
+/// failing `STATEMENT`.
+const PG_ERRORS: &[(&str, Option<&str>)] = &[
+    (
+        "ERROR:  deadlock detected",
+        Some("DETAIL:  Process 4312 waits for ShareLock on transaction 4821934; blocked by process 4377."),
+    ),
+    (
+        "ERROR:  duplicate key value violates unique constraint \"order_items_pkey\"",
+        Some("DETAIL:  Key (order_id, sku)=(88123, SKU-4471) already exists."),
+    ),
+    ("ERROR:  canceling statement due to statement timeout", None),
+    (
+        "ERROR:  could not serialize access due to concurrent update",
+        None,
+    ),
+];
+
+const DNS_EXTERNAL: &[&str] = &[
+    "api.stripe.com.",
+    "s3.amazonaws.com.",
+    "registry-1.docker.io.",
+    "hooks.slack.com.",
+];
+
+const KLOG_WARNS: &[(&str, &str)] = &[
+    (
+        "reflector.go:539",
+        "k8s.io/client-go@v0.29.3/tools/cache/reflector.go:229: watch of *v1.EndpointSlice ended with: an error on the server (\"unable to decode an event from the watch stream: http2: client connection lost\") has prevented the request from succeeding",
+    ),
+    (
+        "health.go:62",
+        "plugin/health: Local health request to \"http://:8080/health\" took more than 1s: 1.204s",
+    ),
+];
+
+const KLOG_ERRORS: &[(&str, &str)] = &[
+    (
+        "errors.go:62",
+        "plugin/errors: 2 {name} A: read udp {client}->8.8.8.8:53: i/o timeout",
+    ),
+    (
+        "reflector.go:147",
+        "pkg/mod/k8s.io/client-go@v0.29.3/tools/cache/reflector.go:229: Failed to watch *v1.Service: failed to list *v1.Service: Get \"https://10.96.0.1:443/api/v1/services?limit=500&resourceVersion=0\": dial tcp 10.96.0.1:443: connect: connection refused",
+    ),
+];
+
+const LOGFMT_CALLERS: &[&str] = &[
+    "main.go:1134",
+    "head.go:761",
+    "compact.go:519",
+    "scrape.go:1386",
+    "manager.go:648",
+];
+
+const LOGFMT_PATHS: &[&str] = &[
+    "/api/v1/query_range",
+    "/api/v1/query",
+    "/api/v1/series",
+    "/api/ds/query",
+    "/api/dashboards/uid/k8s-overview",
+];
+
+const LOGFMT_ERRORS: &[&str] = &[
+    "context deadline exceeded",
+    "query processing would load too many samples into memory in query execution",
+    "dial tcp 10.96.14.2:9090: connect: connection refused",
+    "database is locked",
+];
+
+/// Logger classes, relative to the service's package.
+const JAVA_LOGGERS: &[&str] = &[
+    "web.CheckoutController",
+    "service.PaymentService",
+    "client.GatewayClient",
+    "repository.OrderRepository",
+    "messaging.EventPublisher",
+];
+
+/// Exception class, message, innermost JDK frames, and an optional cause.
+type JavaException = (
+    &'static str,
+    &'static str,
+    &'static [&'static str],
+    Option<(&'static str, &'static str)>,
+);
+
+const JAVA_EXCEPTIONS: &[JavaException] = &[
+    (
+        "java.net.SocketTimeoutException",
+        "Read timed out",
+        &[
+            "java.base/sun.nio.ch.NioSocketImpl.timedRead(NioSocketImpl.java:288)",
+            "java.base/sun.nio.ch.NioSocketImpl.implRead(NioSocketImpl.java:314)",
+            "java.base/java.net.Socket$SocketInputStream.read(Socket.java:976)",
+        ],
+        None,
+    ),
+    (
+        "java.lang.NullPointerException",
+        "Cannot invoke \"String.length()\" because \"token\" is null",
+        &[],
+        None,
+    ),
+    (
+        "org.springframework.dao.DataAccessResourceFailureException",
+        "Unable to acquire JDBC Connection",
+        &["org.springframework.orm.jpa.vendor.HibernateJpaDialect.convertHibernateAccessException(HibernateJpaDialect.java:277)"],
+        Some((
+            "java.sql.SQLTransientConnectionException",
+            "HikariPool-1 - Connection is not available, request timed out after 30000ms.",
+        )),
+    ),
+    (
+        "java.lang.IllegalStateException",
+        "Payment provider returned status 502",
+        &[],
+        None,
+    ),
+];
+
+const GO_ERRORS: &[&str] = &[
+    "dial tcp 10.96.41.7:5432: connect: connection refused",
+    "context deadline exceeded",
+    "pq: deadlock detected",
+    "rpc error: code = Unavailable desc = connection error",
+    "json: cannot unmarshal string into Go struct field Item.qty of type int",
+];
+
+/// The raw line a container with log format `format` writes for `r`. Can span
+/// several lines (PostgreSQL errors, and stack traces inside the JSON formats).
+pub fn render(format: LogFormat, r: &K8sLogRecord, rng: &mut impl Rng) -> String {
+    let ts = DateTime::from_timestamp_micros(r._timestamp).unwrap_or_default();
+    match format {
+        LogFormat::Nginx => nginx(r, ts, rng),
+        LogFormat::IngressNginx => ingress_nginx(r, ts, rng),
+        LogFormat::Postgres => postgres(r, ts, rng),
+        LogFormat::Klog => coredns(r, ts, rng),
+        LogFormat::Logfmt => logfmt(r, ts, rng),
+        LogFormat::JavaJson => java_json(r, ts, rng),
+        LogFormat::GoJson => go_json(r, ts, rng),
+    }
+}
+
+fn pick<T: Copy>(items: &[T], rng: &mut impl Rng) -> T {
+    items[rng.gen_range(0..items.len())]
+}
+
+fn any_pod(rng: &mut impl Rng) -> &'static Pod {
+    let pods = &topology().pods;
+    &pods[rng.gen_range(0..pods.len())]
+}
+
+fn client_ip(rng: &mut impl Rng) -> String {
+    format!("{}.{}", pick(CLIENT_NETS, rng), rng.gen_range(2..255))
+}
+
+fn pod_ip(rng: &mut impl Rng) -> String {
+    format!("10.244.{}.{}", rng.gen_range(0..8), rng.gen_range(2..255))
+}
+
+/// Method and path of the request a line is about: one of the service's own
+/// HTTP operations when it has some, else a storefront request.
+fn request(r: &K8sLogRecord, rng: &mut impl Rng) -> (&'static str, &'static str) {
+    match r.event_type.as_str() {
+        "healthcheck" => return ("GET", "/healthz"),
+        "login" | "login_error" => return ("POST", "/api/v1/login"),
+        _ => {}
+    }
+    let ops: Vec<(&'static str, &'static str)> = topology()
+        .ops_for(&r.service)
+        .iter()
+        .filter_map(|op| op.split_once(' '))
+        .filter(|(method, path)| HTTP_METHODS.contains(method) && path.starts_with('/'))
+        .collect();
+    if ops.is_empty() {
+        pick(STOREFRONT_REQUESTS, rng)
+    } else {
+        pick(&ops, rng)
+    }
+}
+
+fn user_agent(r: &K8sLogRecord, rng: &mut impl Rng) -> &'static str {
+    if r.event_type == "healthcheck" {
+        "kube-probe/1.29"
+    } else {
+        pick(USER_AGENTS, rng)
+    }
+}
+
+fn body_bytes(r: &K8sLogRecord, rng: &mut impl Rng) -> u32 {
+    match r.status_code {
+        204 | 304 => 0,
+        _ => rng.gen_range(180..48_000),
+    }
+}
+
+/// klog header plus message: `E1018 10:54:21.123456       1 file.go:42] msg`.
+fn klog(r: &K8sLogRecord, ts: DateTime<Utc>, caller: &str, msg: &str) -> String {
+    let severity = match r.log_level.as_str() {
+        "WARN" => 'W',
+        "ERROR" => 'E',
+        _ => 'I',
+    };
+    format!(
+        "{}{} {:>7} {}] {}",
+        severity,
+        ts.format("%m%d %H:%M:%S%.6f"),
+        1,
+        caller,
+        msg
+    )
+}
+
+fn nginx(r: &K8sLogRecord, ts: DateTime<Utc>, rng: &mut impl Rng) -> String {
+    let (method, path) = request(r, rng);
+    let level = match r.log_level.as_str() {
+        "ERROR" => "error",
+        "WARN" => "warn",
+        _ => {
+            return format!(
+                "{} - - [{}] \"{} {} HTTP/1.1\" {} {} \"{}\" \"{}\"",
+                client_ip(rng),
+                ts.format("%d/%b/%Y:%H:%M:%S %z"),
+                method,
+                path,
+                r.status_code,
+                body_bytes(r, rng),
+                if rng.gen_bool(0.6) {
+                    "https://shop.example.com/"
+                } else {
+                    "-"
+                },
+                user_agent(r, rng)
+            )
+        }
+    };
+    let msg = if level == "error" {
+        pick(NGINX_ERRORS, rng)
+    } else {
+        pick(NGINX_WARNS, rng)
+    };
+    let worker = rng.gen_range(20..40);
+    format!(
+        "{} [{}] {}#{}: *{} {}, client: {}, server: _, request: \"{} {} HTTP/1.1\", upstream: \"http://{}:8080{}\", host: \"shop.example.com\"",
+        ts.format("%Y/%m/%d %H:%M:%S"),
+        level,
+        worker,
+        worker,
+        rng.gen_range(1..400_000),
+        msg,
+        client_ip(rng),
+        method,
+        path,
+        pod_ip(rng),
+        path
+    )
+}
+
+/// `namespace-service-port` of a random backend, as ingress-nginx names upstreams.
+fn ingress_upstream(r: &K8sLogRecord, rng: &mut impl Rng) -> (&'static str, &'static str) {
+    let backends: Vec<_> = topology()
+        .pods
+        .iter()
+        .filter(|p| p.service != r.service)
+        .collect();
+    let p = if backends.is_empty() {
+        &topology().pods[0]
+    } else {
+        pick(&backends, rng)
+    };
+    (p.namespace, p.service)
+}
+
+fn ingress_nginx(r: &K8sLogRecord, ts: DateTime<Utc>, rng: &mut impl Rng) -> String {
+    let (namespace, service) = ingress_upstream(r, rng);
+    let target = format!("{}/{}", namespace, service);
+    let controller_msg = match r.log_level.as_str() {
+        "WARN" => Some(pick(INGRESS_WARNS, rng)),
+        "ERROR" => Some(pick(INGRESS_ERRORS, rng)),
+        _ => None,
+    };
+    if let Some((caller, msg)) = controller_msg {
+        return klog(r, ts, caller, &msg.replace("{target}", &target));
+    }
+
+    let (method, path) = request(r, rng);
+    let request_secs = r.response_time_ms / 1000.0;
+    let upstream_secs = (request_secs - rng.gen_range(0.0..0.001)).max(0.0);
+    format!(
+        "{} - - [{}] \"{} {} HTTP/1.1\" {} {} \"-\" \"{}\" {} {:.3} [{}-{}-80] [] {}:8080 {} {:.3} {} {:032x}",
+        client_ip(rng),
+        ts.format("%d/%b/%Y:%H:%M:%S %z"),
+        method,
+        path,
+        r.status_code,
+        body_bytes(r, rng),
+        user_agent(r, rng),
+        rng.gen_range(300..1_400),
+        request_secs,
+        namespace,
+        service,
+        pod_ip(rng),
+        body_bytes(r, rng),
+        upstream_secs,
+        r.status_code,
+        rng.gen::<u128>()
+    )
+}
+
+fn postgres(r: &K8sLogRecord, ts: DateTime<Utc>, rng: &mut impl Rng) -> String {
+    let prefix = format!(
+        "{} [{}] ",
+        ts.format("%Y-%m-%d %H:%M:%S%.3f UTC"),
+        rng.gen_range(1_000..65_000)
+    );
+    let statement = pick(PG_STATEMENTS, rng);
+    // Clients are the other services of the database's namespace.
+    let clients: Vec<&str> = topology()
+        .pods
+        .iter()
+        .filter(|p| p.namespace == r.namespace && p.service != r.service)
+        .map(|p| p.service)
+        .collect();
+    let lines: Vec<String> = match r.log_level.as_str() {
+        "DEBUG" => vec![format!(
+            "LOG:  connection authorized: user=app database={} application_name={}",
+            r.namespace,
+            if clients.is_empty() {
+                "psql"
+            } else {
+                pick(&clients, rng)
+            }
+        )],
+        "WARN" => {
+            let (msg, hint) = pick(PG_WARNINGS, rng);
+            std::iter::once(msg)
+                .chain(hint)
+                .map(str::to_string)
+                .collect()
+        }
+        "ERROR" => {
+            let (msg, detail) = pick(PG_ERRORS, rng);
+            std::iter::once(msg)
+                .chain(detail)
+                .map(str::to_string)
+                .chain([format!("STATEMENT:  {}", statement)])
+                .collect()
+        }
+        _ => vec![format!(
+            "LOG:  duration: {:.3} ms  statement: {}",
+            r.response_time_ms, statement
+        )],
+    };
+    lines
+        .iter()
+        .map(|l| format!("{}{}", prefix, l))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// CoreDNS in klog: query log lines, plugin warnings and errors.
+fn coredns(r: &K8sLogRecord, ts: DateTime<Utc>, rng: &mut impl Rng) -> String {
+    let name = if rng.gen_bool(0.7) {
+        let p = any_pod(rng);
+        format!("{}.{}.svc.cluster.local.", p.service, p.namespace)
+    } else {
+        pick(DNS_EXTERNAL, rng).to_string()
+    };
+    let client = format!("{}:{}", pod_ip(rng), rng.gen_range(32_768..61_000));
+    match r.log_level.as_str() {
+        "WARN" => {
+            let (caller, msg) = pick(KLOG_WARNS, rng);
+            klog(r, ts, caller, msg)
+        }
+        "ERROR" => {
+            let (caller, msg) = pick(KLOG_ERRORS, rng);
+            let msg = msg.replace("{name}", &name).replace("{client}", &client);
+            klog(r, ts, caller, &msg)
+        }
+        _ => {
+            let internal = name.ends_with(".cluster.local.");
+            let rcode = if rng.gen_bool(0.05) {
+                "NXDOMAIN"
+            } else {
+                "NOERROR"
+            };
+            let msg = format!(
+                "{} - {} \"{} IN {} udp {} false 512\" {} {} {} {:.6}s",
+                client,
+                rng.gen_range(1..65_535),
+                if rng.gen_bool(0.8) { "A" } else { "AAAA" },
+                name,
+                rng.gen_range(40..90),
+                rcode,
+                if internal { "qr,aa,rd" } else { "qr,rd,ra" },
+                rng.gen_range(90..300),
+                r.response_time_ms / 1000.0
+            );
+            klog(r, ts, "log.go:62", &msg)
+        }
+    }
+}
+
+/// A logfmt value, quoted with `"` and `\` escaped.
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn logfmt(r: &K8sLogRecord, ts: DateTime<Utc>, rng: &mut impl Rng) -> String {
+    let mut line = format!(
+        "ts={} caller={} level={} component={} msg={}",
+        ts.format("%Y-%m-%dT%H:%M:%S%.3fZ"),
+        pick(LOGFMT_CALLERS, rng),
+        r.log_level.to_lowercase(),
+        r.service,
+        quote(&r.message)
+    );
+    if r.event_type == "request" {
+        line.push_str(&format!(
+            " method=GET path={} status={} duration={}ms",
+            pick(LOGFMT_PATHS, rng),
+            r.status_code,
+            r.response_time_ms
+        ));
+    }
+    if r.log_level == "ERROR" {
+        line.push_str(&format!(" err={}", quote(pick(LOGFMT_ERRORS, rng))));
+    }
+    line
+}
+
+/// `payments-api` → `io.shop.payments.api`.
+fn java_package(service: &str) -> String {
+    format!("io.shop.{}", service.replace('-', "."))
+}
+
+fn java_stack_trace(package: &str, rng: &mut impl Rng) -> String {
+    let (class, msg, jdk_frames, cause) = pick(JAVA_EXCEPTIONS, rng);
+    let app_frames = [
+        format!(
+            "{}.client.GatewayClient.charge(GatewayClient.java:{})",
+            package,
+            rng.gen_range(60..180)
+        ),
+        format!(
+            "{}.service.PaymentService.process(PaymentService.java:{})",
+            package,
+            rng.gen_range(40..220)
+        ),
+        format!(
+            "{}.web.CheckoutController.checkout(CheckoutController.java:{})",
+            package,
+            rng.gen_range(30..90)
+        ),
+    ];
+    let framework_frames = [
+        "org.springframework.web.servlet.FrameworkServlet.service(FrameworkServlet.java:885)",
+        "jakarta.servlet.http.HttpServlet.service(HttpServlet.java:658)",
+        "org.apache.tomcat.util.threads.TaskThread$WrappingRunnable.run(TaskThread.java:61)",
+        "java.base/java.lang.Thread.run(Thread.java:1583)",
+    ];
+
+    let mut out = format!("{}: {}", class, msg);
+    let frames = jdk_frames
+        .iter()
+        .map(|f| f.to_string())
+        .chain(app_frames)
+        .chain(framework_frames.iter().map(|f| f.to_string()));
+    for frame in frames {
+        out.push_str(&format!("\n\tat {}", frame));
+    }
+    if let Some((cause_class, cause_msg)) = cause {
+        out.push_str(&format!(
+            "\nCaused by: {}: {}\n\tat com.zaxxer.hikari.pool.HikariPool.createTimeoutException(HikariPool.java:686)\n\tat com.zaxxer.hikari.pool.HikariPool.getConnection(HikariPool.java:179)\n\t... {} common frames omitted",
+            cause_class,
+            cause_msg,
+            rng.gen_range(40..90)
+        ));
+    }
+    out
+}
+
+fn java_json(r: &K8sLogRecord, ts: DateTime<Utc>, rng: &mut impl Rng) -> String {
+    let package = java_package(&r.service);
+    let (level, level_value) = match r.log_level.as_str() {
+        "DEBUG" => ("DEBUG", 10_000),
+        "WARN" => ("WARN", 30_000),
+        "ERROR" => ("ERROR", 40_000),
+        _ => ("INFO", 20_000),
+    };
+    let mut line = json!({
+        "@timestamp": ts.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+        "@version": "1",
+        "message": r.message,
+        "logger_name": format!("{}.{}", package, pick(JAVA_LOGGERS, rng)),
+        "thread_name": format!("http-nio-8080-exec-{}", rng.gen_range(1..=200)),
+        "level": level,
+        "level_value": level_value,
+    });
+    if r.log_level == "ERROR" {
+        line["stack_trace"] = json!(java_stack_trace(&package, rng));
+    }
+    line.to_string()
+}
+
+/// Handler name for a request path: `/api/v1/stock` → `handleStock`.
+fn go_handler(path: &str) -> String {
+    let segment = path
+        .rsplit('/')
+        .find(|s| !s.is_empty() && !s.starts_with(|c: char| c.is_ascii_digit()))
+        .unwrap_or("root");
+    let mut chars = segment.chars();
+    let first = chars.next().map(|c| c.to_ascii_uppercase());
+    format!("handle{}{}", first.unwrap_or_default(), chars.as_str())
+}
+
+fn go_stack_trace(service: &str, path: &str, rng: &mut impl Rng) -> String {
+    format!(
+        "github.com/shop/{svc}/internal/server.(*Server).{handler}\n\t/app/internal/server/handlers.go:{}\n\
+         net/http.HandlerFunc.ServeHTTP\n\t/usr/local/go/src/net/http/server.go:2171\n\
+         github.com/shop/{svc}/internal/middleware.Tracing.func1\n\t/app/internal/middleware/tracing.go:{}\n\
+         net/http.HandlerFunc.ServeHTTP\n\t/usr/local/go/src/net/http/server.go:2171\n\
+         net/http.serverHandler.ServeHTTP\n\t/usr/local/go/src/net/http/server.go:3142\n\
+         net/http.(*conn).serve\n\t/usr/local/go/src/net/http/server.go:2044",
+        rng.gen_range(40..400),
+        rng.gen_range(20..80),
+        svc = service,
+        handler = go_handler(path)
+    )
+}
+
+fn go_json(r: &K8sLogRecord, ts: DateTime<Utc>, rng: &mut impl Rng) -> String {
+    let (_, path) = request(r, rng);
+    let mut line = json!({
+        "level": r.log_level.to_lowercase(),
+        "ts": ts.timestamp_micros() as f64 / 1_000_000.0,
+        "caller": format!("server/handlers.go:{}", rng.gen_range(40..400)),
+        "msg": r.message,
+        "path": path,
+        "status": r.status_code,
+        "latency_ms": r.response_time_ms,
+    });
+    if r.log_level == "ERROR" {
+        line["error"] = json!(pick(GO_ERRORS, rng));
+        line["stacktrace"] = json!(go_stack_trace(&r.service, path, rng));
+    }
+    line.to_string()
+}
@@ -1,22 +1,22 @@
 use rand::{seq::SliceRandom, Rng};
-use uuid::Uuid;
 
+use super::formats::render;
+use super::templates::pick_message;
 use super::types::*;
 use crate::anomaly::{AnomalyState, AnomalyType};
-use crate::topology::{CLUSTERS, NODES, PODS};
-use crate::utils::{daily_seasonal, pod_name, weighted_choice};
+use crate::cardinality::{extra_labels, Stream};
+use crate::lifecycle::PodRef;
+use crate::topology::topology;
+use crate::utils::{daily_seasonal, rng_uuid, weighted_choice};
 
 pub fn generate_log_record(
-    pod_idx: usize,
+    pod_ref: &PodRef,
     timestamp_us: i64,
     anomaly: Option<&AnomalyState>,
     login_error_prob: f64,
     rng: &mut impl Rng,
 ) -> K8sLogRecord {
-    let pod = &PODS[pod_idx];
-    let cluster = CLUSTERS[rng.gen_range(0..CLUSTERS.len())];
-    let node = NODES[pod_idx % NODES.len()];
-    let pname = pod_name(pod_idx);
+    let pod = &topology().pods[pod_ref.svc_idx];
 
     let is_anomaly = anomaly.map(|a| a.is_active()).unwrap_or(false);
     let anomaly_type = anomaly.map(|a| &a.anomaly_type);
@@ -54,7 +54,9 @@ pub fn generate_log_record(
         (pod.base_err * (1.0 + rng.gen_range(-0.50_f64..=0.50))).clamp(0.0, 0.05)
     };
 
-    let rps = (pod.base_rps as f64 * season * (1.0 + rng.gen_range(-0.20_f64..=0.20))) as u32;
+    let rps = (pod.base_rps as f64 / pod.replicas as f64
+        * season
+        * (1.0 + rng.gen_range(-0.20_f64..=0.20))) as u32;
     let net_rx = rps as u64 * rng.gen_range(800..1200);
     let net_tx = rps as u64 * rng.gen_range(400..800);
 
@@ -70,28 +72,21 @@ pub fn generate_log_record(
         rng,
     );
 
-    let (event_type, message) = if is_login_anomaly {
-        (
-            "login_error",
-            MESSAGES_LOGIN_ERROR[rng.gen_range(0..MESSAGES_LOGIN_ERROR.len())].to_string(),
-        )
-    } else if rng.gen_bool(login_error_prob) {
-        (
-            "login_error",
-            MESSAGES_LOGIN_ERROR[rng.gen_range(0..MESSAGES_LOGIN_ERROR.len())].to_string(),
-        )
+    let (event_type, (message, template_id)) = if is_login_anomaly || rng.gen_bool(login_error_prob)
+    {
+        ("login_error", pick_message(MESSAGES_LOGIN_ERROR, rng))
     } else {
         let et = EVENT_TYPES[rng.gen_range(0..EVENT_TYPES.len())];
-        let msg = if et == "login" {
-            MESSAGES_LOGIN_OK[rng.gen_range(0..MESSAGES_LOGIN_OK.len())].to_string()
+        let templates = if et == "login" {
+            MESSAGES_LOGIN_OK
         } else {
             match log_level {
-                "ERROR" => MESSAGES_ERROR[rng.gen_range(0..MESSAGES_ERROR.len())].to_string(),
-                "WARN" => MESSAGES_WARN[rng.gen_range(0..MESSAGES_WARN.len())].to_string(),
-                _ => MESSAGES_INFO[rng.gen_range(0..MESSAGES_INFO.len())].to_string(),
+                "ERROR" => MESSAGES_ERROR,
+                "WARN" => MESSAGES_WARN,
+                _ => MESSAGES_INFO,
             }
         };
-        (et, msg)
+        (et, pick_message(templates, rng))
     };
 
     let status_code: u16 = if error_rate > 0.20 {
@@ -108,13 +103,13 @@ pub fn generate_log_record(
             .unwrap()
     };
 
-    K8sLogRecord {
+    let mut record = K8sLogRecord {
         _timestamp: timestamp_us,
-        cluster: cluster.to_string(),
+        cluster: pod_ref.cluster.to_string(),
         namespace: pod.namespace.to_string(),
-        pod: pname,
+        pod: pod_ref.name.clone(),
         container: pod.container.to_string(),
-        node: node.to_string(),
+        node: pod_ref.node.to_string(),
         service: pod.service.to_string(),
         cpu_millicores: cpu,
         memory_mb: memory,
@@ -128,6 +123,16 @@ pub fn generate_log_record(
         event_type: event_type.to_string(),
         status_code,
         message,
-        unique_id: Uuid::new_v4().to_string(),
-    }
+        log: String::new(),
+        template_id: template_id.to_string(),
+        unique_id: rng_uuid(rng).to_string(),
+        trace_id: String::new(),
+        span_id: String::new(),
+        exception_type: String::new(),
+        exception_message: String::new(),
+        exception_stacktrace: String::new(),
+        labels: extra_labels(Stream::Logs, pod_ref.cluster, &pod_ref.name, rng),
+    };
+    record.log = render(pod.log_format, &record, rng);
+    record
 }
@@ -1,30 +1,169 @@
 use std::time::Duration;
 
 use chrono::Utc;
+use opentelemetry_proto::tonic::collector::logs::v1::logs_service_client::LogsServiceClient;
 use reqwest::Client;
+use tonic::transport::Channel;
 
 use super::generate::generate_log_record;
-use super::types::LOGIN_ERROR_BACKGROUND_PROB;
+use super::otlp::{
+    logs_to_otlp_payload, logs_to_otlp_protobuf, logs_to_resource_logs, LogsEncoding,
+};
+use super::types::{K8sLogRecord, LOGIN_ERROR_BACKGROUND_PROB};
 use crate::anomaly::{AnomalyState, AnomalyType};
-use crate::client::http::post_live;
-use crate::config::{api_base, DEFAULT_ORG, DEFAULT_STREAM_LOGS, PODS_PER_TICK};
-use crate::utils::print_anomaly_header;
+use crate::client::grpc::{grpc_logs_client, report_export, send_grpc_logs};
+use crate::client::http::{post_live, post_otlp, post_otlp_protobuf};
+use crate::config::{api_base, grpc_endpoint, org, stream_logs};
+use crate::lifecycle::pods_at;
+use crate::rate::{pick_pods, print_rate_header, RateSchedule, TokenBucket};
+use crate::utils::{print_anomaly_header, seeded_rng};
 
+/// Where the live loop sends each tick's records.
+enum Transport {
+    Json {
+        client: Client,
+        url: String,
+    },
+    Otlp {
+        client: Client,
+        url: String,
+        stream: String,
+        encoding: LogsEncoding,
+    },
+    Grpc {
+        client: LogsServiceClient<Channel>,
+        org: String,
+        stream: String,
+    },
+}
+
+impl Transport {
+    async fn send(&mut self, records: &[K8sLogRecord], anomaly_state: &Option<AnomalyState>) {
+        match self {
+            Transport::Json { client, url } => post_live(client, url, records, anomaly_state).await,
+            Transport::Otlp {
+                client,
+                url,
+                stream,
+                encoding: LogsEncoding::Json,
+            } => {
+                let payload = logs_to_otlp_payload(records);
+                post_otlp(
+                    client,
+                    url,
+                    Some(stream),
+                    &payload,
+                    records.len(),
+                    anomaly_state,
+                )
+                .await
+            }
+            Transport::Otlp {
+                client,
+                url,
+                stream,
+                encoding: LogsEncoding::Protobuf,
+            } => {
+                let body = logs_to_otlp_protobuf(records);
+                post_otlp_protobuf(
+                    client,
+                    url,
+                    Some(stream),
+                    body,
+                    records.len(),
+                    anomaly_state,
+                )
+                .await
+            }
+            Transport::Grpc {
+                client,
+                org,
+                stream,
+            } => {
+                let resource_logs = logs_to_resource_logs(records);
+                let result = send_grpc_logs(client, resource_logs, org, stream).await;
+                if !report_export(result, records.len(), "records", anomaly_state) {
+                    if let Ok(new_client) = grpc_logs_client(&grpc_endpoint()).await {
+                        *client = new_client;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Posts to `/_json`, or to OTLP `/v1/logs` in the given encoding when `otlp`
+/// is set (same records, for comparing the two ingestion paths).
 pub async fn run_live_logs(
     anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+    otlp: Option<LogsEncoding>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let api_url = format!(
-        "{}/api/{}/{}/_json",
-        api_base(), DEFAULT_ORG, DEFAULT_STREAM_LOGS
-    );
+    let stream = stream_logs();
     let client = Client::builder()
         .danger_accept_invalid_certs(true)
         .build()?;
-    let mut rng = rand::thread_rng();
+    let transport = match otlp {
+        Some(encoding) => {
+            let url = format!("{}/api/{}/v1/logs", api_base(), org());
+            println!(
+                "Live logs (OTLP {}) → {} [stream-name: {}]",
+                encoding.name(),
+                url,
+                stream
+            );
+            Transport::Otlp {
+                client,
+                url,
+                stream,
+                encoding,
+            }
+        }
+        None => {
+            let url = format!("{}/api/{}/{}/_json", api_base(), org(), stream);
+            println!("Live logs → {}", url);
+            Transport::Json { client, url }
+        }
+    };
+    run_loop(anomaly_type, seed, rate, transport).await
+}
+
+pub async fn run_live_logs_grpc(
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = grpc_logs_client(&grpc_endpoint()).await?;
+    let org = org();
+    let stream = stream_logs();
+    println!(
+        "Live logs (gRPC OTLP) → {} [org: {}, stream: {}]",
+        &grpc_endpoint(),
+        org,
+        stream
+    );
+    let transport = Transport::Grpc {
+        client,
+        org,
+        stream,
+    };
+    run_loop(anomaly_type, seed, rate, transport).await
+}
+
+/// One tick per second: a record per running pod (or the rate's picks).
+async fn run_loop(
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+    mut transport: Transport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = seeded_rng(seed);
     let mut interval = tokio::time::interval(Duration::from_secs(1));
     let mut anomaly_state = anomaly_type.map(AnomalyState::new);
+    let mut bucket = rate.map(TokenBucket::new);
 
-    println!("Live logs → {}", api_url);
+    print_rate_header(&bucket, "one record per running pod per second");
     print_anomaly_header(&anomaly_state);
     println!("Press Ctrl+C to stop.\n");
 
@@ -35,18 +174,23 @@ pub async fn run_live_logs(
         }
 
         let now_us = Utc::now().timestamp_micros();
-        let records: Vec<_> = (0..PODS_PER_TICK)
-            .map(|i| {
+        let pods = pods_at(now_us, seed);
+        let records: Vec<_> = pick_pods(bucket.as_mut(), &pods, now_us, &mut rng)
+            .into_iter()
+            .map(|(pod, ts_us)| {
                 generate_log_record(
-                    i,
-                    now_us,
+                    pod,
+                    ts_us,
                     anomaly_state.as_ref(),
                     LOGIN_ERROR_BACKGROUND_PROB,
                     &mut rng,
                 )
             })
             .collect();
+        if records.is_empty() {
+            continue;
+        }
 
-        post_live(&client, &api_url, &records, &anomaly_state).await;
+        transport.send(&records, &anomaly_state).await;
     }
 }
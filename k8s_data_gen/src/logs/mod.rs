@@ -1,9 +1,20 @@
+pub mod backfill;
+pub mod formats;
 pub mod generate;
 pub mod historical;
 pub mod live;
+pub mod otlp;
+pub mod schema;
+pub mod templates;
 pub mod types;
 
+pub use backfill::run_backfill_logs;
 pub use generate::generate_log_record;
 pub use historical::run_historical_logs;
-pub use live::run_live_logs;
+pub use live::{run_live_logs, run_live_logs_grpc};
+pub use otlp::{
+    logs_to_otlp_payload, logs_to_otlp_protobuf, logs_to_resource_logs, LogsEncoding,
+};
+pub use schema::init_schema_changes;
+pub use templates::init_template_ids;
 pub use types::K8sLogRecord;
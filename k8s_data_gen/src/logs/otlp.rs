@@ -0,0 +1,195 @@
+//! `K8sLogRecord` → OTLP logs, for `/v1/logs` (JSON or protobuf) and the gRPC
+//! `LogsService`. Every encoding is rendered from the same protobuf messages,
+//! so the three paths carry identical data.
+
+use opentelemetry_proto::tonic::{
+    collector::logs::v1::ExportLogsServiceRequest,
+    common::v1::{any_value::Value, AnyValue, InstrumentationScope},
+    logs::v1::{LogRecord, ResourceLogs, ScopeLogs},
+    resource::v1::Resource,
+};
+use prost::Message;
+
+use super::templates::template_ids_stripped;
+use super::types::K8sLogRecord;
+use crate::traces::otlp::{any_value_json, attributes_json, hex_id, kv_double, kv_int, kv_str};
+
+/// Body encoding for OTLP/HTTP (`--otlp json|protobuf`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum LogsEncoding {
+    Json,
+    Protobuf,
+}
+
+impl LogsEncoding {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "json" => Ok(LogsEncoding::Json),
+            "protobuf" | "proto" => Ok(LogsEncoding::Protobuf),
+            _ => Err(format!("unknown --otlp '{}' (use json or protobuf)", s)),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LogsEncoding::Json => "json",
+            LogsEncoding::Protobuf => "protobuf",
+        }
+    }
+}
+
+/// OTLP `SeverityNumber` for a `log_level` (the first value of each range).
+pub fn severity_number(log_level: &str) -> i32 {
+    match log_level {
+        "TRACE" => 1,
+        "DEBUG" => 5,
+        "INFO" => 9,
+        "WARN" => 13,
+        "ERROR" => 17,
+        "FATAL" => 21,
+        _ => 0,
+    }
+}
+
+fn log_record(r: &K8sLogRecord) -> LogRecord {
+    let ts_ns = (r._timestamp * 1000) as u64;
+    let mut attributes = vec![
+        kv_str("event_type", &r.event_type),
+        kv_int("status_code", r.status_code as i64),
+        kv_int("cpu_millicores", r.cpu_millicores as i64),
+        kv_int("memory_mb", r.memory_mb as i64),
+        kv_int("network_rx_bytes", r.network_rx_bytes as i64),
+        kv_int("network_tx_bytes", r.network_tx_bytes as i64),
+        kv_int("restarts", r.restarts as i64),
+        kv_double("response_time_ms", r.response_time_ms),
+        kv_double("error_rate", r.error_rate),
+        kv_int("requests_per_second", r.requests_per_second as i64),
+        kv_str("unique_id", &r.unique_id),
+    ];
+    if !r.template_id.is_empty() && !template_ids_stripped() {
+        attributes.push(kv_str("template_id", &r.template_id));
+    }
+    for (key, value) in [
+        ("exception.type", &r.exception_type),
+        ("exception.message", &r.exception_message),
+        ("exception.stacktrace", &r.exception_stacktrace),
+    ] {
+        if !value.is_empty() {
+            attributes.push(kv_str(key, value));
+        }
+    }
+    attributes.extend(r.labels.iter().map(|(k, v)| kv_str(k, v)));
+    // A collector ships the container's raw line as the body; the generator's
+    // summary of it stays available as `message`.
+    let body = if r.log.is_empty() {
+        &r.message
+    } else {
+        attributes.push(kv_str("message", &r.message));
+        &r.log
+    };
+    LogRecord {
+        time_unix_nano: ts_ns,
+        observed_time_unix_nano: ts_ns,
+        severity_number: severity_number(&r.log_level),
+        severity_text: r.log_level.clone(),
+        body: Some(AnyValue {
+            value: Some(Value::StringValue(body.clone())),
+        }),
+        attributes,
+        trace_id: hex_id(&r.trace_id),
+        span_id: hex_id(&r.span_id),
+        ..Default::default()
+    }
+}
+
+/// One `ResourceLogs` per record, resource attributes from the pod's identity.
+pub fn logs_to_resource_logs(records: &[K8sLogRecord]) -> Vec<ResourceLogs> {
+    records
+        .iter()
+        .map(|r| ResourceLogs {
+            resource: Some(Resource {
+                // Trace logs of services outside the topology have no pod,
+                // node or cluster; leave those attributes out.
+                attributes: [
+                    ("service.name", &r.service),
+                    ("k8s.namespace.name", &r.namespace),
+                    ("k8s.pod.name", &r.pod),
+                    ("k8s.container.name", &r.container),
+                    ("k8s.node.name", &r.node),
+                    ("k8s.cluster.name", &r.cluster),
+                ]
+                .into_iter()
+                .filter(|(_, v)| !v.is_empty())
+                .map(|(k, v)| kv_str(k, v))
+                .collect(),
+                dropped_attributes_count: 0,
+            }),
+            scope_logs: vec![ScopeLogs {
+                scope: Some(InstrumentationScope {
+                    name: "k8s-data-gen".to_string(),
+                    version: "0.1.0".to_string(),
+                    ..Default::default()
+                }),
+                log_records: vec![log_record(r)],
+                schema_url: String::new(),
+            }],
+            schema_url: String::new(),
+        })
+        .collect()
+}
+
+/// `ExportLogsServiceRequest` body for `POST /v1/logs` with
+/// `Content-Type: application/x-protobuf`.
+pub fn logs_to_otlp_protobuf(records: &[K8sLogRecord]) -> Vec<u8> {
+    ExportLogsServiceRequest {
+        resource_logs: logs_to_resource_logs(records),
+    }
+    .encode_to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn log_record_json(l: &LogRecord) -> serde_json::Value {
+    let mut record = serde_json::json!({
+        "timeUnixNano": l.time_unix_nano.to_string(),
+        "observedTimeUnixNano": l.observed_time_unix_nano.to_string(),
+        "severityNumber": l.severity_number,
+        "severityText": l.severity_text,
+        "body": any_value_json(l.body.as_ref()),
+        "attributes": attributes_json(&l.attributes),
+    });
+    if !l.trace_id.is_empty() {
+        record["traceId"] = hex(&l.trace_id).into();
+    }
+    if !l.span_id.is_empty() {
+        record["spanId"] = hex(&l.span_id).into();
+    }
+    record
+}
+
+/// OTLP/HTTP JSON body (`/v1/logs`).
+pub fn logs_to_otlp_payload(records: &[K8sLogRecord]) -> serde_json::Value {
+    let resource_logs: Vec<_> = logs_to_resource_logs(records)
+        .iter()
+        .map(|rl| {
+            let resource_attrs = rl
+                .resource
+                .as_ref()
+                .map(|r| attributes_json(&r.attributes))
+                .unwrap_or_default();
+            serde_json::json!({
+                "resource": {"attributes": resource_attrs},
+                "scopeLogs": rl.scope_logs.iter().map(|sl| serde_json::json!({
+                    "scope": {
+                        "name": sl.scope.as_ref().map(|s| s.name.as_str()).unwrap_or_default(),
+                        "version": sl.scope.as_ref().map(|s| s.version.as_str()).unwrap_or_default(),
+                    },
+                    "logRecords": sl.log_records.iter().map(log_record_json).collect::<Vec<_>>(),
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    serde_json::json!({ "resourceLogs": resource_logs })
+}
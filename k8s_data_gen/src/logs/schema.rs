@@ -0,0 +1,357 @@
+//! Scheduled schema changes for log records (`--schema-changes SPEC`).
+//!
+//! Reshapes `K8sLogRecord`s from a point in time on, the way a deployment
+//! changes what a service logs: a field is added, dropped, renamed, changes
+//! type, or moves into a nested object. Each change applies to every record
+//! whose `_timestamp` is at or after its time, in `historical`, `live` and
+//! `run` alike, so the same schedule replays against any window.
+//!
+//! Spec syntax, one `OP@TIME` per change, comma-separated (`@TIME` omitted:
+//! from the start):
+//!
+//! ```text
+//! add:tenant=string@+5m               new field; string | int | float | bool
+//! drop:network_tx_bytes@+10m          field disappears
+//! rename:pod=pod_name@+15m            same values, new name
+//! type:status_code=string@+20m        200 → "200"; string | int | float | bool
+//! nest:k8s=cluster+namespace+node@1h  fields move into object `k8s`
+//! ```
+//!
+//! `TIME` is anything `--start` takes (`+30m`, `now-2h`, `2026-03-14T09:30:00Z`);
+//! relative times count from the window start in `historical` and from the
+//! start of the run otherwise.
+//! Fields inside nested objects are addressed as `k8s.node`. Changes apply in
+//! time order, then spec order. They reshape the JSON written or sent
+//! (`/_json`, files, `ingest` of plain JSON). Live OTLP and gRPC logs keep the
+//! original schema. `ingest --otlp/--grpc` reads files as `K8sLogRecord`s, so
+//! it refuses `--schema-changes` and stops with an error on a file written
+//! with them.
+
+use std::sync::OnceLock;
+
+use chrono::DateTime;
+use serde_json::{Map, Value};
+
+use crate::historical::parse_time_us;
+use crate::utils::{mix64, str_hash};
+
+/// Distinct values of a string field added by `add:NAME=string`.
+const ADDED_STRING_VALUES: u64 = 20;
+
+#[derive(Clone, Copy)]
+enum FieldType {
+    String,
+    Int,
+    Float,
+    Bool,
+}
+
+impl FieldType {
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "string" => Some(FieldType::String),
+            "int" => Some(FieldType::Int),
+            "float" => Some(FieldType::Float),
+            "bool" => Some(FieldType::Bool),
+            _ => None,
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Int => "int",
+            FieldType::Float => "float",
+            FieldType::Bool => "bool",
+        }
+    }
+
+    /// A value of this type for field `name` of the record keyed `key`.
+    fn draw(&self, name: &str, key: u64) -> Value {
+        let h = mix64(key ^ str_hash(name));
+        match self {
+            FieldType::String => Value::from(format!("{}-{}", name, h % ADDED_STRING_VALUES)),
+            FieldType::Int => Value::from(h % 10_000),
+            FieldType::Float => Value::from((h % 100_000) as f64 / 100.0),
+            FieldType::Bool => Value::from(h & 1 == 0),
+        }
+    }
+
+    /// `v` as this type; `None` when it has no such reading ("abc" as int).
+    fn convert(&self, v: &Value) -> Option<Value> {
+        match (self, v) {
+            (_, Value::Null) => None,
+            (FieldType::String, Value::String(_)) => Some(v.clone()),
+            (FieldType::String, _) => Some(Value::from(v.to_string())),
+            (FieldType::Int, Value::Number(n)) => n
+                .as_i64()
+                .or_else(|| n.as_f64().map(|f| f as i64))
+                .map(Value::from),
+            (FieldType::Int, Value::Bool(b)) => Some(Value::from(*b as i64)),
+            (FieldType::Int, Value::String(s)) => s.trim().parse::<i64>().ok().map(Value::from),
+            (FieldType::Float, Value::Number(n)) => n.as_f64().map(Value::from),
+            (FieldType::Float, Value::Bool(b)) => Some(Value::from(*b as i64 as f64)),
+            (FieldType::Float, Value::String(s)) => s.trim().parse::<f64>().ok().map(Value::from),
+            (FieldType::Bool, Value::Bool(_)) => Some(v.clone()),
+            (FieldType::Bool, Value::Number(n)) => n.as_f64().map(|f| Value::from(f != 0.0)),
+            (FieldType::Bool, Value::String(s)) => match s.trim() {
+                "true" | "1" => Some(Value::from(true)),
+                "false" | "0" => Some(Value::from(false)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+enum Op {
+    Add(String, FieldType),
+    Drop(String),
+    Rename(String, String),
+    Retype(String, FieldType),
+    Nest(String, Vec<String>),
+}
+
+impl Op {
+    fn parse(s: &str) -> Result<Self, String> {
+        let (kind, arg) = s
+            .split_once(':')
+            .ok_or_else(|| format!("'{}' is not OP:ARGS", s))?;
+        let pair = || {
+            arg.split_once('=')
+                .map(|(a, b)| (a.trim(), b.trim()))
+                .ok_or_else(|| format!("'{}' needs {}:A=B", s, kind))
+        };
+        let field_type = |t: &str| {
+            FieldType::parse(t)
+                .ok_or_else(|| format!("'{}': type must be string, int, float or bool", s))
+        };
+        let op = match kind {
+            "add" => {
+                let (name, t) = pair()?;
+                Op::Add(field(name)?, field_type(t)?)
+            }
+            "drop" => Op::Drop(field(arg)?),
+            "rename" => {
+                let (from, to) = pair()?;
+                Op::Rename(field(from)?, field(to)?)
+            }
+            "type" => {
+                let (name, t) = pair()?;
+                Op::Retype(field(name)?, field_type(t)?)
+            }
+            "nest" => {
+                let (into, fields) = pair()?;
+                let fields = fields
+                    .split('+')
+                    .map(field)
+                    .collect::<Result<Vec<_>, _>>()?;
+                Op::Nest(field(into)?, fields)
+            }
+            _ => {
+                return Err(format!(
+                    "unknown change '{}' (use add, drop, rename, type or nest)",
+                    kind
+                ))
+            }
+        };
+        Ok(op)
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Op::Add(name, t) => format!("add {} ({})", name, t.name()),
+            Op::Drop(name) => format!("drop {}", name),
+            Op::Rename(from, to) => format!("rename {} → {}", from, to),
+            Op::Retype(name, t) => format!("{} becomes {}", name, t.name()),
+            Op::Nest(into, fields) => format!("nest {} into {}", fields.join(", "), into),
+        }
+    }
+
+    fn apply(&self, record: &mut Map<String, Value>, key: u64) {
+        match self {
+            Op::Add(name, t) => insert(record, name, t.draw(name, key)),
+            Op::Drop(name) => {
+                remove(record, name);
+            }
+            Op::Rename(from, to) => {
+                if let Some(v) = remove(record, from) {
+                    insert(record, to, v);
+                }
+            }
+            Op::Retype(name, t) => {
+                if let Some(v) = remove(record, name) {
+                    if let Some(v) = t.convert(&v) {
+                        insert(record, name, v);
+                    }
+                }
+            }
+            Op::Nest(into, fields) => {
+                for f in fields {
+                    if let Some(v) = remove(record, f) {
+                        let leaf = f.rsplit('.').next().unwrap_or(f);
+                        insert(record, &format!("{}.{}", into, leaf), v);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A field path (`status_code`, `k8s.node`); `_timestamp` stays as it is.
+fn field(s: &str) -> Result<String, String> {
+    let s = s.trim();
+    let valid = !s.is_empty()
+        && s.split('.').all(|part| {
+            part.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+    if !valid {
+        return Err(format!("'{}' is not a valid field name", s));
+    }
+    if s == "_timestamp" {
+        return Err("_timestamp cannot be changed".to_string());
+    }
+    Ok(s.to_string())
+}
+
+/// Removes the field at `path`, leaving emptied objects behind.
+fn remove(record: &mut Map<String, Value>, path: &str) -> Option<Value> {
+    match path.split_once('.') {
+        None => record.remove(path),
+        Some((head, rest)) => remove(record.get_mut(head)?.as_object_mut()?, rest),
+    }
+}
+
+/// Sets the field at `path`, creating (or replacing non-object values with)
+/// the objects on the way.
+fn insert(record: &mut Map<String, Value>, path: &str, v: Value) {
+    match path.split_once('.') {
+        None => {
+            record.insert(path.to_string(), v);
+        }
+        Some((head, rest)) => {
+            let parent = record
+                .entry(head)
+                .or_insert_with(|| Value::Object(Map::new()));
+            if !parent.is_object() {
+                *parent = Value::Object(Map::new());
+            }
+            if let Value::Object(parent) = parent {
+                insert(parent, rest, v);
+            }
+        }
+    }
+}
+
+struct Change {
+    at_us: i64,
+    op: Op,
+}
+
+pub struct SchemaChanges {
+    /// In time order, then spec order.
+    changes: Vec<Change>,
+}
+
+impl SchemaChanges {
+    pub fn parse(spec: &str, base_us: i64) -> Result<Self, String> {
+        let bad = |why: String| format!("invalid schema changes '{}': {}", spec, why);
+        let mut changes = Vec::new();
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let (op, at) = part.split_once('@').unwrap_or((part, ""));
+            let at_us = match at.trim() {
+                "" => i64::MIN,
+                at => parse_time_us(at, base_us).map_err(&bad)?,
+            };
+            changes.push(Change {
+                at_us,
+                op: Op::parse(op.trim()).map_err(&bad)?,
+            });
+        }
+        if changes.is_empty() {
+            return Err(bad("no changes given".to_string()));
+        }
+        changes.sort_by_key(|c| c.at_us);
+        Ok(SchemaChanges { changes })
+    }
+
+    pub fn describe(&self) -> String {
+        self.changes
+            .iter()
+            .map(|c| {
+                let at = match c.at_us {
+                    i64::MIN => "from the start".to_string(),
+                    at => format!(
+                        "from {}",
+                        DateTime::from_timestamp_micros(at).unwrap_or_default()
+                    ),
+                };
+                format!("\n  {}: {}", at, c.op.describe())
+            })
+            .collect()
+    }
+
+    /// Whether a record at `ts_us` has any change to apply.
+    pub fn active_at(&self, ts_us: i64) -> bool {
+        self.changes.first().is_some_and(|c| c.at_us <= ts_us)
+    }
+
+    /// Applies the changes due at `ts_us` to `record`, a serialized log
+    /// record. `key` keeps the values of added fields stable per record.
+    pub fn apply(&self, record: &mut Value, ts_us: i64, key: u64) {
+        let Some(record) = record.as_object_mut() else {
+            return;
+        };
+        for c in self.changes.iter().take_while(|c| c.at_us <= ts_us) {
+            c.op.apply(record, key);
+        }
+    }
+}
+
+static SCHEMA_CHANGES: OnceLock<SchemaChanges> = OnceLock::new();
+
+/// Install the `--schema-changes` schedule for this run; relative times are
+/// counted from `base_us`. Without one, records keep their schema.
+pub fn init_schema_changes(
+    spec: Option<&str>,
+    base_us: i64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let Some(spec) = spec else {
+        return Ok(());
+    };
+    let changes = SchemaChanges::parse(spec, base_us)?;
+    println!("Schema changes:{}", changes.describe());
+    SCHEMA_CHANGES
+        .set(changes)
+        .map_err(|_| "schema changes already initialised".into())
+}
+
+pub fn schema_changes() -> Option<&'static SchemaChanges> {
+    SCHEMA_CHANGES.get()
+}
+
+/// Reshapes a raw JSON log record (`ingest`) by the schedule, if any.
+pub fn apply_to_json(record: &mut Value) {
+    let Some(changes) = schema_changes() else {
+        return;
+    };
+    let ts_us = record
+        .get("_timestamp")
+        .and_then(Value::as_i64)
+        .unwrap_or_default();
+    if !changes.active_at(ts_us) {
+        return;
+    }
+    let unique_id = record
+        .get("unique_id")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    let key = record_key(unique_id, ts_us);
+    changes.apply(record, ts_us, key);
+}
+
+/// Per-record key for the values of added fields.
+pub fn record_key(unique_id: &str, ts_us: i64) -> u64 {
+    str_hash(unique_id) ^ ts_us as u64
+}
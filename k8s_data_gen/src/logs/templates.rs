@@ -0,0 +1,101 @@
+//! Parameterized log messages.
+//!
+//! Messages come from templates with `{slot}`s (`MESSAGES_*` in `types.rs`),
+//! filled with fresh values per record, so log pattern extraction has
+//! variable parts to learn around. Each record keeps the ID of its template
+//! in `template_id`, the ground truth to score recovered patterns against.
+//! `--strip-template-id` leaves it out of everything the generator writes or
+//! sends.
+
+use std::sync::OnceLock;
+
+use rand::Rng;
+
+static STRIP_TEMPLATE_ID: OnceLock<bool> = OnceLock::new();
+
+/// Keep or strip `template_id` for this run (`--strip-template-id`).
+pub fn init_template_ids(strip: bool) -> Result<(), Box<dyn std::error::Error>> {
+    STRIP_TEMPLATE_ID
+        .set(strip)
+        .map_err(|_| "template ID mode already initialised".into())
+}
+
+pub fn template_ids_stripped() -> bool {
+    STRIP_TEMPLATE_ID.get().copied().unwrap_or(false)
+}
+
+const SERVICE_ACCOUNTS: &[&str] = &[
+    "ci-deployer",
+    "payments-sync",
+    "inventory-reconciler",
+    "grafana-reader",
+    "backup-agent",
+];
+
+const PATHS: &[&str] = &[
+    "/api/v1/products",
+    "/api/v1/cart",
+    "/api/v1/checkout",
+    "/api/v1/orders",
+    "/api/v1/stock",
+    "/api/v1/payments",
+];
+
+/// A value for `slot`; `None` for unknown slots, which stay as written.
+fn slot_value(slot: &str, rng: &mut impl Rng) -> Option<String> {
+    Some(match slot {
+        "user" => format!("user-{}", rng.gen_range(1_000..100_000)),
+        "ip" => format!(
+            "{}.{}.{}.{}",
+            rng.gen_range(1..224),
+            rng.gen_range(0..256),
+            rng.gen_range(0..256),
+            rng.gen_range(1..255)
+        ),
+        "sa" => SERVICE_ACCOUNTS[rng.gen_range(0..SERVICE_ACCOUNTS.len())].to_string(),
+        "ms" => rng.gen_range(1..800).to_string(),
+        "slow_ms" => rng.gen_range(500..9_000).to_string(),
+        "n" => rng.gen_range(1..10).to_string(),
+        "retry" => rng.gen_range(1..=3).to_string(),
+        "pct" => rng.gen_range(80..100).to_string(),
+        "mem" => (rng.gen_range(1..16) * 128).to_string(),
+        "sku" => format!("SKU-{:05}", rng.gen_range(0..100_000)),
+        "hex" => format!("{:016x}", rng.gen::<u64>()),
+        "key" => match rng.gen_range(0..3) {
+            0 => format!("product:{}", rng.gen_range(1..20_000)),
+            1 => format!("session:{:08x}", rng.gen::<u32>()),
+            _ => format!("cart:user-{}", rng.gen_range(1_000..100_000)),
+        },
+        "path" => PATHS[rng.gen_range(0..PATHS.len())].to_string(),
+        _ => return None,
+    })
+}
+
+/// `template` with every `{slot}` replaced by a fresh value.
+pub fn fill(template: &str, rng: &mut impl Rng) -> String {
+    let mut out = String::with_capacity(template.len() + 16);
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let Some(close) = rest[open..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..open]);
+        let slot = &rest[open + 1..open + close];
+        match slot_value(slot, rng) {
+            Some(value) => out.push_str(&value),
+            None => out.push_str(&rest[open..=open + close]),
+        }
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A message from one of `templates`, and its template ID.
+pub fn pick_message(
+    templates: &[(&'static str, &str)],
+    rng: &mut impl Rng,
+) -> (String, &'static str) {
+    let (id, template) = templates[rng.gen_range(0..templates.len())];
+    (fill(template, rng), id)
+}
@@ -1,6 +1,14 @@
-use serde::Serialize;
+use std::collections::BTreeMap;
 
-#[derive(Debug, Serialize)]
+use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+
+use super::schema::{record_key, schema_changes};
+use super::templates::template_ids_stripped;
+
+/// Serialized through `--schema-changes` (see `schema.rs`); the derived
+/// (de)serializers below are the unchanged schema.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(remote = "Self")]
 pub struct K8sLogRecord {
     pub _timestamp: i64,
     pub cluster: String,
@@ -21,7 +29,100 @@ pub struct K8sLogRecord {
     pub event_type: String,
     pub status_code: u16,
     pub message: String,
+    /// The raw line as the container wrote it, in its native format (see
+    /// `formats.rs`); empty for lines no container wrote.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub log: String,
+    /// ID of the template `message` was filled from (`MESSAGES_*`); left out
+    /// with `--strip-template-id`.
+    #[serde(default, skip_serializing_if = "skip_template_id")]
+    pub template_id: String,
     pub unique_id: String,
+    /// Hex trace/span IDs of the request that produced the line; empty when
+    /// the record is not tied to a trace.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub trace_id: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub span_id: String,
+    /// Set on `exception` lines (`--trace-logs`), as the OpenTelemetry
+    /// `exception.*` attributes.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub exception_type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub exception_message: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub exception_stacktrace: String,
+    /// `--cardinality` stress labels, written as top-level fields.
+    #[serde(flatten)]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl Serialize for K8sLogRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match schema_changes() {
+            Some(changes) if changes.active_at(self._timestamp) => {
+                let mut v = K8sLogRecord::serialize(self, serde_json::value::Serializer)
+                    .map_err(ser::Error::custom)?;
+                let key = record_key(&self.unique_id, self._timestamp);
+                changes.apply(&mut v, self._timestamp, key);
+                v.serialize(serializer)
+            }
+            _ => K8sLogRecord::serialize(self, serializer),
+        }
+    }
+}
+
+impl K8sLogRecord {
+    /// Top-level keys of the unchanged schema, optional fields included.
+    pub fn field_names() -> Vec<String> {
+        // Every field listed, so a new one cannot be missed; the non-empty
+        // strings keep the optional ones from being skipped.
+        let s = || "x".to_string();
+        let full = K8sLogRecord {
+            _timestamp: 0,
+            cluster: s(),
+            namespace: s(),
+            pod: s(),
+            container: s(),
+            node: s(),
+            service: s(),
+            cpu_millicores: 0,
+            memory_mb: 0,
+            network_rx_bytes: 0,
+            network_tx_bytes: 0,
+            restarts: 0,
+            response_time_ms: 0.0,
+            error_rate: 0.0,
+            requests_per_second: 0,
+            log_level: s(),
+            event_type: s(),
+            status_code: 0,
+            message: s(),
+            log: s(),
+            template_id: s(),
+            unique_id: s(),
+            trace_id: s(),
+            span_id: s(),
+            exception_type: s(),
+            exception_message: s(),
+            exception_stacktrace: s(),
+            labels: BTreeMap::new(),
+        };
+        match K8sLogRecord::serialize(&full, serde_json::value::Serializer) {
+            Ok(serde_json::Value::Object(map)) => map.into_iter().map(|(k, _)| k).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for K8sLogRecord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        K8sLogRecord::deserialize(deserializer).map_err(de::Error::custom)
+    }
+}
+
+fn skip_template_id(id: &str) -> bool {
+    id.is_empty() || template_ids_stripped()
 }
 
 pub const LOG_LEVELS_NORMAL: &[(&str, u32)] =
@@ -42,49 +143,104 @@ pub const EVENT_TYPES: &[&str] = &[
     "login",
 ];
 
-pub const MESSAGES_INFO: &[&str] = &[
-    "Processed request successfully",
-    "Cache hit for key",
-    "Health check passed",
-    "Database query completed",
-    "gRPC call returned OK",
-    "Scheduled job ran",
-    "Config reload triggered",
+// Message templates: `(template_id, template)`. `{slot}`s are filled per
+// record by `templates::fill`; the ID travels with the record as its ground
+// truth for log pattern extraction.
+
+pub const MESSAGES_INFO: &[(&str, &str)] = &[
+    ("info-01", "Processed request {path} in {ms}ms"),
+    ("info-02", "Cache hit for key {key}"),
+    ("info-03", "Health check passed in {ms}ms"),
+    ("info-04", "Database query completed in {ms}ms, {n} rows"),
+    ("info-05", "gRPC call {hex} returned OK"),
+    ("info-06", "Scheduled job {hex} ran in {ms}ms"),
+    ("info-07", "Config reload triggered by {user}"),
+    ("info-08", "Reserved {n} units of {sku} for order {hex}"),
+];
+
+pub const MESSAGES_WARN: &[(&str, &str)] = &[
+    ("warn-01", "Slow query detected, took {slow_ms}ms"),
+    ("warn-02", "Retry attempt {retry} of 3 for {path}"),
+    ("warn-03", "Connection pool near capacity: {pct}% in use"),
+    ("warn-04", "Rate limit threshold approaching for {ip}"),
+    ("warn-05", "Certificate expiring in {n} days"),
+    ("warn-06", "Stock for {sku} below threshold: {n} left"),
 ];
 
-pub const MESSAGES_WARN: &[&str] = &[
-    "Slow query detected, took >500ms",
-    "Retry attempt 1 of 3",
-    "Connection pool near capacity",
-    "Rate limit threshold approaching",
-    "Certificate expiring in 14 days",
+pub const MESSAGES_ERROR: &[(&str, &str)] = &[
+    ("error-01", "Upstream service returned 503 for {path}"),
+    ("error-02", "Database connection timeout after {slow_ms}ms"),
+    ("error-03", "Failed to parse response body from {ip}"),
+    (
+        "error-04",
+        "OOMKilled: container exceeded memory limit of {mem}Mi",
+    ),
+    (
+        "error-05",
+        "Panic: index out of bounds: the len is {n} but the index is {n}",
+    ),
+    ("error-06", "TLS handshake failed with {ip}"),
+    ("error-07", "Request queue overflow, dropping request {hex}"),
 ];
 
-pub const MESSAGES_ERROR: &[&str] = &[
-    "Upstream service returned 503",
-    "Database connection timeout",
-    "Failed to parse response body",
-    "OOMKilled: container exceeded memory limit",
-    "Panic: index out of bounds",
-    "TLS handshake failed",
-    "Request queue overflow, dropping request",
+pub const MESSAGES_LOGIN_OK: &[(&str, &str)] = &[
+    ("login-01", "User {user} login successful from {ip}"),
+    ("login-02", "Service account {sa} authenticated"),
+    ("login-03", "OAuth token {hex} validated"),
+    ("login-04", "API key auth successful for {user}"),
 ];
 
-pub const MESSAGES_LOGIN_OK: &[&str] = &[
-    "User login successful",
-    "Service account authenticated",
-    "OAuth token validated",
-    "API key auth successful",
+pub const MESSAGES_LOGIN_ERROR: &[(&str, &str)] = &[
+    (
+        "login-error-01",
+        "login error: invalid credentials for {user}",
+    ),
+    (
+        "login-error-02",
+        "login error: account {user} locked after {n} failures",
+    ),
+    (
+        "login-error-03",
+        "login error: token {hex} expired or revoked",
+    ),
+    (
+        "login-error-04",
+        "login error: IP {ip} blocked after too many attempts",
+    ),
+    (
+        "login-error-05",
+        "login error: MFA verification failed for {user}",
+    ),
+    (
+        "login-error-06",
+        "login error: service account {sa} rejected",
+    ),
+    (
+        "login-error-07",
+        "login error: brute force attempt detected from {ip}",
+    ),
 ];
 
-pub const MESSAGES_LOGIN_ERROR: &[&str] = &[
-    "login error: invalid credentials for user",
-    "login error: account locked after repeated failures",
-    "login error: token expired or revoked",
-    "login error: IP blocked after too many attempts",
-    "login error: MFA verification failed",
-    "login error: service account rejected",
-    "login error: brute force attempt detected",
+/// `(exception.type, exception.message)` for error spans (`--trace-logs`).
+pub const EXCEPTIONS: &[(&str, &str)] = &[
+    (
+        "UpstreamUnavailableError",
+        "upstream returned 503 Service Unavailable",
+    ),
+    (
+        "DeadlineExceededError",
+        "context deadline exceeded after 2000ms",
+    ),
+    ("ConnectionResetError", "connection reset by peer"),
+    (
+        "PoolTimeoutError",
+        "timed out waiting for a database connection",
+    ),
+    (
+        "SerializationError",
+        "failed to decode response body: unexpected EOF",
+    ),
+    ("NullReferenceError", "order.total is null"),
 ];
 
 /// ~0.5% of live records → ~3 login errors/min background noise for RCF training
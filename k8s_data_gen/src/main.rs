@@ -1,27 +1,68 @@
 /// K8s Data Generator — Kubernetes observability data for OpenObserve.
 ///
 /// USAGE:
-///   cargo run -- historical [--days N] [--stream logs|metrics|traces|all]
-///   cargo run -- ingest [FILE] [--org ORG] [--stream STREAM]
-///   cargo run -- live [--stream logs|metrics|traces] [--anomaly TYPE] [--grpc]
+///   cargo run -- historical [--days N] [--stream logs|metrics|traces|audit|all] [--seed N]
+///                           [--start TIME] [--end TIME] [--interval DUR] [--out PATH]
+///                           [--shards N] [--shard-files] [--format json|ndjson]
+///                           [--compress gzip|zstd]
+///   cargo run -- ingest [FILE...] [--org ORG] [--stream STREAM] [--grpc]
+///                       [--otlp json|protobuf] [--remote-write]
+///   cargo run -- live [--stream logs|metrics|traces|audit|all] [--anomaly TYPE] [--grpc] [--seed N]
+///                     [--otlp json|protobuf] [--remote-write] [--serve ADDR]
+///                     [--rate SPEC] [--logs-rate SPEC] [--metrics-rate SPEC] [--traces-rate SPEC]
+///                     [--audit-rate SPEC]
+///   cargo run -- run [--backfill 7d] [--stream logs|metrics|traces|audit|all] [--anomaly TYPE]
+///                    [--seed N] [--rate SPEC ...] [--grpc] [--otlp ...] [--remote-write] [--serve ADDR]
+///                    # backfill straight to O2, then live
 ///   cargo run -- corr          # one-shot correlatable logs+metrics+traces
 ///   cargo run -- repro         # reproduce issue #1848
 ///
-/// ANOMALY TYPES: cpu | memory | errors | restarts | latency | login
+/// GLOBAL FLAGS: --topology FILE (yaml|toml|json, default: built-in 10-service topology)
+///               --profile NAME (connection profile from ~/.config/o2gen/profiles.toml)
+///               --temporality cumulative|delta (OTLP sums and histograms, default: cumulative)
+///               --kube-metrics (add kube-state-metrics, cAdvisor and node-exporter families)
+///               --cardinality SPEC (extra high-cardinality labels on metrics and logs,
+///                                   e.g. user_id=100000:zipf,request_path=500)
+///               --trace-logs (traces also emit span start/end, exception and slow-query
+///                             logs carrying their trace_id and span_id)
+///               --strip-template-id (leave the ground-truth template_id out of log records)
+///               --k8s-events (metrics runs also emit Kubernetes Events to k8s_events)
+///               --schema-changes SPEC (scheduled field adds, drops, renames, type changes
+///                                      and nesting in log records, e.g.
+///                                      type:status_code=string@+10m,rename:pod=pod_name@+20m)
+///
+/// ANOMALY TYPES: cpu | memory | errors | restarts | latency | login | suspicious (audit)
 mod anomaly;
+mod audit;
+mod cardinality;
 mod client;
 mod config;
 mod corr;
+mod events;
+mod historical;
 mod ingest;
+mod lifecycle;
 mod logs;
 mod metrics;
+mod output;
+mod rate;
+mod remote_write_proto;
 mod repro;
+mod run;
 mod topology;
 mod traces;
 mod utils;
 
 use anomaly::AnomalyType;
-use config::{DEFAULT_ORG, DEFAULT_STREAM_LOGS};
+use config::{
+    HISTORICAL_OUT_AUDIT, HISTORICAL_OUT_LOGS, HISTORICAL_OUT_METRICS, HISTORICAL_OUT_TRACES,
+};
+use historical::HistoricalOpts;
+use logs::LogsEncoding;
+use metrics::{CounterStore, Temporality};
+use output::{Compression, OutputFormat};
+use rate::RateSchedule;
+use run::{LiveRates, LiveTransport};
 
 #[tokio::main]
 async fn main() {
@@ -32,21 +73,96 @@ async fn main() {
         std::process::exit(1);
     }
 
+    if let Err(e) = topology::init_topology(parse_flag_str(&args, "--topology").as_deref()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = config::init_profile(parse_flag_str(&args, "--profile").as_deref()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if let Some(t) = parse_flag_str(&args, "--temporality") {
+        let result: Result<(), Box<dyn std::error::Error>> = Temporality::parse(&t)
+            .map_err(Into::into)
+            .and_then(metrics::init_temporality);
+        if let Err(e) = result {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = metrics::init_kube_metrics(args.contains(&"--kube-metrics".to_string())) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = cardinality::init_cardinality(parse_flag_str(&args, "--cardinality").as_deref()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = traces::init_trace_logs(args.contains(&"--trace-logs".to_string())) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = logs::init_template_ids(args.contains(&"--strip-template-id".to_string())) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    if let Err(e) = events::init_k8s_events(args.contains(&"--k8s-events".to_string())) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    // The historical window is parsed ahead of the schedule: relative
+    // --schema-changes times count from its start, and from now in live runs.
+    let historical_opts = (args[1] == "historical").then(|| {
+        let days = parse_flag_u32(&args, "--days").unwrap_or(7);
+        parse_historical_opts(&args, days)
+    });
+    let schema_base_us = match &historical_opts {
+        Some(opts) => opts.start_us,
+        None => chrono::Utc::now().timestamp_micros(),
+    };
+    if let Err(e) = logs::init_schema_changes(
+        parse_flag_str(&args, "--schema-changes").as_deref(),
+        schema_base_us,
+    ) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+
+    // Every run is seeded; without --seed a fresh seed is drawn and printed so the
+    // run can be replayed exactly.
+    let seed_flag = parse_flag_u64(&args, "--seed");
+    let seed = seed_flag.unwrap_or_else(utils::random_seed);
+
     match args[1].as_str() {
         "historical" => {
-            let days = parse_flag_u32(&args, "--days").unwrap_or(7);
             let stream = parse_flag_str(&args, "--stream").unwrap_or_else(|| "logs".to_string());
+            let Some(opts) = historical_opts else {
+                unreachable!("historical options are parsed before the schema changes")
+            };
+            println!("Seed: {} (window: {})", seed, opts.describe());
+            println!("Replay: --seed {} {}", seed, opts.window_args());
 
             let result = match stream.as_str() {
-                "logs" => logs::run_historical_logs(days),
-                "metrics" => metrics::run_historical_metrics(days),
-                "traces" => traces::run_historical_traces(days),
-                "all" => logs::run_historical_logs(days)
-                    .and_then(|_| metrics::run_historical_metrics(days))
-                    .and_then(|_| traces::run_historical_traces(days)),
+                "logs" => logs::run_historical_logs(&opts, seed),
+                "metrics" => metrics::run_historical_metrics(&opts, seed),
+                "traces" => traces::run_historical_traces(&opts, seed),
+                "audit" => audit::run_historical_audit(&opts, seed),
+                "all" => logs::run_historical_logs(&opts.in_out_dir(HISTORICAL_OUT_LOGS), seed)
+                    .and_then(|_| {
+                        metrics::run_historical_metrics(
+                            &opts.in_out_dir(HISTORICAL_OUT_METRICS),
+                            seed,
+                        )
+                    })
+                    .and_then(|_| {
+                        traces::run_historical_traces(&opts.in_out_dir(HISTORICAL_OUT_TRACES), seed)
+                    })
+                    .and_then(|_| {
+                        audit::run_historical_audit(&opts.in_out_dir(HISTORICAL_OUT_AUDIT), seed)
+                    }),
                 other => {
                     eprintln!(
-                        "Unknown stream '{}'. Valid: logs, metrics, traces, all",
+                        "Unknown stream '{}'. Valid: logs, metrics, traces, audit, all",
                         other
                     );
                     std::process::exit(1);
@@ -56,18 +172,27 @@ async fn main() {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
+            cardinality::print_report();
         }
 
         "ingest" => {
-            let file_path = if args.len() > 2 && !args[2].starts_with("--") {
-                args[2].as_str()
-            } else {
-                "../output_k8s.json"
-            };
-            let org = parse_flag_str(&args, "--org").unwrap_or_else(|| DEFAULT_ORG.to_string());
-            let stream = parse_flag_str(&args, "--stream")
-                .unwrap_or_else(|| DEFAULT_STREAM_LOGS.to_string());
-            if let Err(e) = ingest::run_ingest(file_path, &org, &stream).await {
+            // Every positional argument is a file; several are ingested concurrently.
+            let mut file_paths: Vec<String> = args[2..]
+                .iter()
+                .take_while(|a| !a.starts_with("--"))
+                .cloned()
+                .collect();
+            if file_paths.is_empty() {
+                file_paths.push(HISTORICAL_OUT_LOGS.to_string());
+            }
+            let org = parse_flag_str(&args, "--org").unwrap_or_else(config::org);
+            let stream = parse_flag_str(&args, "--stream").unwrap_or_else(config::stream_logs);
+            let use_grpc = args.contains(&"--grpc".to_string());
+            let otlp = parse_otlp(&args);
+            let remote_write = args.contains(&"--remote-write".to_string());
+            let result =
+                ingest::run_ingest(&file_paths, &org, &stream, use_grpc, otlp, remote_write).await;
+            if let Err(e) = result {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
@@ -77,22 +202,53 @@ async fn main() {
             let stream = parse_flag_str(&args, "--stream").unwrap_or_else(|| "logs".to_string());
             let anomaly_type =
                 parse_flag_str(&args, "--anomaly").and_then(|s| AnomalyType::from_str(&s));
-            let use_grpc = args.contains(&"--grpc".to_string());
+            let transport = parse_live_transport(&args);
 
             if let Some(ref s) = parse_flag_str(&args, "--anomaly") {
                 if AnomalyType::from_str(s).is_none() {
-                    eprintln!("Unknown anomaly type '{}'. Valid: cpu, memory, errors, restarts, latency, login", s);
+                    eprintln!("Unknown anomaly type '{}'. Valid: cpu, memory, errors, restarts, latency, login, suspicious", s);
                     std::process::exit(1);
                 }
             }
 
+            let LiveRates {
+                logs: logs_rate,
+                metrics: metrics_rate,
+                traces: traces_rate,
+                audit: audit_rate,
+            } = parse_rates(&args);
+
+            println!("Seed: {}", seed);
+            cardinality::spawn_live_report();
             let result = match stream.as_str() {
-                "logs" => logs::run_live_logs(anomaly_type).await,
-                "metrics" => metrics::run_live_metrics(anomaly_type).await,
-                "traces" if use_grpc => traces::run_live_traces_grpc(anomaly_type).await,
-                "traces" => traces::run_live_traces(anomaly_type).await,
+                "logs" => transport.logs(anomaly_type, seed, logs_rate).await,
+                "metrics" => {
+                    transport
+                        .metrics(anomaly_type, seed, metrics_rate, CounterStore::new())
+                        .await
+                }
+                "traces" => transport.traces(anomaly_type, seed, traces_rate).await,
+                "audit" => audit::run_live_audit(anomaly_type, seed, audit_rate).await,
+                "all" => {
+                    // Each signal keeps its own RNG stream and rate budget.
+                    tokio::try_join!(
+                        transport.logs(anomaly_type.clone(), seed, logs_rate),
+                        transport.metrics(
+                            anomaly_type.clone(),
+                            seed,
+                            metrics_rate,
+                            CounterStore::new()
+                        ),
+                        transport.traces(anomaly_type.clone(), seed, traces_rate),
+                        audit::run_live_audit(anomaly_type.clone(), seed, audit_rate),
+                    )
+                    .map(|_| ())
+                }
                 other => {
-                    eprintln!("Unknown stream '{}'. Valid: logs, metrics, traces", other);
+                    eprintln!(
+                        "Unknown stream '{}'. Valid: logs, metrics, traces, audit, all",
+                        other
+                    );
                     std::process::exit(1);
                 }
             };
@@ -102,6 +258,39 @@ async fn main() {
             }
         }
 
+        "run" => {
+            let stream = parse_flag_str(&args, "--stream").unwrap_or_else(|| "all".to_string());
+            let backfill = parse_flag_str(&args, "--backfill").unwrap_or_else(|| "7d".to_string());
+            let Some(backfill_secs) = utils::parse_duration_secs(&backfill) else {
+                eprintln!("Invalid --backfill '{}'. Examples: 7d, 12h, 90m", backfill);
+                std::process::exit(1);
+            };
+            let anomaly_type =
+                parse_flag_str(&args, "--anomaly").and_then(|s| AnomalyType::from_str(&s));
+            if let Some(ref s) = parse_flag_str(&args, "--anomaly") {
+                if AnomalyType::from_str(s).is_none() {
+                    eprintln!("Unknown anomaly type '{}'. Valid: cpu, memory, errors, restarts, latency, login, suspicious", s);
+                    std::process::exit(1);
+                }
+            }
+            let rates = parse_rates(&args);
+            let transport = parse_live_transport(&args);
+
+            let start_us = chrono::Utc::now().timestamp_micros() - backfill_secs as i64 * 1_000_000;
+            println!(
+                "Seed: {} (backfill from: {})",
+                seed,
+                chrono::DateTime::from_timestamp_micros(start_us).unwrap_or_default()
+            );
+            cardinality::spawn_live_report();
+            let result =
+                run::run_continuous(&stream, start_us, anomaly_type, seed, rates, &transport).await;
+            if let Err(e) = result {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+
         "corr" => {
             if let Err(e) = corr::run_corr().await {
                 eprintln!("Error: {}", e);
@@ -130,38 +319,231 @@ fn parse_flag_u32(args: &[String], flag: &str) -> Option<u32> {
         .and_then(|w| w[1].parse().ok())
 }
 
+fn parse_flag_u64(args: &[String], flag: &str) -> Option<u64> {
+    args.windows(2)
+        .find(|w| w[0] == flag)
+        .and_then(|w| w[1].parse().ok())
+}
+
 fn parse_flag_str(args: &[String], flag: &str) -> Option<String> {
     args.windows(2).find(|w| w[0] == flag).map(|w| w[1].clone())
 }
 
+/// `--start` / `--end` / `--interval` / `--out` / `--shards` / `--shard-files` /
+/// `--format` / `--compress` for `historical`. Without `--start` the window is the `days` before `--end`
+/// (default: now); without `--shards` there is one shard per CPU thread.
+fn parse_historical_opts(args: &[String], days: u32) -> HistoricalOpts {
+    let fail = |e: String| -> ! {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    };
+    let now_us = chrono::Utc::now().timestamp_micros();
+    let time_flag = |flag: &str| {
+        parse_flag_str(args, flag)
+            .map(|s| historical::parse_time_us(&s, now_us).unwrap_or_else(|e| fail(e)))
+    };
+
+    let end_us = time_flag("--end").unwrap_or(now_us);
+    let start_us = time_flag("--start").unwrap_or(end_us - days as i64 * 86_400 * 1_000_000);
+    if start_us >= end_us {
+        fail("--start must be before --end".to_string());
+    }
+    let interval_secs = parse_flag_str(args, "--interval").map(|s| {
+        match utils::parse_duration_secs(&s) {
+            Some(v) if v > 0 => v as i64,
+            _ => fail(format!("invalid --interval '{}' (e.g. 10s, 1m)", s)),
+        }
+    });
+
+    HistoricalOpts {
+        start_us,
+        end_us,
+        interval_secs,
+        out: parse_flag_str(args, "--out"),
+        shards: parse_flag_str(args, "--shards")
+            .map(|s| match s.parse::<usize>() {
+                Ok(n) if n > 0 => n,
+                _ => fail(format!("invalid --shards '{}' (a count of 1 or more)", s)),
+            })
+            .unwrap_or_else(rayon::current_num_threads),
+        shard_files: args.contains(&"--shard-files".to_string()),
+        format: parse_flag_str(args, "--format")
+            .map(|s| OutputFormat::parse(&s).unwrap_or_else(|e| fail(e)))
+            .unwrap_or(OutputFormat::Json),
+        compress: parse_flag_str(args, "--compress")
+            .map(|s| Compression::parse(&s).unwrap_or_else(|e| fail(e)))
+            .unwrap_or(Compression::None),
+    }
+}
+
+/// `--otlp json|protobuf`: send logs to OTLP `/v1/logs` instead of `/_json`.
+fn parse_otlp(args: &[String]) -> Option<LogsEncoding> {
+    parse_flag_str(args, "--otlp").map(|s| {
+        LogsEncoding::parse(&s).unwrap_or_else(|e| {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        })
+    })
+}
+
+fn parse_live_transport(args: &[String]) -> LiveTransport {
+    LiveTransport {
+        grpc: args.contains(&"--grpc".to_string()),
+        otlp: parse_otlp(args),
+        remote_write: args.contains(&"--remote-write".to_string()),
+        serve: parse_flag_str(args, "--serve"),
+    }
+}
+
+/// `--logs-rate` / `--metrics-rate` / `--traces-rate` / `--audit-rate`, each
+/// falling back to `--rate`.
+fn parse_rates(args: &[String]) -> LiveRates {
+    let rate_for = |signal: &str| {
+        let spec = parse_flag_str(args, &format!("--{}-rate", signal))
+            .or_else(|| parse_flag_str(args, "--rate"))?;
+        match RateSchedule::parse(&spec) {
+            Ok(r) => Some(r),
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+    };
+    LiveRates {
+        logs: rate_for("logs"),
+        metrics: rate_for("metrics"),
+        traces: rate_for("traces"),
+        audit: rate_for("audit"),
+    }
+}
+
 fn print_usage() {
     println!("k8s_data_gen — Kubernetes observability data generator\n");
     println!("USAGE:");
-    println!("  cargo run -- historical [--days N] [--stream logs|metrics|traces|all]");
+    println!("  cargo run -- historical [--days N] [--stream logs|metrics|traces|audit|all] [--seed N]");
+    println!("                          [--start TIME] [--end TIME] [--interval DUR] [--out PATH]");
+    println!("                          [--shards N] [--shard-files] [--format json|ndjson]");
+    println!("                          [--compress gzip|zstd]");
     println!("    logs    → ../output_k8s.json");
     println!("    metrics → ../output_k8s_metrics.json");
     println!("    traces  → ../output_k8s_traces.json");
-    println!("    all     → all three\n");
-    println!("  cargo run -- ingest [FILE] [--org ORG] [--stream STREAM]");
+    println!("    audit   → ../output_k8s_audit.json");
+    println!("    all     → logs, metrics, traces and audit (--out names a directory)");
+    println!("    TIME: 2026-03-14T09:30:00Z | 2026-03-14 | now | now-2h | -7d | +1h");
+    println!("    Default window: --days before --end (now); --interval 10s (traces 30s)");
+    println!("    --shards N: parallel time shards (default: CPU threads), merged into one file");
+    println!("    --shard-files: keep output_k8s.0.json, output_k8s.1.json, ... instead");
+    println!("    --format ndjson / --compress gzip|zstd: default names follow, e.g. .ndjson.zst\n");
+    println!("  cargo run -- ingest [FILE...] [--org ORG] [--stream STREAM] [--grpc]");
+    println!("                      [--otlp json|protobuf] [--remote-write]");
+    println!("    several FILEs (e.g. shard files) are uploaded concurrently");
+    println!("    JSON array or NDJSON, plain/gzip/zstd — detected automatically");
     println!("    k8s_logs    → /_json           (stream_type=logs)");
     println!("    k8s_metrics → /v1/metrics OTLP (stream_type=metrics)");
-    println!("    k8s_traces  → /v1/traces OTLP  (stream_type=traces)\n");
-    println!("  cargo run -- live [--stream logs|metrics|traces] [--anomaly TYPE] [--grpc]");
-    println!("    --grpc: use gRPC OTLP for traces (port 5081, prod service flows)\n");
+    println!("    k8s_traces  → /v1/traces OTLP  (stream_type=traces)");
+    println!("    k8s_audit   → /_json           (stream_type=logs)");
+    println!("    --grpc: send logs, metrics and traces through the OTLP gRPC services instead");
+    println!("    --otlp json|protobuf: logs → /v1/logs OTLP instead of /_json");
+    println!("    --remote-write: metrics → Prometheus remote_write (/prometheus/api/v1/write)\n");
+    println!(
+        "  cargo run -- live [--stream logs|metrics|traces|audit|all] [--anomaly TYPE] [--grpc] [--seed N]"
+    );
+    println!("                   [--otlp json|protobuf] [--remote-write] [--serve ADDR]");
+    println!("                   [--rate SPEC] [--logs-rate SPEC] [--metrics-rate SPEC] [--traces-rate SPEC]");
+    println!("                   [--audit-rate SPEC]");
+    println!("    --grpc: use gRPC OTLP (port 5081); traces switch to prod service flows");
+    println!("    --otlp json|protobuf: logs → /v1/logs OTLP instead of /_json");
+    println!("    --remote-write: metrics → Prometheus remote_write instead of OTLP");
+    println!("    --serve :9100: metrics → Prometheus scrape endpoint (text / OpenMetrics)");
+    println!("    --stream audit: API server audit events (audit.k8s.io/v1) → k8s_audit");
+    println!("    --stream all: logs, metrics, traces and audit concurrently\n");
+    println!("  cargo run -- run [--backfill 7d] [--stream logs|metrics|traces|audit|all] [--anomaly TYPE]");
+    println!("                   [--seed N] [--rate SPEC ...] [--grpc] [--otlp json|protobuf]");
+    println!("                   [--remote-write] [--serve ADDR]");
+    println!("    Streams the backfill window oldest-first to OpenObserve over HTTP, then");
+    println!("    continues live with no gap (default: 7d, --stream all). Live flags, the");
+    println!("    transport ones included, apply after the switch.\n");
     println!("  cargo run -- corr");
     println!("    One-shot: correlatable logs+metrics+traces for 3 services.");
-    println!("    All types share service.name+namespace — verifies normal correlation.\n");
+    println!("    All types share service.name+namespace — verifies normal correlation.");
+    println!("    Fixed values stamped with the wall clock; --seed does not apply.\n");
     println!("  cargo run -- repro");
     println!("    Reproduce issue #1848: ECS-style logs (no service.name) + traces");
     println!("    with service.name, same namespace — shows split in Discovered Services.\n");
-    println!("ANOMALY TYPES: cpu | memory | errors | restarts | latency | login\n");
+    println!("GLOBAL FLAGS:");
+    println!("  --topology FILE   load namespaces/services/clusters/nodes from a .yaml,");
+    println!("                    .toml or .json file (see topology.example.yaml).");
+    println!("                    Default: built-in 10-service topology.");
+    println!("  --profile NAME    endpoint/org/credentials/stream names from a named profile");
+    println!("                    in ~/.config/o2gen/profiles.toml (or $O2GEN_PROFILES).");
+    println!("                    O2_* env vars still override individual settings.");
+    println!("  --temporality T   cumulative (default) or delta, for OTLP metric sums and");
+    println!("                    histograms (ingest, live, run).");
+    println!("  --kube-metrics    add kube_pod_*, kube_deployment_*, kube_node_*, cAdvisor");
+    println!("                    container_* and node-exporter node_* families derived from");
+    println!("                    the pod records (ingest, live, run).");
+    println!("  --cardinality SPEC");
+    println!("                    extra labels on every metric and log record, to stress");
+    println!("                    series cardinality (historical, live, run). SPEC is");
+    println!("                    NAME=VALUES[:uniform|zipf|zipf:S], comma-separated; the");
+    println!("                    resulting series counts are reported (live: every minute).");
+    println!("  --trace-logs      traces also emit the log lines of their spans (start/end,");
+    println!("                    exceptions on error spans, slow DB queries) with matching");
+    println!("                    trace_id/span_id, to the logs stream (historical: a");
+    println!("                    *_logs file next to the traces file; live, run).");
+    println!("  --strip-template-id");
+    println!("                    log messages are filled from templates; each record names");
+    println!("                    its template in template_id. This leaves that field out of");
+    println!("                    what is written and sent (historical, ingest, live, run).");
+    println!("  --k8s-events      metrics runs also emit Kubernetes Events (Scheduled, Killing,");
+    println!("                    BackOff, OOMKilling, Unhealthy, ...) following the same pod");
+    println!("                    churn, restarts and anomalies, to the k8s_events stream");
+    println!("                    (historical: a *_events file next to the metrics file;");
+    println!("                    live, run).");
+    println!("  --schema-changes SPEC");
+    println!("                    reshape log records from a point in time on (historical,");
+    println!("                    ingest, live, run; JSON only, not OTLP/gRPC). SPEC is");
+    println!("                    OP@TIME, comma-separated, TIME as for --start (relative");
+    println!("                    times: from the historical window start, else from now):");
+    println!("                    add:NAME=TYPE  drop:NAME  rename:OLD=NEW  type:NAME=TYPE");
+    println!("                    nest:OBJ=F1+F2  (TYPE: string|int|float|bool).\n");
+    println!("ANOMALY TYPES: cpu | memory | errors | restarts | latency | login | suspicious (audit)\n");
+    println!("RATE (live; events/sec — records for logs/metrics, spans for traces,");
+    println!("  API requests for audit):");
+    println!("  --rate 500                     constant 500 eps");
+    println!("  --rate ramp:100..2000/5m       linear ramp over 5 min, then hold");
+    println!("  --rate step:100/1m,500/1m,1000 stages, last one holds");
+    println!("  --rate burst:100,5000/10s@1m   5000 eps for 10 s every minute, else 100");
+    println!("  --logs-rate / --metrics-rate / --traces-rate / --audit-rate override --rate per stream.");
+    println!("  Without a rate: one record per pod/sec, 3 traces per deployment/sec (10/sec with --grpc),");
+    println!("  ~2 API requests per cluster/sec.\n");
+    println!("SEED:");
+    println!("  --seed N makes historical and live output reproducible (values, IDs, anomaly");
+    println!("  timing). Relative --start/--end count from the wall clock, so historical");
+    println!("  prints the absolute window; re-run with that --seed/--start/--end for a");
+    println!("  byte-identical file. Without --seed a random seed is printed for later replay.");
+    println!("  corr and repro ignore --seed: fixed data, wall-clock timestamps.\n");
     println!("EXAMPLES:");
     println!("  cargo run -- historical --days 7 --stream all");
+    println!("  cargo run -- run --backfill 7d --anomaly cpu");
+    println!("  cargo run -- historical --days 7 --stream logs --seed 42");
+    println!("  cargo run -- historical --stream metrics --start -2h --interval 1s --out incident.json");
     println!("  cargo run -- ingest ../output_k8s_metrics.json --stream k8s_metrics");
     println!("  cargo run -- ingest ../output_k8s_traces.json --stream k8s_traces");
     println!("  cargo run -- live --stream metrics --anomaly cpu");
     println!("  cargo run -- live --stream traces --grpc --anomaly latency");
-    println!("  cargo run -- live --stream logs --anomaly login\n");
+    println!("  cargo run -- live --stream traces --trace-logs --anomaly errors");
+    println!("  cargo run -- live --stream metrics --grpc");
+    println!("  cargo run -- live --stream metrics --remote-write");
+    println!("  cargo run -- live --stream metrics --serve :9100 --anomaly cpu");
+    println!("  cargo run -- live --stream metrics --kube-metrics --anomaly restarts");
+    println!("  cargo run -- live --stream metrics --k8s-events --anomaly memory");
+    println!("  cargo run -- live --stream metrics --remote-write --cardinality user_id=100000:zipf");
+    println!("  cargo run -- live --stream logs --anomaly login");
+    println!("  cargo run -- live --stream logs --otlp protobuf");
+    println!("  cargo run -- live --stream logs --schema-changes type:status_code=string@+5m");
+    println!("  cargo run -- live --stream audit --anomaly suspicious");
+    println!("  cargo run -- live --stream all --logs-rate 5000 --traces-rate ramp:100..2000/10m\n");
     println!("ANOMALY DETECTION CONFIGS:");
     println!("  Logs/CPU:           k8s_logs    → logs    → custom SQL AVG(cpu_millicores)");
     println!("  Logs/Errors:        k8s_logs    → logs    → count(*) filter log_level=ERROR");
@@ -174,4 +556,6 @@ fn print_usage() {
     println!("  Metrics/ErrorRate:  error_rate         → metrics → avg(value)");
     println!("  Traces/Latency:     k8s_traces  → traces  → avg(duration_ms)");
     println!("  Traces/Errors:      k8s_traces  → traces  → count(*) filter status=ERROR");
+    println!("  Audit/Suspicious:   k8s_audit   → logs    → count(*) filter objectref_resource='secrets'");
+    println!("                                              and responsestatus_code=403");
 }
@@ -0,0 +1,108 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+
+use super::counters::CounterStore;
+use super::generate::generate_metric_record;
+use super::otlp::MetricsEncoder;
+use crate::client::http::post_batch;
+use crate::config::{api_base, org, stream_events, INGEST_BATCH_SIZE, INTERVAL_SECONDS};
+use crate::events::{k8s_events_enabled, EventRecorder};
+use crate::lifecycle::pods_at;
+use crate::utils::seeded_rng;
+
+/// Streams historical metrics oldest-first from `start_us` as OTLP until the
+/// cursor passes the wall clock, integrating the running totals per pod.
+/// Returns the counters, so the live loop continues exactly where this stops.
+pub async fn run_backfill_metrics(
+    start_us: i64,
+    seed: u64,
+) -> Result<CounterStore, Box<dyn std::error::Error>> {
+    let api_url = format!("{}/api/{}/v1/metrics", api_base(), org());
+    let events_url = format!("{}/api/{}/{}/_json", api_base(), org(), stream_events());
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    let mut rng = seeded_rng(seed);
+    // Same as `ingest`: each record expands to one OTLP data point per field.
+    let batch_size = 100;
+    let mut encoder = MetricsEncoder::new();
+    let mut counters = CounterStore::new();
+
+    println!(
+        "Backfill metrics (OTLP) → {} from {}",
+        api_url,
+        DateTime::from_timestamp_micros(start_us).unwrap_or_default()
+    );
+
+    let mut ts_us = start_us;
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut sent = 0usize;
+    // `--k8s-events`, posted to their own stream as they accumulate.
+    let mut events = k8s_events_enabled().then(EventRecorder::new);
+    let mut event_batch = Vec::new();
+    let mut events_sent = 0usize;
+    while ts_us <= Utc::now().timestamp_micros() {
+        let pods = pods_at(ts_us, seed);
+        counters.retain(|pod| pods.iter().any(|p| p.name == pod));
+        let records: Vec<_> = pods
+            .iter()
+            .map(|pod| {
+                let mut r = generate_metric_record(pod, ts_us, None, &mut rng);
+                counters.apply(&mut r);
+                r
+            })
+            .collect();
+        if let Some(ref mut events) = events {
+            let prev_us = ts_us - INTERVAL_SECONDS * 1_000_000;
+            event_batch.extend(events.events(prev_us, ts_us, seed, &records));
+            if event_batch.len() >= INGEST_BATCH_SIZE {
+                post_batch(
+                    &client,
+                    &events_url,
+                    None,
+                    &serde_json::to_value(&event_batch)?,
+                )
+                .await?;
+                events_sent += event_batch.len();
+                event_batch.clear();
+            }
+        }
+        batch.extend(records);
+        if batch.len() >= batch_size {
+            post_batch(&client, &api_url, None, &encoder.encode(&batch)).await?;
+            sent += batch.len();
+            batch.clear();
+            if (sent / batch_size).is_multiple_of(500) {
+                println!(
+                    "[backfill metrics] {} records, up to {}",
+                    sent,
+                    DateTime::from_timestamp_micros(ts_us).unwrap_or_default()
+                );
+            }
+        }
+        ts_us += INTERVAL_SECONDS * 1_000_000;
+    }
+    if !batch.is_empty() {
+        post_batch(&client, &api_url, None, &encoder.encode(&batch)).await?;
+        sent += batch.len();
+    }
+    if !event_batch.is_empty() {
+        post_batch(
+            &client,
+            &events_url,
+            None,
+            &serde_json::to_value(&event_batch)?,
+        )
+        .await?;
+        events_sent += event_batch.len();
+    }
+    if events_sent > 0 {
+        println!("[backfill metrics] {} Kubernetes events", events_sent);
+    }
+
+    println!(
+        "[backfill metrics] done: {} records — switching to live",
+        sent
+    );
+    Ok(counters)
+}
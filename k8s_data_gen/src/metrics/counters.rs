@@ -0,0 +1,150 @@
+//! Per-pod counter state: `container_cpu_time`, `requests_total` and the
+//! network byte totals.
+//!
+//! Every path keeps real state. A pod's counters start from the closed-form
+//! value of `generate.rs` the first time it is seen (no jump at `run`'s
+//! handover), then grow by the record's own rates × the time since its
+//! previous point. A record with a restart resets them to zero, as a restarted
+//! container's counters do, and gives the pod a new `container_id`.
+//!
+//! Live runs and `run`'s backfill emit each pod in time order and feed one
+//! store. `historical` generates newest-first in parallel shards, so it first
+//! runs fixed stretches of the window on their own, with the pods carried over
+//! from the previous stretch starting at zero ([`CounterStore::carrying`]).
+//! Chaining those ([`CounterStore::follow`]) gives the exact state at the start
+//! of every stretch, from which each is then regenerated in time order.
+
+use std::collections::HashMap;
+
+use super::types::K8sMetricRecord;
+use crate::utils::container_id;
+
+/// `started_secs` of a pod carried over from the previous stretch whose
+/// container has not restarted in this one: its totals are increments.
+const CARRIED: i64 = i64::MIN;
+
+/// Running totals of one pod's current container.
+#[derive(Clone)]
+struct PodCounters {
+    last_us: i64,
+    /// Unix seconds the current container started (the counters' zero).
+    started_secs: i64,
+    cpu_secs: f64,
+    requests: f64,
+    rx_bytes: f64,
+    tx_bytes: f64,
+}
+
+impl PodCounters {
+    fn seed(r: &K8sMetricRecord) -> Self {
+        PodCounters {
+            last_us: r._timestamp,
+            started_secs: r.pod_start_time,
+            cpu_secs: r.container_cpu_time,
+            requests: r.requests_total as f64,
+            rx_bytes: r.network_rx_bytes_total as f64,
+            tx_bytes: r.network_tx_bytes_total as f64,
+        }
+    }
+
+    /// Adds `secs` at the record's current rates.
+    fn add(&mut self, r: &K8sMetricRecord, secs: f64) {
+        self.cpu_secs += r.cpu_millicores as f64 / 1000.0 * secs;
+        self.requests += r.requests_per_second * secs;
+        self.rx_bytes += r.network_rx_bytes_per_sec as f64 * secs;
+        self.tx_bytes += r.network_tx_bytes_per_sec as f64 * secs;
+    }
+}
+
+/// Integrates `container_cpu_time`, `requests_total` and the network byte
+/// totals per pod. Use one per live loop or backfill.
+#[derive(Default, Clone)]
+pub struct CounterStore {
+    pods: HashMap<String, PodCounters>,
+}
+
+impl CounterStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overwrites `r`'s running totals (and `pod_start_time`, the counters'
+    /// start, and the container ID) with the pod's integrated state, advanced
+    /// to `r._timestamp`.
+    /// Points at or before the pod's last one leave the state as it is.
+    pub fn apply(&mut self, r: &mut K8sMetricRecord) {
+        let c = self
+            .pods
+            .entry(r.pod.clone())
+            .or_insert_with(|| PodCounters::seed(r));
+        if r._timestamp > c.last_us {
+            let secs = (r._timestamp - c.last_us) as f64 / 1_000_000.0;
+            if r.restarts > 0 {
+                // The container restarted somewhere in the interval; take the
+                // midpoint and count only what the new container did since.
+                let restart_us = r._timestamp - (r._timestamp - c.last_us) / 2;
+                c.started_secs = restart_us.div_euclid(1_000_000);
+                c.cpu_secs = 0.0;
+                c.requests = 0.0;
+                c.rx_bytes = 0.0;
+                c.tx_bytes = 0.0;
+                c.add(r, secs / 2.0);
+            } else {
+                c.add(r, secs);
+            }
+            c.last_us = r._timestamp;
+        }
+
+        r.pod_start_time = c.started_secs;
+        r.container_id = container_id(&r.cluster, &r.pod, c.started_secs as u64);
+        r.container_cpu_time = (c.cpu_secs * 1000.0).round() / 1000.0;
+        r.requests_total = c.requests.round() as u64;
+        r.network_rx_bytes_total = c.rx_bytes.round() as u64;
+        r.network_tx_bytes_total = c.tx_bytes.round() as u64;
+    }
+
+    /// A store for a stretch of points that continues another: `pods`, last
+    /// seen at `last_us`, start from zero and only count what the stretch adds.
+    pub fn carrying(pods: impl IntoIterator<Item = String>, last_us: i64) -> Self {
+        let pods = pods
+            .into_iter()
+            .map(|pod| {
+                let c = PodCounters {
+                    last_us,
+                    started_secs: CARRIED,
+                    cpu_secs: 0.0,
+                    requests: 0.0,
+                    rx_bytes: 0.0,
+                    tx_bytes: 0.0,
+                };
+                (pod, c)
+            })
+            .collect();
+        CounterStore { pods }
+    }
+
+    /// Advances this store past `next`, a [`carrying`](Self::carrying) store
+    /// run over the stretch that follows. Pods absent from `next` are gone.
+    pub fn follow(&mut self, next: &CounterStore) {
+        self.pods.retain(|pod, _| next.pods.contains_key(pod));
+        for (pod, n) in &next.pods {
+            match self.pods.get_mut(pod) {
+                Some(c) if n.started_secs == CARRIED => {
+                    c.last_us = n.last_us;
+                    c.cpu_secs += n.cpu_secs;
+                    c.requests += n.requests;
+                    c.rx_bytes += n.rx_bytes;
+                    c.tx_bytes += n.tx_bytes;
+                }
+                _ => {
+                    self.pods.insert(pod.clone(), n.clone());
+                }
+            }
+        }
+    }
+
+    /// Drops the state of pods for which `keep` is false.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.pods.retain(|pod, _| keep(pod));
+    }
+}
@@ -1,20 +1,24 @@
 use rand::Rng;
+use rand_distr::{Distribution, LogNormal};
 
-use super::types::K8sMetricRecord;
+use super::types::{K8sMetricRecord, LatencyHistogram};
 use crate::anomaly::{AnomalyState, AnomalyType};
+use crate::cardinality::{extra_labels, Stream};
 use crate::config::NODE_MEMORY_MB;
-use crate::topology::{CLUSTERS, NODES, PODS};
-use crate::utils::{daily_seasonal, pod_name};
+use crate::lifecycle::PodRef;
+use crate::topology::topology;
+use crate::utils::{container_id, daily_seasonal, daily_seasonal_integral};
+
+/// Caps per-record latency samples for the busiest pods.
+const MAX_LATENCY_SAMPLES: usize = 500;
 
 pub fn generate_metric_record(
-    pod_idx: usize,
+    pod_ref: &PodRef,
     timestamp_us: i64,
     anomaly: Option<&AnomalyState>,
     rng: &mut impl Rng,
 ) -> K8sMetricRecord {
-    let pod = &PODS[pod_idx];
-    let cluster = CLUSTERS[rng.gen_range(0..CLUSTERS.len())];
-    let node = NODES[pod_idx % NODES.len()];
+    let pod = &topology().pods[pod_ref.svc_idx];
 
     let is_anomaly = anomaly.map(|a| a.is_active()).unwrap_or(false);
     let anomaly_type = anomaly.map(|a| &a.anomaly_type);
@@ -52,24 +56,43 @@ pub fn generate_metric_record(
         0
     };
 
-    let rps = (pod.base_rps as f64 * season * (1.0 + rng.gen_range(-0.20_f64..=0.20))).max(0.0);
+    let rps = (pod.base_rps as f64 / pod.replicas as f64
+        * season
+        * (1.0 + rng.gen_range(-0.20_f64..=0.20)))
+    .max(0.0);
     let net_rx = (rps as u64).saturating_mul(rng.gen_range(800..1200));
     let net_tx = (rps as u64).saturating_mul(rng.gen_range(400..800));
 
-    // cumulative CPU time: base offset + elapsed seconds * cpu rate
-    // gives a realistic monotonically increasing value across ticks
+    // cumulative CPU time since the pod started: base cores × seasonal time
+    // elapsed. A pure function of (pod, time): the starting value for a pod's
+    // counters the first time it is seen, integrated from there by
+    // `counters.rs` in every mode.
     let epoch_secs = timestamp_us / 1_000_000;
-    let cpu_rate = cpu_mc as f64 / 1000.0; // cores
-    let base_offset = (pod_idx as f64 * 1000.0) + 10.0;
-    let container_cpu_time = ((base_offset + epoch_secs as f64 * cpu_rate) * 1000.0).round() / 1000.0;
-    let container_id = format!("{:016x}{:016x}", pod_idx as u64 * 0xdeadbeef, epoch_secs as u64);
+    let cpu_cores = pod.base_cpu as f64 / 1000.0;
+    let running_since = pod_ref.started_secs.min(epoch_secs);
+    let cpu_secs = cpu_cores * daily_seasonal_integral(running_since, epoch_secs, 0.25);
+    let container_cpu_time = (cpu_secs * 1000.0).round() / 1000.0;
+    // Request and byte counters follow the same seasonal curve as rps; the
+    // per-request byte sizes average the ranges used for the rates above.
+    let rps_base = pod.base_rps as f64 / pod.replicas as f64;
+    let requests_total = rps_base * daily_seasonal_integral(running_since, epoch_secs, 0.25);
+
+    // One latency per request served in the last second, log-normal around
+    // the record's average latency (sigma 0.5 gives a realistic right tail).
+    let n_samples = (rps.round() as usize).min(MAX_LATENCY_SAMPLES);
+    let sigma = 0.5;
+    let latency_dist = LogNormal::new(latency.ln() - sigma * sigma / 2.0, sigma)
+        .expect("latency is positive");
+    let samples: Vec<f64> = (0..n_samples)
+        .map(|_| ((latency_dist.sample(rng) * 10.0).round() / 10.0).max(0.1))
+        .collect();
 
     K8sMetricRecord {
         _timestamp: timestamp_us,
-        cluster: cluster.to_string(),
+        cluster: pod_ref.cluster.to_string(),
         namespace: pod.namespace.to_string(),
-        pod: pod_name(pod_idx),
-        node: node.to_string(),
+        pod: pod_ref.name.clone(),
+        node: pod_ref.node.to_string(),
         service: pod.service.to_string(),
         cpu_millicores: cpu_mc,
         cpu_percent: ((cpu_mc as f64 / 10.0) * 100.0).round() / 100.0,
@@ -82,7 +105,14 @@ pub fn generate_metric_record(
         error_rate: (error_rate * 10000.0).round() / 10000.0,
         restarts,
         container_cpu_time,
-        container_id,
+        pod_start_time: running_since,
+        // One ID per container: it changes when the pod's container restarts.
+        container_id: container_id(pod_ref.cluster, &pod_ref.name, running_since as u64),
         total_payment_req: (rps * season * rng.gen_range(0.8..1.2) * 10.0).round() as u64,
+        requests_total: requests_total.round() as u64,
+        network_rx_bytes_total: (requests_total * 1000.0).round() as u64,
+        network_tx_bytes_total: (requests_total * 600.0).round() as u64,
+        request_latency_histogram: LatencyHistogram::from_samples(&samples),
+        labels: extra_labels(Stream::Metrics, pod_ref.cluster, &pod_ref.name, rng),
     }
 }
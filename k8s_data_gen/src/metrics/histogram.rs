@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use super::types::LatencyHistogram;
+
+/// Explicit bucket upper bounds (ms) for `request_latency_histogram`.
+pub const LATENCY_BOUNDS_MS: [f64; 14] = [
+    5.0, 10.0, 25.0, 50.0, 75.0, 100.0, 250.0, 500.0, 750.0, 1000.0, 2500.0, 5000.0, 7500.0,
+    10000.0,
+];
+
+/// Histograms cover the requests of the second before each record.
+pub const HISTOGRAM_WINDOW_NS: i64 = 1_000_000_000;
+
+/// Exponential histogram scale: bucket boundaries grow by 2^(2^-3) ≈ 9%.
+pub const LATENCY_EXP_SCALE: i32 = 3;
+
+/// Index of the exponential bucket holding `v` (> 0): bucket `i` covers
+/// `(base^i, base^(i+1)]` with `base = 2^(2^-scale)`.
+fn exp_index(v: f64, scale: i32) -> i32 {
+    ((v.log2() * 2f64.powi(scale)).ceil() - 1.0) as i32
+}
+
+impl LatencyHistogram {
+    pub fn from_samples(samples: &[f64]) -> Self {
+        let mut h = LatencyHistogram {
+            bucket_counts: vec![0; LATENCY_BOUNDS_MS.len() + 1],
+            exp_scale: LATENCY_EXP_SCALE,
+            ..Default::default()
+        };
+        if samples.is_empty() {
+            return h;
+        }
+
+        let indexes: Vec<i32> = samples
+            .iter()
+            .map(|&v| exp_index(v, LATENCY_EXP_SCALE))
+            .collect();
+        let lo = *indexes.iter().min().unwrap_or(&0);
+        let hi = *indexes.iter().max().unwrap_or(&0);
+        h.exp_offset = lo;
+        h.exp_counts = vec![0; (hi - lo + 1) as usize];
+        h.min = f64::MAX;
+
+        for (&v, &idx) in samples.iter().zip(&indexes) {
+            let bucket = LATENCY_BOUNDS_MS
+                .iter()
+                .position(|&b| v <= b)
+                .unwrap_or(LATENCY_BOUNDS_MS.len());
+            h.bucket_counts[bucket] += 1;
+            h.exp_counts[(idx - lo) as usize] += 1;
+            h.count += 1;
+            h.sum += v;
+            h.min = h.min.min(v);
+            h.max = h.max.max(v);
+        }
+        h.sum = (h.sum * 10.0).round() / 10.0;
+        h
+    }
+
+    /// Adds `other`'s observations (same bounds and scale) into `self`.
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = other.clone();
+            return;
+        }
+        for (a, b) in self.bucket_counts.iter_mut().zip(&other.bucket_counts) {
+            *a += b;
+        }
+
+        let lo = self.exp_offset.min(other.exp_offset);
+        let hi = (self.exp_offset + self.exp_counts.len() as i32)
+            .max(other.exp_offset + other.exp_counts.len() as i32);
+        let mut counts = vec![0; (hi - lo) as usize];
+        for (off, src) in [
+            (self.exp_offset, &self.exp_counts),
+            (other.exp_offset, &other.exp_counts),
+        ] {
+            for (i, c) in src.iter().enumerate() {
+                counts[(off - lo) as usize + i] += c;
+            }
+        }
+        self.exp_offset = lo;
+        self.exp_counts = counts;
+
+        self.count += other.count;
+        self.sum = ((self.sum + other.sum) * 10.0).round() / 10.0;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+}
+
+/// Running per-pod latency histograms, for cumulative exports.
+#[derive(Default)]
+pub struct CumulativeHistograms {
+    /// (start ns, last time ns, running histogram) per pod.
+    totals: HashMap<String, (i64, i64, LatencyHistogram)>,
+}
+
+impl CumulativeHistograms {
+    /// Adds the window ending at `ts_ns` to `pod`'s total. Returns the series
+    /// start time and the total so far.
+    pub fn add(
+        &mut self,
+        pod: &str,
+        ts_ns: i64,
+        window: &LatencyHistogram,
+    ) -> (i64, LatencyHistogram) {
+        let entry = self
+            .totals
+            .entry(pod.to_string())
+            .or_insert_with(|| (ts_ns - HISTOGRAM_WINDOW_NS, ts_ns, window.clone()));
+        if entry.1 < ts_ns {
+            entry.1 = ts_ns;
+            entry.2.merge(window);
+        } else if entry.1 > ts_ns {
+            // Out of order: start the running total over at this point.
+            *entry = (ts_ns - HISTOGRAM_WINDOW_NS, ts_ns, window.clone());
+        }
+        (entry.0, entry.2.clone())
+    }
+
+    /// Drops the totals of pods for which `keep` is false.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.totals.retain(|pod, _| keep(pod));
+    }
+}
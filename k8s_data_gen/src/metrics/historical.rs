@@ -1,61 +1,171 @@
-use std::{
-    fs::File,
-    io::{BufWriter, Write},
-};
-
-use chrono::Utc;
+use rand::rngs::StdRng;
+use rayon::prelude::*;
 
+use super::counters::CounterStore;
 use super::generate::generate_metric_record;
-use crate::config::{CHUNK_SIZE, INTERVAL_SECONDS};
-use crate::topology::PODS;
+use super::types::K8sMetricRecord;
+use crate::config::{stream_events, stream_metrics, HISTORICAL_OUT_METRICS, INTERVAL_SECONDS};
+use crate::events::{k8s_events_enabled, EventRecorder};
+use crate::historical::{
+    companion_path, interval_rng, print_done, write_sharded_with, HistoricalOpts,
+};
+use crate::lifecycle::pods_at;
+use crate::topology::topology;
+
+/// Intervals per stretch of the counter pre-pass: an hour at the default 10s.
+const STRETCH_INTERVALS: i64 = 360;
 
-pub fn run_historical_metrics(days: u32) -> Result<(), Box<dyn std::error::Error>> {
-    let output_path = "../output_k8s_metrics.json";
-    let num_pods = PODS.len();
-    let total_intervals = (days as i64 * 86_400) / INTERVAL_SECONDS;
+pub fn run_historical_metrics(
+    opts: &HistoricalOpts,
+    seed: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_path = opts.out_or(HISTORICAL_OUT_METRICS);
+    let interval_secs = opts.interval_secs_or(INTERVAL_SECONDS);
+    let num_pods = topology().pod_count();
+    let total_intervals = opts.intervals(interval_secs);
     let total_records = total_intervals as usize * num_pods;
 
     println!(
-        "Historical metrics: {} days, {} pods → {}",
-        days, num_pods, output_path
+        "Historical metrics: {} every {}s, {} pods → {}",
+        opts.describe(),
+        interval_secs,
+        num_pods,
+        output_path
     );
     println!("Total records: {}", total_records);
 
-    let file = File::create(output_path)?;
-    let mut writer = BufWriter::new(file);
-    let mut rng = rand::thread_rng();
-    let now_us = Utc::now().timestamp_micros();
-
-    writer.write_all(b"[")?;
-    let mut first = true;
-    let mut written = 0usize;
-
-    for interval_idx in 0..total_intervals as usize {
-        let ts_us = now_us - (interval_idx as i64 * INTERVAL_SECONDS * 1_000_000);
-        for pod_idx in 0..num_pods {
-            let record = generate_metric_record(pod_idx, ts_us, None, &mut rng);
-            let json = serde_json::to_string(&record)?;
-            if !first {
-                writer.write_all(b",")?;
+    let counters = Counters::prepare(opts, interval_secs, seed);
+    let (written, files) =
+        write_sharded_with(opts, &output_path, interval_secs, seed, || counters.shard())?;
+    print_done(written, "records", &files, Some(&stream_metrics()));
+
+    if k8s_events_enabled() {
+        // A second pass over the same interval RNGs regenerates the same records;
+        // this time only the events they show are kept.
+        let events_path = companion_path(&output_path, "events");
+        println!("\nKubernetes events → {}", events_path);
+        let (written, files) = write_sharded_with(opts, &events_path, interval_secs, seed, || {
+            let mut records = counters.shard();
+            move |ts_us, rng: &mut StdRng| {
+                let records = records(ts_us, rng);
+                let prev_us = ts_us - interval_secs * 1_000_000;
+                EventRecorder::new().events(prev_us, ts_us, seed, &records)
             }
-            writer.write_all(json.as_bytes())?;
-            first = false;
-            written += 1;
-        }
-        if written % CHUNK_SIZE == 0 {
-            writer.flush()?;
-            println!(
-                "Progress: {:.1}% ({}/{})",
-                written as f64 / total_records as f64 * 100.0,
-                written,
-                total_records
-            );
+        })?;
+        print_done(written, "events", &files, Some(&stream_events()));
+    }
+    Ok(())
+}
+
+fn interval_records(ts_us: i64, seed: u64, rng: &mut StdRng) -> Vec<K8sMetricRecord> {
+    pods_at(ts_us, seed)
+        .iter()
+        .map(|pod| generate_metric_record(pod, ts_us, None, rng))
+        .collect()
+}
+
+/// Integrated counters for a window generated newest-first in shards.
+///
+/// The window is cut into stretches of [`STRETCH_INTERVALS`] (stretch 0 is
+/// the newest), independent of `--shards`. A parallel pre-pass runs each one
+/// on its own and chains the results into the counter state at the start of
+/// every stretch; shards then regenerate their stretches in time order from
+/// there and hand the intervals out newest-first.
+struct Counters<'a> {
+    opts: &'a HistoricalOpts,
+    interval_secs: i64,
+    seed: u64,
+    /// State at the start (oldest edge) of each stretch.
+    starts: Vec<CounterStore>,
+}
+
+impl<'a> Counters<'a> {
+    fn prepare(opts: &'a HistoricalOpts, interval_secs: i64, seed: u64) -> Self {
+        let mut counters = Counters {
+            opts,
+            interval_secs,
+            seed,
+            starts: Vec::new(),
+        };
+        let total_intervals = opts.intervals(interval_secs);
+        let stretches = (total_intervals + STRETCH_INTERVALS - 1) / STRETCH_INTERVALS;
+        let runs: Vec<CounterStore> = (0..stretches)
+            .into_par_iter()
+            .map(|stretch| {
+                let (_, oldest) = counters.stretch_range(stretch);
+                let mut store = if oldest + 1 < total_intervals {
+                    let prev_us = counters.ts_us(oldest + 1);
+                    let pods = pods_at(prev_us, seed).into_iter().map(|p| p.name);
+                    CounterStore::carrying(pods, prev_us)
+                } else {
+                    CounterStore::new()
+                };
+                counters.run_stretch(stretch, &mut store);
+                store
+            })
+            .collect();
+
+        let mut state = CounterStore::new();
+        let mut starts = vec![CounterStore::new(); runs.len()];
+        for (start, run) in starts.iter_mut().zip(&runs).rev() {
+            *start = state.clone();
+            state.follow(run);
         }
+        counters.starts = starts;
+        counters
     }
 
-    writer.write_all(b"]")?;
-    writer.flush()?;
-    println!("\nDone! {} records → '{}'", written, output_path);
-    println!("Ingest: cargo run -- ingest ../output_k8s_metrics.json --stream k8s_metrics");
-    Ok(())
+    fn ts_us(&self, interval_idx: i64) -> i64 {
+        self.opts.end_us - interval_idx * self.interval_secs * 1_000_000
+    }
+
+    /// Newest and oldest interval index of `stretch`.
+    fn stretch_range(&self, stretch: i64) -> (i64, i64) {
+        let newest = stretch * STRETCH_INTERVALS;
+        let oldest = (newest + STRETCH_INTERVALS).min(self.opts.intervals(self.interval_secs)) - 1;
+        (newest, oldest)
+    }
+
+    /// The records of `stretch`, oldest interval first, with `store`'s
+    /// counters applied.
+    fn run_stretch(
+        &self,
+        stretch: i64,
+        store: &mut CounterStore,
+    ) -> Vec<(i64, Vec<K8sMetricRecord>)> {
+        let (newest, oldest) = self.stretch_range(stretch);
+        (newest..=oldest)
+            .rev()
+            .map(|idx| {
+                let ts_us = self.ts_us(idx);
+                let mut records =
+                    interval_records(ts_us, self.seed, &mut interval_rng(self.seed, idx));
+                for r in &mut records {
+                    store.apply(r);
+                }
+                (idx, records)
+            })
+            .collect()
+    }
+
+    /// Generator for one shard, for [`write_sharded_with`]. Each interval's
+    /// records come from its stretch, regenerated from the stretch's start
+    /// state, so the RNG handed in is not needed.
+    fn shard(&self) -> impl FnMut(i64, &mut StdRng) -> Vec<K8sMetricRecord> + '_ {
+        let mut pending: Vec<(i64, Vec<K8sMetricRecord>)> = Vec::new();
+        move |ts_us, _| {
+            let idx = (self.opts.end_us - ts_us) / (self.interval_secs * 1_000_000);
+            if pending.first().is_none_or(|&(oldest, _)| oldest < idx) {
+                let stretch = idx / STRETCH_INTERVALS;
+                pending = self.run_stretch(stretch, &mut self.starts[stretch as usize].clone());
+            }
+            while pending.last().is_some_and(|&(newest, _)| newest < idx) {
+                pending.pop();
+            }
+            match pending.pop() {
+                Some((_, records)) => records,
+                None => Vec::new(),
+            }
+        }
+    }
 }
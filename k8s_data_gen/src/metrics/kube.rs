@@ -0,0 +1,700 @@
+//! kube-state-metrics, cAdvisor and node-exporter families (`--kube-metrics`).
+//!
+//! Derived from the pod records and the topology, with the names and labels
+//! the real exporters use, so prebuilt Kubernetes dashboards work against
+//! generated data:
+//!
+//!   - per pod: `kube_pod_*` (info, phase, readiness, container status and
+//!     restarts, resource requests/limits) and cAdvisor `container_*` (CPU,
+//!     memory, network), following the record's values and its anomalies
+//!   - per deployment: `kube_deployment_*` replica counts
+//!   - per node: `kube_node_*` and node-exporter `node_*`
+//!
+//! A record with a restart marks its container as crash-looping for that point
+//! (`CrashLoopBackOff`, not ready, deployment replica unavailable), which is
+//! what the `restarts` anomaly looks like on a dashboard.
+//!
+//! Deployment and node samples need every pod of a timestamp. They go out as
+//! soon as a timestamp has as many pods as the topology runs, or once a later
+//! batch no longer carries it, so batch boundaries in `ingest` and `run` do not
+//! split them.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::OnceLock;
+
+use super::types::K8sMetricRecord;
+use crate::config::{NODE_CPU_CORES, NODE_MEMORY_MB};
+use crate::topology::topology;
+use crate::utils::{daily_seasonal_integral, mix64, str_hash};
+
+const MIB: f64 = 1024.0 * 1024.0;
+/// Nodes reboot once per period, at a per-node offset.
+const NODE_UPTIME_PERIOD_SECS: i64 = 30 * 86_400;
+/// Node CPU busy share cap, so idle time keeps growing at the daily peak.
+const NODE_MAX_BUSY: f64 = 0.7;
+const NODE_RESERVED_BYTES: f64 = 512.0 * MIB;
+const NODE_MAX_PODS: f64 = 110.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(super) enum FamilyKind {
+    Gauge,
+    Counter,
+}
+
+/// One exported family. Counter names keep their `_total` suffix.
+pub(super) struct Family {
+    pub name: &'static str,
+    pub help: &'static str,
+    /// UCUM unit for OTLP.
+    pub unit: &'static str,
+    pub kind: FamilyKind,
+}
+
+const fn gauge(name: &'static str, help: &'static str, unit: &'static str) -> Family {
+    Family {
+        name,
+        help,
+        unit,
+        kind: FamilyKind::Gauge,
+    }
+}
+
+const fn counter(name: &'static str, help: &'static str, unit: &'static str) -> Family {
+    Family {
+        name,
+        help,
+        unit,
+        kind: FamilyKind::Counter,
+    }
+}
+
+const POD_INFO: Family = gauge("kube_pod_info", "Information about pod.", "1");
+const POD_STATUS_PHASE: Family = gauge("kube_pod_status_phase", "The pods current phase.", "1");
+const POD_STATUS_READY: Family = gauge(
+    "kube_pod_status_ready",
+    "Describes whether the pod is ready to serve requests.",
+    "1",
+);
+const CONTAINER_INFO: Family = gauge(
+    "kube_pod_container_info",
+    "Information about a container in a pod.",
+    "1",
+);
+const CONTAINER_RESTARTS: Family = counter(
+    "kube_pod_container_status_restarts_total",
+    "The number of container restarts per container.",
+    "{restart}",
+);
+const CONTAINER_READY: Family = gauge(
+    "kube_pod_container_status_ready",
+    "Describes whether the containers readiness check succeeded.",
+    "1",
+);
+const CONTAINER_RUNNING: Family = gauge(
+    "kube_pod_container_status_running",
+    "Describes whether the container is currently in running state.",
+    "1",
+);
+const CONTAINER_WAITING_REASON: Family = gauge(
+    "kube_pod_container_status_waiting_reason",
+    "Describes the reason the container is currently in waiting state.",
+    "1",
+);
+const CONTAINER_REQUESTS: Family = gauge(
+    "kube_pod_container_resource_requests",
+    "The number of requested request resource by a container.",
+    "1",
+);
+const CONTAINER_LIMITS: Family = gauge(
+    "kube_pod_container_resource_limits",
+    "The number of requested limit resource by a container.",
+    "1",
+);
+const DEPLOYMENT_SPEC_REPLICAS: Family = gauge(
+    "kube_deployment_spec_replicas",
+    "Number of desired pods for a deployment.",
+    "{pod}",
+);
+const DEPLOYMENT_REPLICAS: Family = gauge(
+    "kube_deployment_status_replicas",
+    "The number of replicas per deployment.",
+    "{pod}",
+);
+const DEPLOYMENT_AVAILABLE: Family = gauge(
+    "kube_deployment_status_replicas_available",
+    "The number of available replicas per deployment.",
+    "{pod}",
+);
+const DEPLOYMENT_UNAVAILABLE: Family = gauge(
+    "kube_deployment_status_replicas_unavailable",
+    "The number of unavailable replicas per deployment.",
+    "{pod}",
+);
+const NODE_INFO: Family = gauge("kube_node_info", "Information about a cluster node.", "1");
+const NODE_CONDITION: Family = gauge(
+    "kube_node_status_condition",
+    "The condition of a cluster node.",
+    "1",
+);
+const NODE_CAPACITY: Family = gauge(
+    "kube_node_status_capacity",
+    "The capacity for different resources of a node.",
+    "1",
+);
+const NODE_ALLOCATABLE: Family = gauge(
+    "kube_node_status_allocatable",
+    "The allocatable for different resources of a node that are available for scheduling.",
+    "1",
+);
+const CADVISOR_CPU: Family = counter(
+    "container_cpu_usage_seconds_total",
+    "Cumulative cpu time consumed in seconds.",
+    "s",
+);
+const CADVISOR_WORKING_SET: Family = gauge(
+    "container_memory_working_set_bytes",
+    "Current working set in bytes.",
+    "By",
+);
+const CADVISOR_MEMORY_USAGE: Family = gauge(
+    "container_memory_usage_bytes",
+    "Current memory usage in bytes, including all memory regardless of when it was accessed.",
+    "By",
+);
+const CADVISOR_MEMORY_LIMIT: Family = gauge(
+    "container_spec_memory_limit_bytes",
+    "Memory limit for the container.",
+    "By",
+);
+const CADVISOR_NET_RX: Family = counter(
+    "container_network_receive_bytes_total",
+    "Cumulative count of bytes received.",
+    "By",
+);
+const CADVISOR_NET_TX: Family = counter(
+    "container_network_transmit_bytes_total",
+    "Cumulative count of bytes transmitted.",
+    "By",
+);
+const NODE_CPU: Family = counter(
+    "node_cpu_seconds_total",
+    "Seconds the CPUs spent in each mode.",
+    "s",
+);
+const NODE_MEM_TOTAL: Family = gauge(
+    "node_memory_MemTotal_bytes",
+    "Memory information field MemTotal_bytes.",
+    "By",
+);
+const NODE_MEM_AVAILABLE: Family = gauge(
+    "node_memory_MemAvailable_bytes",
+    "Memory information field MemAvailable_bytes.",
+    "By",
+);
+const NODE_LOAD1: Family = gauge("node_load1", "1m load average.", "1");
+const NODE_BOOT_TIME: Family = gauge(
+    "node_boot_time_seconds",
+    "Node boot time, in unixtime.",
+    "s",
+);
+const NODE_NET_RX: Family = counter(
+    "node_network_receive_bytes_total",
+    "Network device statistic receive_bytes.",
+    "By",
+);
+const NODE_NET_TX: Family = counter(
+    "node_network_transmit_bytes_total",
+    "Network device statistic transmit_bytes.",
+    "By",
+);
+
+/// Every family, in exposition order.
+pub(super) const FAMILIES: &[&Family] = &[
+    &POD_INFO,
+    &POD_STATUS_PHASE,
+    &POD_STATUS_READY,
+    &CONTAINER_INFO,
+    &CONTAINER_RESTARTS,
+    &CONTAINER_READY,
+    &CONTAINER_RUNNING,
+    &CONTAINER_WAITING_REASON,
+    &CONTAINER_REQUESTS,
+    &CONTAINER_LIMITS,
+    &DEPLOYMENT_SPEC_REPLICAS,
+    &DEPLOYMENT_REPLICAS,
+    &DEPLOYMENT_AVAILABLE,
+    &DEPLOYMENT_UNAVAILABLE,
+    &NODE_INFO,
+    &NODE_CONDITION,
+    &NODE_CAPACITY,
+    &NODE_ALLOCATABLE,
+    &CADVISOR_CPU,
+    &CADVISOR_WORKING_SET,
+    &CADVISOR_MEMORY_USAGE,
+    &CADVISOR_MEMORY_LIMIT,
+    &CADVISOR_NET_RX,
+    &CADVISOR_NET_TX,
+    &NODE_CPU,
+    &NODE_MEM_TOTAL,
+    &NODE_MEM_AVAILABLE,
+    &NODE_LOAD1,
+    &NODE_BOOT_TIME,
+    &NODE_NET_RX,
+    &NODE_NET_TX,
+];
+
+/// One point of a family.
+pub(super) struct KubeSample {
+    pub family: &'static Family,
+    pub labels: Vec<(&'static str, String)>,
+    pub ts_us: i64,
+    pub value: f64,
+    /// Counters: unix seconds the running total started from.
+    pub start_secs: Option<i64>,
+}
+
+impl KubeSample {
+    /// Label values joined, to key per-series state (delta sums).
+    pub fn series_key(&self) -> String {
+        let values: Vec<&str> = self.labels.iter().map(|(_, v)| v.as_str()).collect();
+        values.join("\u{1f}")
+    }
+}
+
+static KUBE_METRICS: OnceLock<bool> = OnceLock::new();
+
+/// Turn the Kubernetes families on for this run. Off if never set.
+pub fn init_kube_metrics(enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+    KUBE_METRICS
+        .set(enabled)
+        .map_err(|_| "kube metrics already initialised".into())
+}
+
+pub(super) fn kube_metrics_enabled() -> bool {
+    *KUBE_METRICS.get_or_init(|| false)
+}
+
+/// What a deployment or node sample needs from one pod.
+struct PodSummary {
+    cluster: String,
+    node: String,
+    service: String,
+    cpu_cores: f64,
+    memory_bytes: f64,
+    crash_looping: bool,
+}
+
+/// Builds the families from metric records. Restart counts and pending
+/// timestamps carry over between calls, so keep one per live run or ingested
+/// file; like cumulative histograms, restart totals start over when a record
+/// is older than the pod's last one.
+#[derive(Default)]
+pub struct KubeMetrics {
+    /// (last record time µs, restarts so far) per pod.
+    restarts: HashMap<String, (i64, u64)>,
+    /// Pods per timestamp whose deployment and node samples are not out yet.
+    pending: BTreeMap<i64, Vec<PodSummary>>,
+}
+
+impl KubeMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pod and container samples for `records`, then deployment and node
+    /// samples for every timestamp that is complete.
+    pub(super) fn samples(&mut self, records: &[K8sMetricRecord]) -> Vec<KubeSample> {
+        let mut out = Vec::new();
+        let mut ordered: Vec<&K8sMetricRecord> = records.iter().collect();
+        ordered.sort_by_key(|r| r._timestamp);
+        for r in ordered {
+            let summary = self.pod_samples(r, &mut out);
+            self.pending.entry(r._timestamp).or_default().push(summary);
+        }
+
+        let expected = topology().pod_count();
+        let in_batch: HashSet<i64> = records.iter().map(|r| r._timestamp).collect();
+        let done: Vec<i64> = self
+            .pending
+            .iter()
+            .filter(|(ts, pods)| pods.len() >= expected || !in_batch.contains(ts))
+            .map(|(ts, _)| *ts)
+            .collect();
+        for ts in done {
+            if let Some(pods) = self.pending.remove(&ts) {
+                aggregate_samples(ts, &pods, &mut out);
+            }
+        }
+        out
+    }
+
+    fn pod_samples(&mut self, r: &K8sMetricRecord, out: &mut Vec<KubeSample>) -> PodSummary {
+        let ts_us = r._timestamp;
+        let spec = topology().pods.iter().find(|p| p.service == r.service);
+        let container = spec.map(|p| p.container).unwrap_or(r.service.as_str());
+        let start = (r.pod_start_time > 0).then_some(r.pod_start_time);
+
+        let entry = self
+            .restarts
+            .entry(r.pod.clone())
+            .or_insert((ts_us, r.restarts as u64));
+        if entry.0 < ts_us {
+            *entry = (ts_us, entry.1 + r.restarts as u64);
+        } else if entry.0 > ts_us {
+            *entry = (ts_us, r.restarts as u64);
+        }
+        let restarts = entry.1;
+        let crash_looping = r.restarts > 0;
+
+        let uid = pod_uid(&r.cluster, &r.pod);
+        let replica_set = r.pod.rsplit_once('-').map(|(rs, _)| rs).unwrap_or(&r.pod);
+        let image = pod_image(&r.namespace, &r.service, &r.pod);
+        // The record's own ID, which changes when the container restarts.
+        let container_id = format!("containerd://{}", r.container_id);
+
+        let pod_labels = || {
+            vec![
+                ("cluster", r.cluster.clone()),
+                ("namespace", r.namespace.clone()),
+                ("pod", r.pod.clone()),
+                ("uid", uid.clone()),
+            ]
+        };
+        let container_labels = || {
+            let mut l = pod_labels();
+            l.push(("container", container.to_string()));
+            l
+        };
+        let cadvisor_labels = || {
+            vec![
+                ("cluster", r.cluster.clone()),
+                ("namespace", r.namespace.clone()),
+                ("pod", r.pod.clone()),
+                ("container", container.to_string()),
+                ("node", r.node.clone()),
+                ("image", image.clone()),
+            ]
+        };
+        let mut push = |family, labels, value, start_secs| {
+            out.push(KubeSample {
+                family,
+                labels,
+                ts_us,
+                value,
+                start_secs,
+            })
+        };
+
+        let mut l = pod_labels();
+        l.extend([
+            ("node", r.node.clone()),
+            ("host_ip", node_ip(&r.cluster, &r.node)),
+            ("pod_ip", pod_ip(&r.cluster, &r.pod)),
+            ("created_by_kind", "ReplicaSet".to_string()),
+            ("created_by_name", replica_set.to_string()),
+        ]);
+        push(&POD_INFO, l, 1.0, None);
+        for phase in ["Pending", "Running", "Succeeded", "Failed", "Unknown"] {
+            let mut l = pod_labels();
+            l.push(("phase", phase.to_string()));
+            push(&POD_STATUS_PHASE, l, flag(phase == "Running"), None);
+        }
+        for (condition, on) in [
+            ("true", !crash_looping),
+            ("false", crash_looping),
+            ("unknown", false),
+        ] {
+            let mut l = pod_labels();
+            l.push(("condition", condition.to_string()));
+            push(&POD_STATUS_READY, l, flag(on), None);
+        }
+
+        let mut l = container_labels();
+        l.extend([("image", image.clone()), ("container_id", container_id)]);
+        push(&CONTAINER_INFO, l, 1.0, None);
+        push(
+            &CONTAINER_RESTARTS,
+            container_labels(),
+            restarts as f64,
+            start,
+        );
+        push(
+            &CONTAINER_READY,
+            container_labels(),
+            flag(!crash_looping),
+            None,
+        );
+        push(
+            &CONTAINER_RUNNING,
+            container_labels(),
+            flag(!crash_looping),
+            None,
+        );
+        if crash_looping {
+            let mut l = container_labels();
+            l.push(("reason", "CrashLoopBackOff".to_string()));
+            push(&CONTAINER_WAITING_REASON, l, 1.0, None);
+        }
+        if let Some(p) = spec {
+            let cpu = p.base_cpu as f64 / 1000.0;
+            let memory = p.base_mem as f64 * MIB;
+            for (family, factor) in [(&CONTAINER_REQUESTS, 1.0), (&CONTAINER_LIMITS, 2.0)] {
+                for (resource, unit, value) in [("cpu", "core", cpu), ("memory", "byte", memory)] {
+                    let mut l = container_labels();
+                    l.extend([
+                        ("node", r.node.clone()),
+                        ("resource", resource.to_string()),
+                        ("unit", unit.to_string()),
+                    ]);
+                    push(family, l, value * factor, None);
+                }
+            }
+        }
+
+        let mut l = cadvisor_labels();
+        l.push(("cpu", "total".to_string()));
+        push(&CADVISOR_CPU, l, r.container_cpu_time, start);
+        let working_set = r.memory_mb as f64 * MIB;
+        push(&CADVISOR_WORKING_SET, cadvisor_labels(), working_set, None);
+        push(
+            &CADVISOR_MEMORY_USAGE,
+            cadvisor_labels(),
+            (working_set * 1.1).round(),
+            None,
+        );
+        if let Some(p) = spec {
+            let limit = p.base_mem as f64 * 2.0 * MIB;
+            push(&CADVISOR_MEMORY_LIMIT, cadvisor_labels(), limit, None);
+        }
+        let net_labels = || {
+            vec![
+                ("cluster", r.cluster.clone()),
+                ("namespace", r.namespace.clone()),
+                ("pod", r.pod.clone()),
+                ("node", r.node.clone()),
+                ("interface", "eth0".to_string()),
+            ]
+        };
+        let rx = r.network_rx_bytes_total as f64;
+        push(&CADVISOR_NET_RX, net_labels(), rx, start);
+        let tx = r.network_tx_bytes_total as f64;
+        push(&CADVISOR_NET_TX, net_labels(), tx, start);
+
+        PodSummary {
+            cluster: r.cluster.clone(),
+            node: r.node.clone(),
+            service: r.service.clone(),
+            cpu_cores: r.cpu_millicores as f64 / 1000.0,
+            memory_bytes: working_set,
+            crash_looping,
+        }
+    }
+}
+
+fn flag(on: bool) -> f64 {
+    if on {
+        1.0
+    } else {
+        0.0
+    }
+}
+
+/// Stable UUID-formatted ID from a hash.
+pub(crate) fn uuid_from(h: u64) -> String {
+    let l = mix64(h);
+    format!(
+        "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+        h >> 32,
+        (h >> 16) & 0xffff,
+        h & 0xfff,
+        0x8000 | ((l >> 48) & 0x3fff),
+        l & 0xffff_ffff_ffff
+    )
+}
+
+/// `kube_pod_info`'s `uid` of a pod.
+pub(crate) fn pod_uid(cluster: &str, pod: &str) -> String {
+    uuid_from(mix64(str_hash(cluster) ^ str_hash(pod)))
+}
+
+pub(crate) fn node_uid(cluster: &str, node: &str) -> String {
+    uuid_from(mix64(str_hash(cluster) ^ str_hash(node)))
+}
+
+pub(crate) fn pod_ip(cluster: &str, pod: &str) -> String {
+    let h = mix64(str_hash(cluster) ^ str_hash(pod));
+    format!(
+        "10.{}.{}.{}",
+        100 + (h >> 48) % 100,
+        (h >> 8) % 256,
+        2 + h % 250
+    )
+}
+
+/// Container image of a pod; a rollout (new ReplicaSet) bumps the version.
+pub(crate) fn pod_image(namespace: &str, service: &str, pod: &str) -> String {
+    let replica_set = pod.rsplit_once('-').map(|(rs, _)| rs).unwrap_or(pod);
+    let rs_h = str_hash(replica_set);
+    format!(
+        "registry.example.com/{}/{}:v1.{}.{}",
+        namespace,
+        service,
+        rs_h % 30,
+        (rs_h >> 8) % 10
+    )
+}
+
+pub(crate) fn node_ip(cluster: &str, node: &str) -> String {
+    let h = mix64(str_hash(cluster) ^ str_hash(node));
+    format!("10.{}.{}.{}", h % 100, (h >> 8) % 256, 2 + (h >> 16) % 250)
+}
+
+/// Deployment and node samples at `ts_us` for every cluster with pods in `pods`.
+fn aggregate_samples(ts_us: i64, pods: &[PodSummary], out: &mut Vec<KubeSample>) {
+    let topo = topology();
+    let t = ts_us.div_euclid(1_000_000);
+    let clusters: HashSet<&str> = pods.iter().map(|p| p.cluster.as_str()).collect();
+    let mut push = |family, labels, value, start_secs| {
+        out.push(KubeSample {
+            family,
+            labels,
+            ts_us,
+            value,
+            start_secs,
+        })
+    };
+
+    for cluster in topo.clusters.iter().filter(|c| clusters.contains(c.name)) {
+        let on_cluster = || pods.iter().filter(|p| p.cluster == cluster.name);
+
+        for &svc_idx in &cluster.services {
+            let spec = &topo.pods[svc_idx];
+            let unavailable = on_cluster()
+                .filter(|p| p.service == spec.service && p.crash_looping)
+                .count() as f64;
+            let replicas = spec.replicas as f64;
+            let labels = || {
+                vec![
+                    ("cluster", cluster.name.to_string()),
+                    ("namespace", spec.namespace.to_string()),
+                    ("deployment", spec.service.to_string()),
+                ]
+            };
+            push(&DEPLOYMENT_SPEC_REPLICAS, labels(), replicas, None);
+            push(&DEPLOYMENT_REPLICAS, labels(), replicas, None);
+            let available = (replicas - unavailable).max(0.0);
+            push(&DEPLOYMENT_AVAILABLE, labels(), available, None);
+            push(&DEPLOYMENT_UNAVAILABLE, labels(), unavailable, None);
+        }
+
+        // Counters follow the cluster's average load per node over the daily
+        // curve, so they are a pure function of (node, time) like
+        // `container_cpu_time`; gauges follow the pods on the node right now.
+        let nodes = cluster.nodes.len().max(1) as f64;
+        let cores = NODE_CPU_CORES as f64;
+        let (cpu_mc, rps): (f64, f64) = cluster
+            .services
+            .iter()
+            .map(|&s| &topo.pods[s])
+            .fold((0.0, 0.0), |(c, r), p| {
+                (c + (p.base_cpu * p.replicas) as f64, r + p.base_rps as f64)
+            });
+        let busy = (cpu_mc / 1000.0 / nodes / cores).min(NODE_MAX_BUSY);
+        let rps_per_node = rps / nodes;
+
+        for node in &cluster.nodes {
+            let node_h = str_hash(cluster.name) ^ str_hash(node);
+            let phase = (mix64(node_h) % NODE_UPTIME_PERIOD_SECS as u64) as i64;
+            let boot =
+                (t - phase).div_euclid(NODE_UPTIME_PERIOD_SECS) * NODE_UPTIME_PERIOD_SECS + phase;
+            let uptime = (t - boot) as f64;
+            let weighted = daily_seasonal_integral(boot, t, 0.25);
+            let on_node: Vec<&PodSummary> = on_cluster().filter(|p| p.node == *node).collect();
+            let memory_total = NODE_MEMORY_MB * MIB;
+            let memory_used: f64 = on_node.iter().map(|p| p.memory_bytes).sum();
+            let load: f64 = on_node.iter().map(|p| p.cpu_cores).sum::<f64>() + 0.1;
+
+            let kube_labels = || {
+                vec![
+                    ("cluster", cluster.name.to_string()),
+                    ("node", node.to_string()),
+                ]
+            };
+            let exporter_labels = || {
+                let mut l = kube_labels();
+                l.push(("instance", format!("{}:9100", node)));
+                l
+            };
+
+            let mut l = kube_labels();
+            l.extend([
+                ("internal_ip", node_ip(cluster.name, node)),
+                ("kernel_version", "6.1.0-18-cloud-amd64".to_string()),
+                ("os_image", "Debian GNU/Linux 12 (bookworm)".to_string()),
+                (
+                    "container_runtime_version",
+                    "containerd://1.7.13".to_string(),
+                ),
+                ("kubelet_version", "v1.29.2".to_string()),
+            ]);
+            push(&NODE_INFO, l, 1.0, None);
+            for (status, on) in [("true", true), ("false", false), ("unknown", false)] {
+                let mut l = kube_labels();
+                l.extend([
+                    ("condition", "Ready".to_string()),
+                    ("status", status.to_string()),
+                ]);
+                push(&NODE_CONDITION, l, flag(on), None);
+            }
+            for (family, reserved_cpu, reserved_mem) in [
+                (&NODE_CAPACITY, 0.0, 0.0),
+                (&NODE_ALLOCATABLE, 0.1, NODE_RESERVED_BYTES / 2.0),
+            ] {
+                for (resource, unit, value) in [
+                    ("cpu", "core", cores - reserved_cpu),
+                    ("memory", "byte", memory_total - reserved_mem),
+                    ("pods", "integer", NODE_MAX_PODS),
+                ] {
+                    let mut l = kube_labels();
+                    l.extend([
+                        ("resource", resource.to_string()),
+                        ("unit", unit.to_string()),
+                    ]);
+                    push(family, l, value, None);
+                }
+            }
+
+            for cpu in 0..NODE_CPU_CORES {
+                for (mode, seconds) in [
+                    ("idle", uptime - busy * weighted),
+                    ("user", 0.75 * busy * weighted),
+                    ("system", 0.2 * busy * weighted),
+                    ("iowait", 0.05 * busy * weighted),
+                ] {
+                    let mut l = exporter_labels();
+                    l.extend([("cpu", cpu.to_string()), ("mode", mode.to_string())]);
+                    let seconds = (seconds * 100.0).round() / 100.0;
+                    push(&NODE_CPU, l, seconds, Some(boot));
+                }
+            }
+            push(&NODE_MEM_TOTAL, exporter_labels(), memory_total, None);
+            let available = (memory_total - NODE_RESERVED_BYTES - memory_used)
+                .max(memory_total * 0.02)
+                .round();
+            push(&NODE_MEM_AVAILABLE, exporter_labels(), available, None);
+            push(
+                &NODE_LOAD1,
+                exporter_labels(),
+                (load * 100.0).round() / 100.0,
+                None,
+            );
+            push(&NODE_BOOT_TIME, exporter_labels(), boot as f64, None);
+            for (family, bytes_per_request) in [(&NODE_NET_RX, 1000.0), (&NODE_NET_TX, 600.0)] {
+                let mut l = exporter_labels();
+                l.push(("device", "eth0".to_string()));
+                let bytes = (rps_per_node * weighted * bytes_per_request).round();
+                push(family, l, bytes, Some(boot));
+            }
+        }
+    }
+}
@@ -1,31 +1,167 @@
 use std::time::Duration;
 
 use chrono::Utc;
+use opentelemetry_proto::tonic::collector::metrics::v1::metrics_service_client::MetricsServiceClient;
 use reqwest::Client;
+use tonic::transport::Channel;
 
-use super::generate::generate_metric_record;
-use super::otlp::metrics_to_otlp_payload;
+use super::counters::CounterStore;
+use super::generate_metric_record;
+use super::otlp::MetricsEncoder;
+use super::remote_write::RemoteWriteEncoder;
+use super::types::K8sMetricRecord;
 use crate::anomaly::{AnomalyState, AnomalyType};
-use crate::client::http::post_otlp;
-use crate::config::{api_base, DEFAULT_ORG, PODS_PER_TICK};
-use crate::utils::print_anomaly_header;
+use crate::client::grpc::{grpc_metrics_client, report_export, send_grpc_metrics};
+use crate::client::http::{post_otlp, post_remote_write};
+use crate::config::{api_base, grpc_endpoint, org};
+use crate::events::LiveEvents;
+use crate::lifecycle::pods_at;
+use crate::rate::{pick_pods, print_rate_header, RateSchedule, TokenBucket};
+use crate::utils::{print_anomaly_header, seeded_rng};
 
+/// Where the live loop sends each tick's records.
+enum Transport {
+    Otlp {
+        client: Client,
+        url: String,
+        encoder: MetricsEncoder,
+    },
+    RemoteWrite {
+        client: Client,
+        url: String,
+        encoder: RemoteWriteEncoder,
+    },
+    Grpc {
+        client: MetricsServiceClient<Channel>,
+        org: String,
+        encoder: MetricsEncoder,
+    },
+}
+
+impl Transport {
+    async fn send(
+        &mut self,
+        records: &[K8sMetricRecord],
+        anomaly_state: &Option<AnomalyState>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match self {
+            Transport::Otlp {
+                client,
+                url,
+                encoder,
+            } => {
+                let payload = encoder.encode(records);
+                post_otlp(client, url, None, &payload, records.len(), anomaly_state).await;
+            }
+            Transport::RemoteWrite {
+                client,
+                url,
+                encoder,
+            } => {
+                let body = encoder.encode_body(records)?;
+                post_remote_write(client, url, body, records.len(), anomaly_state).await;
+            }
+            Transport::Grpc {
+                client,
+                org,
+                encoder,
+            } => {
+                let resource_metrics = encoder.encode_proto(records);
+                let result = send_grpc_metrics(client, resource_metrics, org, None).await;
+                if !report_export(result, records.len(), "records", anomaly_state) {
+                    if let Ok(new_client) = grpc_metrics_client(&grpc_endpoint()).await {
+                        *client = new_client;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn http_client() -> Result<Client, Box<dyn std::error::Error>> {
+    Ok(Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?)
+}
+
+/// `counters` carries the running totals over from `run`'s backfill; start
+/// from an empty store otherwise.
 pub async fn run_live_metrics(
     anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+    counters: CounterStore,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let api_url = format!("{}/api/{}/v1/metrics", api_base(), DEFAULT_ORG);
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()?;
-    let mut rng = rand::thread_rng();
-    let mut interval = tokio::time::interval(Duration::from_secs(1));
-    let mut anomaly_state = anomaly_type.map(AnomalyState::new);
-
-    println!("Live metrics (OTLP) → {}", api_url);
+    let url = format!("{}/api/{}/v1/metrics", api_base(), org());
+    println!("Live metrics (OTLP) → {}", url);
     println!(
         "Streams (type=metrics): cpu_percent, memory_percent, request_latency_ms, error_rate, ..."
     );
+    let transport = Transport::Otlp {
+        client: http_client()?,
+        url,
+        encoder: MetricsEncoder::new(),
+    };
+    run_loop(anomaly_type, seed, rate, counters, transport).await
+}
+
+/// Live metrics as Prometheus remote_write to OpenObserve's
+/// `/prometheus/api/v1/write` endpoint.
+pub async fn run_live_metrics_remote_write(
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+    counters: CounterStore,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let url = format!("{}/api/{}/prometheus/api/v1/write", api_base(), org());
+    println!("Live metrics (Prometheus remote_write) → {}", url);
+    let transport = Transport::RemoteWrite {
+        client: http_client()?,
+        url,
+        encoder: RemoteWriteEncoder::new(),
+    };
+    run_loop(anomaly_type, seed, rate, counters, transport).await
+}
+
+pub async fn run_live_metrics_grpc(
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+    counters: CounterStore,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let client = grpc_metrics_client(&grpc_endpoint()).await?;
+    let org = org();
+    println!(
+        "Live metrics (gRPC OTLP) → {} [org: {}]",
+        &grpc_endpoint(),
+        org
+    );
+    let transport = Transport::Grpc {
+        client,
+        org,
+        encoder: MetricsEncoder::new(),
+    };
+    run_loop(anomaly_type, seed, rate, counters, transport).await
+}
+
+/// One tick per second: the records of the running pods (or the rate's picks),
+/// with integrated counters, and the Kubernetes events they show.
+async fn run_loop(
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
+    mut counters: CounterStore,
+    mut transport: Transport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let mut rng = seeded_rng(seed);
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let mut anomaly_state = anomaly_type.map(AnomalyState::new);
+    let mut bucket = rate.map(TokenBucket::new);
+
+    print_rate_header(&bucket, "one record per running pod per second");
     print_anomaly_header(&anomaly_state);
+    let mut events = LiveEvents::new(seed)?;
     println!("Press Ctrl+C to stop.\n");
 
     loop {
@@ -35,19 +171,23 @@ pub async fn run_live_metrics(
         }
 
         let now_us = Utc::now().timestamp_micros();
-        let records: Vec<_> = (0..PODS_PER_TICK)
-            .map(|i| generate_metric_record(i, now_us, anomaly_state.as_ref(), &mut rng))
+        let pods = pods_at(now_us, seed);
+        counters.retain(|pod| pods.iter().any(|p| p.name == pod));
+        let records: Vec<_> = pick_pods(bucket.as_mut(), &pods, now_us, &mut rng)
+            .into_iter()
+            .map(|(pod, ts_us)| {
+                let mut r = generate_metric_record(pod, ts_us, anomaly_state.as_ref(), &mut rng);
+                counters.apply(&mut r);
+                r
+            })
             .collect();
+        if let Some(ref mut events) = events {
+            events.post(now_us, &records, &anomaly_state).await;
+        }
+        if records.is_empty() {
+            continue;
+        }
 
-        let payload = metrics_to_otlp_payload(&records);
-        post_otlp(
-            &client,
-            &api_url,
-            None,
-            &payload,
-            records.len(),
-            &anomaly_state,
-        )
-        .await;
+        transport.send(&records, &anomaly_state).await?;
     }
 }
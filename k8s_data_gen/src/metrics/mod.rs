@@ -1,11 +1,22 @@
+pub mod backfill;
+pub mod counters;
 pub mod generate;
+pub mod histogram;
 pub mod historical;
+pub mod kube;
 pub mod live;
 pub mod otlp;
+pub mod remote_write;
+pub mod scrape;
 pub mod types;
 
+pub use backfill::run_backfill_metrics;
+pub use counters::CounterStore;
 pub use generate::generate_metric_record;
 pub use historical::run_historical_metrics;
-pub use live::run_live_metrics;
-pub use otlp::metrics_to_otlp_payload;
+pub use kube::init_kube_metrics;
+pub use live::{run_live_metrics, run_live_metrics_grpc, run_live_metrics_remote_write};
+pub use otlp::{init_temporality, pod_family_count, MetricsEncoder, Temporality};
+pub use remote_write::RemoteWriteEncoder;
+pub use scrape::run_live_metrics_serve;
 pub use types::K8sMetricRecord;
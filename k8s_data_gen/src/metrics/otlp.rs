@@ -1,37 +1,633 @@
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+use opentelemetry_proto::tonic::{
+    common::v1::{InstrumentationScope, KeyValue},
+    metrics::v1::{
+        exponential_histogram_data_point::Buckets, metric::Data, number_data_point,
+        ExponentialHistogram, ExponentialHistogramDataPoint, Gauge, Histogram, HistogramDataPoint,
+        Metric, NumberDataPoint, ResourceMetrics, ScopeMetrics, Sum,
+    },
+    resource::v1::Resource,
+};
+
+use super::histogram::{CumulativeHistograms, HISTOGRAM_WINDOW_NS, LATENCY_BOUNDS_MS};
+use super::kube::{kube_metrics_enabled, FamilyKind, KubeMetrics, KubeSample, FAMILIES};
 use super::types::K8sMetricRecord;
+use crate::traces::otlp::{attributes_json, kv_str};
 
-pub fn metric_record_to_resource_metrics(r: &K8sMetricRecord) -> serde_json::Value {
-    let ts_ns = (r._timestamp * 1000).to_string();
+/// Aggregation temporality for sums and histograms (`--temporality`).
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Temporality {
+    /// Running totals since the pod started (sums) or since the series was
+    /// first seen (histograms).
+    Cumulative,
+    /// Change since the previous point of the same series.
+    Delta,
+}
 
-    let attrs = serde_json::json!([
-        {"key": "service",   "value": {"stringValue": r.service.as_str()}},
-        {"key": "namespace", "value": {"stringValue": r.namespace.as_str()}},
-        {"key": "pod",       "value": {"stringValue": r.pod.as_str()}},
-        {"key": "node",      "value": {"stringValue": r.node.as_str()}},
-        {"key": "cluster",   "value": {"stringValue": r.cluster.as_str()}},
-    ]);
+impl Temporality {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "cumulative" => Ok(Temporality::Cumulative),
+            "delta" => Ok(Temporality::Delta),
+            _ => Err(format!(
+                "unknown --temporality '{}' (use cumulative or delta)",
+                s
+            )),
+        }
+    }
 
-    serde_json::json!({
-        "resource": {
-            "attributes": [
-                {"key": "service.name",        "value": {"stringValue": r.service.as_str()}},
-                {"key": "k8s.namespace.name",  "value": {"stringValue": r.namespace.as_str()}},
-                {"key": "k8s.pod.name",        "value": {"stringValue": r.pod.as_str()}},
-                {"key": "k8s.node.name",       "value": {"stringValue": r.node.as_str()}},
-                {"key": "k8s.cluster.name",    "value": {"stringValue": r.cluster.as_str()}},
-            ]
-        },
-        "scopeMetrics": [{
-            "scope": {"name": "k8s-data-gen"},
-            "metrics": [
-                {"name": "total_payment_req", "gauge": {"dataPoints": [{"attributes": &attrs, "timeUnixNano": &ts_ns, "asInt": r.total_payment_req}]}},
-            ]
-        }]
-    })
+    /// OTLP `AggregationTemporality` enum value.
+    fn otlp(self) -> i32 {
+        match self {
+            Temporality::Delta => 1,
+            Temporality::Cumulative => 2,
+        }
+    }
+}
+
+static TEMPORALITY: OnceLock<Temporality> = OnceLock::new();
+
+/// Select the temporality for this run. Defaults to cumulative if never set.
+pub fn init_temporality(t: Temporality) -> Result<(), Box<dyn std::error::Error>> {
+    TEMPORALITY
+        .set(t)
+        .map_err(|_| "temporality already initialised".into())
+}
+
+fn temporality() -> Temporality {
+    *TEMPORALITY.get_or_init(|| Temporality::Cumulative)
+}
+
+/// How a record field is exported. OpenObserve creates one metrics stream per
+/// metric name, so names match the record fields (and the README's alerts).
+pub(super) enum Kind {
+    Gauge,
+    /// Monotonic counter; the field holds the running total since the pod
+    /// started (`startTimeUnixNano`).
+    Sum,
+}
+
+pub(super) enum Num {
+    Int(u64),
+    Double(f64),
+}
+
+pub(super) struct MetricDef {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// UCUM unit, as recommended by the OpenTelemetry semantic conventions.
+    pub unit: &'static str,
+    pub kind: Kind,
+    pub value: fn(&K8sMetricRecord) -> Num,
+}
+
+/// Metric families emitted per pod record: `METRICS` plus the latency
+/// histogram (OTLP also sends its exponential form).
+pub fn pod_family_count() -> usize {
+    METRICS.len() + 1
+}
+
+pub(super) const METRICS: &[MetricDef] = &[
+    MetricDef {
+        name: "cpu_millicores",
+        description: "CPU usage in millicores",
+        unit: "{millicore}",
+        kind: Kind::Gauge,
+        value: |r| Num::Int(r.cpu_millicores as u64),
+    },
+    MetricDef {
+        name: "cpu_percent",
+        description: "CPU usage as % of one core",
+        unit: "%",
+        kind: Kind::Gauge,
+        value: |r| Num::Double(r.cpu_percent),
+    },
+    MetricDef {
+        name: "memory_mb",
+        description: "Memory working set",
+        unit: "MBy",
+        kind: Kind::Gauge,
+        value: |r| Num::Int(r.memory_mb as u64),
+    },
+    MetricDef {
+        name: "memory_percent",
+        description: "Memory working set as % of node memory",
+        unit: "%",
+        kind: Kind::Gauge,
+        value: |r| Num::Double(r.memory_percent),
+    },
+    MetricDef {
+        name: "network_rx_bytes_per_sec",
+        description: "Inbound network throughput",
+        unit: "By/s",
+        kind: Kind::Gauge,
+        value: |r| Num::Int(r.network_rx_bytes_per_sec),
+    },
+    MetricDef {
+        name: "network_tx_bytes_per_sec",
+        description: "Outbound network throughput",
+        unit: "By/s",
+        kind: Kind::Gauge,
+        value: |r| Num::Int(r.network_tx_bytes_per_sec),
+    },
+    MetricDef {
+        name: "requests_per_second",
+        description: "Request throughput",
+        unit: "{request}/s",
+        kind: Kind::Gauge,
+        value: |r| Num::Double(r.requests_per_second),
+    },
+    MetricDef {
+        name: "request_latency_ms",
+        description: "Average request latency",
+        unit: "ms",
+        kind: Kind::Gauge,
+        value: |r| Num::Double(r.request_latency_ms),
+    },
+    MetricDef {
+        name: "error_rate",
+        description: "Fraction of failed requests",
+        unit: "1",
+        kind: Kind::Gauge,
+        value: |r| Num::Double(r.error_rate),
+    },
+    MetricDef {
+        name: "restarts",
+        description: "Container restarts during the sample interval",
+        unit: "{restart}",
+        kind: Kind::Gauge,
+        value: |r| Num::Int(r.restarts as u64),
+    },
+    MetricDef {
+        name: "container_cpu_time",
+        description: "Cumulative CPU time consumed since the pod started",
+        unit: "s",
+        kind: Kind::Sum,
+        value: |r| Num::Double(r.container_cpu_time),
+    },
+    MetricDef {
+        name: "requests_total",
+        description: "Requests served since the pod started",
+        unit: "{request}",
+        kind: Kind::Sum,
+        value: |r| Num::Int(r.requests_total),
+    },
+    MetricDef {
+        name: "network_rx_bytes_total",
+        description: "Bytes received since the pod started",
+        unit: "By",
+        kind: Kind::Sum,
+        value: |r| Num::Int(r.network_rx_bytes_total),
+    },
+    MetricDef {
+        name: "network_tx_bytes_total",
+        description: "Bytes sent since the pod started",
+        unit: "By",
+        kind: Kind::Sum,
+        value: |r| Num::Int(r.network_tx_bytes_total),
+    },
+    MetricDef {
+        name: "pod_start_time",
+        description: "Pod start time in unix seconds",
+        unit: "s",
+        kind: Kind::Gauge,
+        value: |r| Num::Int(r.pod_start_time.max(0) as u64),
+    },
+    MetricDef {
+        name: "total_payment_req",
+        description: "Payment requests during the sample interval",
+        unit: "{request}",
+        kind: Kind::Gauge,
+        value: |r| Num::Int(r.total_payment_req),
+    },
+];
+
+/// Turns metric records into OTLP `ResourceMetrics` (protobuf or JSON).
+///
+/// Delta and cumulative-histogram output depend on earlier points of the same
+/// series, so keep one encoder per ordered stream of records (a live loop, a
+/// backfill, one ingested file). A delta sum needs two points: the first point
+/// of each series only primes it. Delta windows are taken between consecutive
+/// points in either time order, so newest-first historical files work too.
+/// Cumulative histograms add points up in arrival order and restart when a
+/// point is older than the last one, so they need oldest-first input.
+pub struct MetricsEncoder {
+    temporality: Temporality,
+    /// Delta sums: last (time ns, running total) per (series, metric).
+    last_sum: HashMap<(String, &'static str), (i64, f64)>,
+    /// Cumulative histograms.
+    hist_totals: CumulativeHistograms,
+    /// `--kube-metrics` families.
+    kube: Option<KubeMetrics>,
+}
+
+impl MetricsEncoder {
+    pub fn new() -> Self {
+        MetricsEncoder {
+            temporality: temporality(),
+            last_sum: HashMap::new(),
+            hist_totals: CumulativeHistograms::default(),
+            kube: kube_metrics_enabled().then(KubeMetrics::new),
+        }
+    }
+
+    /// OTLP/HTTP JSON body (`/v1/metrics`).
+    pub fn encode(&mut self, records: &[K8sMetricRecord]) -> serde_json::Value {
+        let resource_metrics = self.encode_proto(records);
+        serde_json::json!({
+            "resourceMetrics": resource_metrics.iter().map(resource_metrics_json).collect::<Vec<_>>()
+        })
+    }
+
+    /// Protobuf messages for the gRPC `MetricsService`.
+    pub fn encode_proto(&mut self, records: &[K8sMetricRecord]) -> Vec<ResourceMetrics> {
+        let mut out: Vec<ResourceMetrics> =
+            records.iter().map(|r| self.resource_metrics(r)).collect();
+        let kube = self.kube.as_mut().map(|k| k.samples(records));
+        if let Some(samples) = kube.filter(|s| !s.is_empty()) {
+            out.push(self.kube_resource_metrics(&samples));
+        }
+        out
+    }
+
+    fn resource_metrics(&mut self, r: &K8sMetricRecord) -> ResourceMetrics {
+        let ts_ns = r._timestamp * 1000;
+        let mut attrs = vec![
+            kv_str("service", &r.service),
+            kv_str("namespace", &r.namespace),
+            kv_str("pod", &r.pod),
+            kv_str("node", &r.node),
+            kv_str("cluster", &r.cluster),
+        ];
+        attrs.extend(r.labels.iter().map(|(k, v)| kv_str(k, v)));
+
+        let mut metrics: Vec<Metric> = METRICS
+            .iter()
+            .filter_map(|m| {
+                let data = match m.kind {
+                    Kind::Gauge => Data::Gauge(Gauge {
+                        data_points: vec![number_point(&attrs, None, ts_ns, (m.value)(r))],
+                    }),
+                    Kind::Sum => {
+                        // Records from files written before `pod_start_time`
+                        // existed have 0; their cumulative points go out
+                        // without a start time.
+                        let start_ns =
+                            (r.pod_start_time > 0).then(|| r.pod_start_time * 1_000_000_000);
+                        let (start_ns, time_ns, value) =
+                            self.sum_point(&r.pod, m.name, start_ns, ts_ns, (m.value)(r))?;
+                        Data::Sum(Sum {
+                            data_points: vec![number_point(&attrs, start_ns, time_ns, value)],
+                            aggregation_temporality: self.temporality.otlp(),
+                            is_monotonic: true,
+                        })
+                    }
+                };
+                Some(Metric {
+                    name: m.name.to_string(),
+                    description: m.description.to_string(),
+                    unit: m.unit.to_string(),
+                    data: Some(data),
+                    ..Default::default()
+                })
+            })
+            .collect();
+        metrics.extend(self.latency_histograms(r, ts_ns, &attrs));
+
+        ResourceMetrics {
+            resource: Some(Resource {
+                attributes: vec![
+                    kv_str("service.name", &r.service),
+                    kv_str("k8s.namespace.name", &r.namespace),
+                    kv_str("k8s.pod.name", &r.pod),
+                    kv_str("k8s.node.name", &r.node),
+                    kv_str("k8s.cluster.name", &r.cluster),
+                ],
+                dropped_attributes_count: 0,
+            }),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(InstrumentationScope {
+                    name: "k8s-data-gen".to_string(),
+                    ..Default::default()
+                }),
+                metrics,
+                schema_url: String::new(),
+            }],
+            schema_url: String::new(),
+        }
+    }
+
+    /// (start, time, value) of a sum point, or `None` while a delta series is
+    /// still priming.
+    fn sum_point(
+        &mut self,
+        series: &str,
+        name: &'static str,
+        start_ns: Option<i64>,
+        ts_ns: i64,
+        value: Num,
+    ) -> Option<(Option<i64>, i64, Num)> {
+        if self.temporality == Temporality::Cumulative {
+            return Some((start_ns, ts_ns, value));
+        }
+
+        let total = match value {
+            Num::Int(v) => v as f64,
+            Num::Double(v) => v,
+        };
+        let (prev_ns, prev_total) = self
+            .last_sum
+            .insert((series.to_string(), name), (ts_ns, total))?;
+        if prev_ns == ts_ns {
+            return None;
+        }
+        // The window runs between the two points whichever came first.
+        let (mut window_start, end_ns) = (prev_ns.min(ts_ns), prev_ns.max(ts_ns));
+        let mut delta = (total - prev_total).abs();
+        if ts_ns > prev_ns && total < prev_total {
+            // Counter reset (container restart): everything since the new
+            // start counts.
+            delta = total;
+            window_start = start_ns.map_or(prev_ns, |s| s.clamp(prev_ns, ts_ns));
+        }
+        let delta = match value {
+            Num::Int(_) => Num::Int(delta.round() as u64),
+            Num::Double(_) => Num::Double((delta * 1000.0).round() / 1000.0),
+        };
+        Some((Some(window_start), end_ns, delta))
+    }
+
+    /// The `--kube-metrics` families, one metric per family with a point per
+    /// sample. Names and labels are the Prometheus ones.
+    fn kube_resource_metrics(&mut self, samples: &[KubeSample]) -> ResourceMetrics {
+        let mut metrics = Vec::new();
+        for family in FAMILIES {
+            let mut points = Vec::new();
+            for s in samples.iter().filter(|s| s.family.name == family.name) {
+                let attrs: Vec<KeyValue> = s.labels.iter().map(|(k, v)| kv_str(k, v)).collect();
+                let ts_ns = s.ts_us * 1000;
+                let value = Num::Double(s.value);
+                let point = match family.kind {
+                    FamilyKind::Gauge => Some(number_point(&attrs, None, ts_ns, value)),
+                    FamilyKind::Counter => {
+                        let start_ns = s.start_secs.map(|t| t * 1_000_000_000);
+                        self.sum_point(&s.series_key(), family.name, start_ns, ts_ns, value)
+                            .map(|(start_ns, ts_ns, v)| number_point(&attrs, start_ns, ts_ns, v))
+                    }
+                };
+                points.extend(point);
+            }
+            if points.is_empty() {
+                continue;
+            }
+            let data = match family.kind {
+                FamilyKind::Gauge => Data::Gauge(Gauge {
+                    data_points: points,
+                }),
+                FamilyKind::Counter => Data::Sum(Sum {
+                    data_points: points,
+                    aggregation_temporality: self.temporality.otlp(),
+                    is_monotonic: true,
+                }),
+            };
+            metrics.push(Metric {
+                name: family.name.to_string(),
+                description: family.help.to_string(),
+                unit: family.unit.to_string(),
+                data: Some(data),
+                ..Default::default()
+            });
+        }
+
+        ResourceMetrics {
+            resource: Some(Resource::default()),
+            scope_metrics: vec![ScopeMetrics {
+                scope: Some(InstrumentationScope {
+                    name: "k8s-data-gen".to_string(),
+                    ..Default::default()
+                }),
+                metrics,
+                schema_url: String::new(),
+            }],
+            schema_url: String::new(),
+        }
+    }
+
+    /// `request_latency_histogram` (explicit bounds) and
+    /// `request_latency_exp_histogram` (exponential) for one record.
+    fn latency_histograms(
+        &mut self,
+        r: &K8sMetricRecord,
+        ts_ns: i64,
+        attrs: &[KeyValue],
+    ) -> Vec<Metric> {
+        let window = &r.request_latency_histogram;
+        let (start_ns, h) = match self.temporality {
+            Temporality::Delta => (ts_ns - HISTOGRAM_WINDOW_NS, window.clone()),
+            Temporality::Cumulative => self.hist_totals.add(&r.pod, ts_ns, window),
+        };
+        if h.bucket_counts.is_empty() {
+            // Record from a file written before histograms existed.
+            return Vec::new();
+        }
+
+        let (min, max) = if h.count > 0 {
+            (Some(h.min), Some(h.max))
+        } else {
+            (None, None)
+        };
+        let temporality = self.temporality.otlp();
+        vec![
+            Metric {
+                name: "request_latency_histogram".to_string(),
+                description: "Request latency distribution".to_string(),
+                unit: "ms".to_string(),
+                data: Some(Data::Histogram(Histogram {
+                    data_points: vec![HistogramDataPoint {
+                        attributes: attrs.to_vec(),
+                        start_time_unix_nano: start_ns as u64,
+                        time_unix_nano: ts_ns as u64,
+                        count: h.count,
+                        sum: Some(h.sum),
+                        bucket_counts: h.bucket_counts.clone(),
+                        explicit_bounds: LATENCY_BOUNDS_MS.to_vec(),
+                        min,
+                        max,
+                        ..Default::default()
+                    }],
+                    aggregation_temporality: temporality,
+                })),
+                ..Default::default()
+            },
+            Metric {
+                name: "request_latency_exp_histogram".to_string(),
+                description: "Request latency distribution (base-2 exponential buckets)"
+                    .to_string(),
+                unit: "ms".to_string(),
+                data: Some(Data::ExponentialHistogram(ExponentialHistogram {
+                    data_points: vec![ExponentialHistogramDataPoint {
+                        attributes: attrs.to_vec(),
+                        start_time_unix_nano: start_ns as u64,
+                        time_unix_nano: ts_ns as u64,
+                        count: h.count,
+                        sum: Some(h.sum),
+                        scale: h.exp_scale,
+                        zero_count: 0,
+                        positive: Some(Buckets {
+                            offset: h.exp_offset,
+                            bucket_counts: h.exp_counts,
+                        }),
+                        min,
+                        max,
+                        ..Default::default()
+                    }],
+                    aggregation_temporality: temporality,
+                })),
+                ..Default::default()
+            },
+        ]
+    }
+}
+
+fn number_point(
+    attrs: &[KeyValue],
+    start_ns: Option<i64>,
+    ts_ns: i64,
+    value: Num,
+) -> NumberDataPoint {
+    NumberDataPoint {
+        attributes: attrs.to_vec(),
+        start_time_unix_nano: start_ns.unwrap_or(0) as u64,
+        time_unix_nano: ts_ns as u64,
+        value: Some(match value {
+            Num::Int(v) => number_data_point::Value::AsInt(v as i64),
+            Num::Double(v) => number_data_point::Value::AsDouble(v),
+        }),
+        ..Default::default()
+    }
+}
+
+// ── OTLP/JSON rendering ─────────────────────────────────────────────────────
+//
+// Hand-rolled rather than the proto crate's serde support, whose output does
+// not follow the OTLP/JSON mapping (nested oneofs, numeric timestamps).
+
+/// Start/end timestamps as OTLP/JSON strings; an unset start is omitted.
+fn times_json(point: &mut serde_json::Value, start_ns: u64, time_ns: u64) {
+    if start_ns > 0 {
+        point["startTimeUnixNano"] = start_ns.to_string().into();
+    }
+    point["timeUnixNano"] = time_ns.to_string().into();
+}
+
+fn number_point_json(p: &NumberDataPoint) -> serde_json::Value {
+    let mut point = serde_json::json!({"attributes": attributes_json(&p.attributes)});
+    times_json(&mut point, p.start_time_unix_nano, p.time_unix_nano);
+    match p.value {
+        Some(number_data_point::Value::AsInt(v)) => point["asInt"] = v.into(),
+        Some(number_data_point::Value::AsDouble(v)) => point["asDouble"] = v.into(),
+        None => {}
+    }
+    point
+}
+
+/// `count`, `sum`, `min`, `max` shared by both histogram point types.
+fn histogram_stats_json(
+    point: &mut serde_json::Value,
+    count: u64,
+    sum: Option<f64>,
+    min: Option<f64>,
+    max: Option<f64>,
+) {
+    point["count"] = count.into();
+    for (key, v) in [("sum", sum), ("min", min), ("max", max)] {
+        if let Some(v) = v {
+            point[key] = v.into();
+        }
+    }
+}
+
+fn metric_json(m: &Metric) -> serde_json::Value {
+    let mut metric = serde_json::json!({
+        "name": m.name,
+        "description": m.description,
+        "unit": m.unit,
+    });
+    match &m.data {
+        Some(Data::Gauge(g)) => {
+            metric["gauge"] = serde_json::json!({
+                "dataPoints": g.data_points.iter().map(number_point_json).collect::<Vec<_>>(),
+            });
+        }
+        Some(Data::Sum(s)) => {
+            metric["sum"] = serde_json::json!({
+                "dataPoints": s.data_points.iter().map(number_point_json).collect::<Vec<_>>(),
+                "aggregationTemporality": s.aggregation_temporality,
+                "isMonotonic": s.is_monotonic,
+            });
+        }
+        Some(Data::Histogram(h)) => {
+            let points: Vec<_> = h
+                .data_points
+                .iter()
+                .map(|p| {
+                    let mut point =
+                        serde_json::json!({"attributes": attributes_json(&p.attributes)});
+                    times_json(&mut point, p.start_time_unix_nano, p.time_unix_nano);
+                    histogram_stats_json(&mut point, p.count, p.sum, p.min, p.max);
+                    point["bucketCounts"] = serde_json::json!(p.bucket_counts);
+                    point["explicitBounds"] = serde_json::json!(p.explicit_bounds);
+                    point
+                })
+                .collect();
+            metric["histogram"] = serde_json::json!({
+                "dataPoints": points,
+                "aggregationTemporality": h.aggregation_temporality,
+            });
+        }
+        Some(Data::ExponentialHistogram(h)) => {
+            let points: Vec<_> = h
+                .data_points
+                .iter()
+                .map(|p| {
+                    let mut point =
+                        serde_json::json!({"attributes": attributes_json(&p.attributes)});
+                    times_json(&mut point, p.start_time_unix_nano, p.time_unix_nano);
+                    histogram_stats_json(&mut point, p.count, p.sum, p.min, p.max);
+                    point["scale"] = p.scale.into();
+                    point["zeroCount"] = p.zero_count.into();
+                    if let Some(b) = &p.positive {
+                        point["positive"] = serde_json::json!({
+                            "offset": b.offset,
+                            "bucketCounts": b.bucket_counts,
+                        });
+                    }
+                    point
+                })
+                .collect();
+            metric["exponentialHistogram"] = serde_json::json!({
+                "dataPoints": points,
+                "aggregationTemporality": h.aggregation_temporality,
+            });
+        }
+        Some(Data::Summary(_)) | None => {}
+    }
+    metric
 }
 
-pub fn metrics_to_otlp_payload(records: &[K8sMetricRecord]) -> serde_json::Value {
+fn resource_metrics_json(rm: &ResourceMetrics) -> serde_json::Value {
+    let resource_attrs = rm
+        .resource
+        .as_ref()
+        .map(|r| attributes_json(&r.attributes))
+        .unwrap_or_default();
     serde_json::json!({
-        "resourceMetrics": records.iter().map(metric_record_to_resource_metrics).collect::<Vec<_>>()
+        "resource": {"attributes": resource_attrs},
+        "scopeMetrics": rm.scope_metrics.iter().map(|sm| serde_json::json!({
+            "scope": {"name": sm.scope.as_ref().map(|s| s.name.as_str()).unwrap_or_default()},
+            "metrics": sm.metrics.iter().map(metric_json).collect::<Vec<_>>(),
+        })).collect::<Vec<_>>()
     })
 }
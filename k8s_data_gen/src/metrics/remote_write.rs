@@ -0,0 +1,181 @@
+//! Prometheus remote_write (protocol 1.0): `K8sMetricRecord` series as a
+//! snappy-compressed `prometheus.WriteRequest` protobuf
+//! ([`crate::remote_write_proto`]).
+//!
+//! Each record field from the OTLP metric table becomes one series labelled
+//! with `service`, `namespace`, `pod`, `node` and `cluster`. Gauges keep their
+//! field name. Counters get a `_total` suffix where they lack one. The latency
+//! histogram becomes a classic `_bucket`/`_sum`/`_count` family. Remote write
+//! has no delta form, so counters and histograms are always cumulative.
+//! With `--kube-metrics` the [`super::kube`] families are added as they are.
+
+use std::collections::BTreeMap;
+
+use prost::Message;
+
+use super::histogram::{CumulativeHistograms, LATENCY_BOUNDS_MS};
+use super::kube::{kube_metrics_enabled, FamilyKind, KubeMetrics, FAMILIES};
+use super::otlp::{Kind, Num, METRICS};
+use super::types::K8sMetricRecord;
+use crate::remote_write_proto::{
+    Label, MetricMetadata, MetricType, Sample, TimeSeries, WriteRequest,
+};
+
+pub(super) const HISTOGRAM_FAMILY: &str = "request_latency_histogram";
+
+/// Prometheus family name for an OTLP metric definition.
+pub(super) fn family_name(name: &str, kind: &Kind) -> String {
+    match kind {
+        Kind::Sum if !name.ends_with("_total") => format!("{}_total", name),
+        _ => name.to_string(),
+    }
+}
+
+/// Builds remote_write requests. Histogram totals carry over between calls, so
+/// use one encoder per live run or ingested file.
+pub struct RemoteWriteEncoder {
+    hist_totals: CumulativeHistograms,
+    /// `--kube-metrics` families.
+    kube: Option<KubeMetrics>,
+}
+
+impl RemoteWriteEncoder {
+    pub fn new() -> Self {
+        RemoteWriteEncoder {
+            hist_totals: CumulativeHistograms::default(),
+            kube: kube_metrics_enabled().then(KubeMetrics::new),
+        }
+    }
+
+    /// `WriteRequest` for `records`, with one series per (family, labels) and
+    /// its samples in time order.
+    pub fn encode(&mut self, records: &[K8sMetricRecord]) -> WriteRequest {
+        let mut series: BTreeMap<Vec<(String, String)>, Vec<Sample>> = BTreeMap::new();
+        let mut push = |name: &str, r: &K8sMetricRecord, extra: Option<(&str, String)>, value| {
+            let mut labels = vec![
+                ("__name__".to_string(), name.to_string()),
+                ("cluster".to_string(), r.cluster.clone()),
+                ("namespace".to_string(), r.namespace.clone()),
+                ("node".to_string(), r.node.clone()),
+                ("pod".to_string(), r.pod.clone()),
+                ("service".to_string(), r.service.clone()),
+            ];
+            labels.extend(r.labels.iter().map(|(k, v)| (k.clone(), v.clone())));
+            if let Some((k, v)) = extra {
+                labels.push((k.to_string(), v));
+            }
+            // `__name__` sorts first: '_' < 'a'.
+            labels.sort();
+            series.entry(labels).or_default().push(Sample {
+                value,
+                timestamp: r._timestamp / 1000,
+            });
+        };
+
+        // Oldest first so running histogram totals see windows in order.
+        let mut ordered: Vec<&K8sMetricRecord> = records.iter().collect();
+        ordered.sort_by_key(|r| r._timestamp);
+        for r in ordered {
+            for m in METRICS {
+                let value = match (m.value)(r) {
+                    Num::Int(v) => v as f64,
+                    Num::Double(v) => v,
+                };
+                push(&family_name(m.name, &m.kind), r, None, value);
+            }
+
+            let window = &r.request_latency_histogram;
+            if window.bucket_counts.is_empty() {
+                // Record from a file written before histograms existed.
+                continue;
+            }
+            let (_, h) = self.hist_totals.add(&r.pod, r._timestamp * 1000, window);
+            let mut cumulative = 0u64;
+            for (i, count) in h.bucket_counts.iter().enumerate() {
+                cumulative += count;
+                let le = LATENCY_BOUNDS_MS
+                    .get(i)
+                    .map(|b| b.to_string())
+                    .unwrap_or_else(|| "+Inf".to_string());
+                let bucket = format!("{}_bucket", HISTOGRAM_FAMILY);
+                push(&bucket, r, Some(("le", le)), cumulative as f64);
+            }
+            push(&format!("{}_sum", HISTOGRAM_FAMILY), r, None, h.sum);
+            push(
+                &format!("{}_count", HISTOGRAM_FAMILY),
+                r,
+                None,
+                h.count as f64,
+            );
+        }
+
+        if let Some(kube) = self.kube.as_mut() {
+            for s in kube.samples(records) {
+                let mut labels = vec![("__name__".to_string(), s.family.name.to_string())];
+                labels.extend(s.labels.into_iter().map(|(k, v)| (k.to_string(), v)));
+                labels.sort();
+                series.entry(labels).or_default().push(Sample {
+                    value: s.value,
+                    timestamp: s.ts_us / 1000,
+                });
+            }
+        }
+
+        let timeseries = series
+            .into_iter()
+            .map(|(labels, samples)| TimeSeries {
+                labels: labels
+                    .into_iter()
+                    .map(|(name, value)| Label { name, value })
+                    .collect(),
+                samples,
+            })
+            .collect();
+        WriteRequest {
+            timeseries,
+            metadata: metadata(self.kube.is_some()),
+        }
+    }
+
+    /// Snappy-compressed protobuf body for `POST .../api/v1/write`.
+    pub fn encode_body(
+        &mut self,
+        records: &[K8sMetricRecord],
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let raw = self.encode(records).encode_to_vec();
+        Ok(snap::raw::Encoder::new().compress_vec(&raw)?)
+    }
+}
+
+fn metadata(kube: bool) -> Vec<MetricMetadata> {
+    let mut out: Vec<MetricMetadata> = METRICS
+        .iter()
+        .map(|m| MetricMetadata {
+            r#type: match m.kind {
+                Kind::Gauge => MetricType::Gauge,
+                Kind::Sum => MetricType::Counter,
+            } as i32,
+            metric_family_name: family_name(m.name, &m.kind),
+            help: m.description.to_string(),
+            unit: m.unit.to_string(),
+        })
+        .collect();
+    out.push(MetricMetadata {
+        r#type: MetricType::Histogram as i32,
+        metric_family_name: HISTOGRAM_FAMILY.to_string(),
+        help: "Request latency distribution".to_string(),
+        unit: "ms".to_string(),
+    });
+    if kube {
+        out.extend(FAMILIES.iter().map(|f| MetricMetadata {
+            r#type: match f.kind {
+                FamilyKind::Gauge => MetricType::Gauge,
+                FamilyKind::Counter => MetricType::Counter,
+            } as i32,
+            metric_family_name: f.name.to_string(),
+            help: f.help.to_string(),
+            unit: String::new(),
+        }));
+    }
+    out
+}
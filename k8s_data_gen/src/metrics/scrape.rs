@@ -0,0 +1,394 @@
+//! Prometheus scrape endpoint (`live --stream metrics --serve ADDR`).
+//!
+//! Instead of pushing, the generator keeps the latest record of every running
+//! pod and serves it on `GET /metrics`. The families match remote_write
+//! ([`super::remote_write`]): the OTLP metric table as gauges and counters plus
+//! the `request_latency_histogram` histogram, labelled with `service`,
+//! `namespace`, `pod`, `node` and `cluster`, plus the [`super::kube`] families
+//! with `--kube-metrics`.
+//!
+//! The format follows the scrape's `Accept` header: OpenMetrics 1.0 when it
+//! asks for `application/openmetrics-text`, the Prometheus text format 0.0.4
+//! otherwise. Samples carry no timestamps, so the scraper stamps them and a pod
+//! that is rolled out or rescheduled simply drops out of the next scrape — the
+//! case staleness handling has to deal with.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Utc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+use super::counters::CounterStore;
+use super::generate::generate_metric_record;
+use super::histogram::{CumulativeHistograms, LATENCY_BOUNDS_MS};
+use super::kube::{kube_metrics_enabled, FamilyKind, KubeMetrics, KubeSample, FAMILIES};
+use super::otlp::{Kind, Num, METRICS};
+use super::remote_write::{family_name, HISTOGRAM_FAMILY};
+use super::types::{K8sMetricRecord, LatencyHistogram};
+use crate::anomaly::{AnomalyState, AnomalyType};
+use crate::events::LiveEvents;
+use crate::lifecycle::pods_at;
+use crate::utils::{print_anomaly_header, seeded_rng};
+
+/// Exposition format, picked per scrape from the `Accept` header.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ExpositionFormat {
+    Text,
+    OpenMetrics,
+}
+
+impl ExpositionFormat {
+    fn from_accept(accept: &str) -> Self {
+        if accept.contains("application/openmetrics-text") {
+            ExpositionFormat::OpenMetrics
+        } else {
+            ExpositionFormat::Text
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            ExpositionFormat::Text => "text/plain; version=0.0.4; charset=utf-8",
+            ExpositionFormat::OpenMetrics => {
+                "application/openmetrics-text; version=1.0.0; charset=utf-8"
+            }
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ExpositionFormat::Text => "text",
+            ExpositionFormat::OpenMetrics => "openmetrics",
+        }
+    }
+}
+
+/// Latest state of one pod: its record and, when the record has a latency
+/// histogram, the running histogram since `start_ns`.
+struct PodSample {
+    record: K8sMetricRecord,
+    histogram: Option<(i64, LatencyHistogram)>,
+}
+
+/// What the tick loop hands to the HTTP side.
+#[derive(Default)]
+struct Snapshot {
+    pods: Vec<PodSample>,
+    /// `--kube-metrics` families for the same pods.
+    kube: Vec<KubeSample>,
+    /// Seconds left of an active anomaly, for the scrape log line.
+    anomaly_remaining: Option<u32>,
+}
+
+fn escape_label(v: &str) -> String {
+    v.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn escape_help(v: &str) -> String {
+    v.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn pod_labels(r: &K8sMetricRecord) -> String {
+    let mut labels = format!(
+        "cluster=\"{}\",namespace=\"{}\",node=\"{}\",pod=\"{}\",service=\"{}\"",
+        escape_label(&r.cluster),
+        escape_label(&r.namespace),
+        escape_label(&r.node),
+        escape_label(&r.pod),
+        escape_label(&r.service)
+    );
+    for (k, v) in &r.labels {
+        labels.push_str(&format!(",{}=\"{}\"", k, escape_label(v)));
+    }
+    labels
+}
+
+fn num(v: Num) -> String {
+    match v {
+        Num::Int(v) => v.to_string(),
+        Num::Double(v) => v.to_string(),
+    }
+}
+
+/// Renders every pod in `format`. Families are grouped, as both formats
+/// require; OpenMetrics counters also get a `_created` sample (pod start).
+fn render(pods: &[PodSample], kube: &[KubeSample], format: ExpositionFormat) -> String {
+    let om = format == ExpositionFormat::OpenMetrics;
+    let labels: Vec<String> = pods.iter().map(|p| pod_labels(&p.record)).collect();
+    let mut out = String::new();
+
+    for m in METRICS {
+        let sample_name = family_name(m.name, &m.kind);
+        // OpenMetrics names the counter family without `_total`.
+        let family = match m.kind {
+            Kind::Sum if om => sample_name.trim_end_matches("_total").to_string(),
+            _ => sample_name.clone(),
+        };
+        let kind = match m.kind {
+            Kind::Gauge => "gauge",
+            Kind::Sum => "counter",
+        };
+        out.push_str(&format!(
+            "# HELP {} {}\n",
+            family,
+            escape_help(m.description)
+        ));
+        out.push_str(&format!("# TYPE {} {}\n", family, kind));
+        for (p, l) in pods.iter().zip(&labels) {
+            let value = num((m.value)(&p.record));
+            out.push_str(&format!("{}{{{}}} {}\n", sample_name, l, value));
+            if om && matches!(m.kind, Kind::Sum) && p.record.pod_start_time > 0 {
+                out.push_str(&format!(
+                    "{}_created{{{}}} {}\n",
+                    family, l, p.record.pod_start_time
+                ));
+            }
+        }
+    }
+
+    out.push_str(&format!(
+        "# HELP {} Request latency distribution\n# TYPE {} histogram\n",
+        HISTOGRAM_FAMILY, HISTOGRAM_FAMILY
+    ));
+    for (p, l) in pods.iter().zip(&labels) {
+        let Some((start_ns, h)) = &p.histogram else {
+            continue;
+        };
+        let mut cumulative = 0u64;
+        for (i, count) in h.bucket_counts.iter().enumerate() {
+            cumulative += count;
+            let le = LATENCY_BOUNDS_MS
+                .get(i)
+                .map(|b| b.to_string())
+                .unwrap_or_else(|| "+Inf".to_string());
+            out.push_str(&format!(
+                "{}_bucket{{{},le=\"{}\"}} {}\n",
+                HISTOGRAM_FAMILY, l, le, cumulative
+            ));
+        }
+        out.push_str(&format!("{}_sum{{{}}} {}\n", HISTOGRAM_FAMILY, l, h.sum));
+        out.push_str(&format!(
+            "{}_count{{{}}} {}\n",
+            HISTOGRAM_FAMILY, l, h.count
+        ));
+        if om {
+            out.push_str(&format!(
+                "{}_created{{{}}} {}\n",
+                HISTOGRAM_FAMILY,
+                l,
+                *start_ns as f64 / 1e9
+            ));
+        }
+    }
+
+    for f in FAMILIES {
+        let samples: Vec<&KubeSample> = kube.iter().filter(|s| s.family.name == f.name).collect();
+        if samples.is_empty() {
+            continue;
+        }
+        let (family, kind) = match f.kind {
+            FamilyKind::Gauge => (f.name, "gauge"),
+            FamilyKind::Counter if om => (f.name.trim_end_matches("_total"), "counter"),
+            FamilyKind::Counter => (f.name, "counter"),
+        };
+        out.push_str(&format!("# HELP {} {}\n", family, escape_help(f.help)));
+        out.push_str(&format!("# TYPE {} {}\n", family, kind));
+        for s in samples {
+            let labels: Vec<String> = s
+                .labels
+                .iter()
+                .map(|(k, v)| format!("{}=\"{}\"", k, escape_label(v)))
+                .collect();
+            let labels = labels.join(",");
+            out.push_str(&format!("{}{{{}}} {}\n", f.name, labels, s.value));
+            if let (true, FamilyKind::Counter, Some(start)) = (om, f.kind, s.start_secs) {
+                out.push_str(&format!("{}_created{{{}}} {}\n", family, labels, start));
+            }
+        }
+    }
+
+    if om {
+        out.push_str("# EOF\n");
+    }
+    out
+}
+
+/// `:9100` listens on every interface, like Prometheus exporters do.
+fn listen_addr(addr: &str) -> String {
+    match addr.strip_prefix(':') {
+        Some(port) => format!("0.0.0.0:{}", port),
+        None => addr.to_string(),
+    }
+}
+
+/// Reads one request head; `None` when the client closed the connection.
+/// Returns the request line and the `Accept` header.
+async fn read_request_head(
+    reader: &mut BufReader<TcpStream>,
+) -> std::io::Result<Option<(String, String)>> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).await? == 0 {
+        return Ok(None);
+    }
+    let mut accept = String::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let l = line.trim_end();
+        if l.is_empty() {
+            break;
+        }
+        if let Some((k, v)) = l.split_once(':') {
+            if k.trim().eq_ignore_ascii_case("accept") {
+                accept = v.trim().to_string();
+            }
+        }
+    }
+    Ok(Some((request_line.trim_end().to_string(), accept)))
+}
+
+async fn serve_connection(
+    stream: TcpStream,
+    snapshot: Arc<Mutex<Snapshot>>,
+) -> std::io::Result<()> {
+    let peer = stream.peer_addr()?;
+    let mut reader = BufReader::new(stream);
+    while let Some((request_line, accept)) = read_request_head(&mut reader).await? {
+        let mut parts = request_line.split_whitespace();
+        let (method, path) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+        let path = path.split('?').next().unwrap_or("");
+
+        let response = if method == "GET" && path == "/metrics" {
+            let format = ExpositionFormat::from_accept(&accept);
+            let (body, pods, anomaly_remaining) = {
+                let s = snapshot.lock().unwrap();
+                let body = render(&s.pods, &s.kube, format);
+                (body, s.pods.len(), s.anomaly_remaining)
+            };
+            let suffix = anomaly_remaining
+                .map(|r| format!(" [ANOMALY ACTIVE: {}s remaining]", r))
+                .unwrap_or_default();
+            println!(
+                "[{}] ✓ scrape from {}: {} pods ({}){}",
+                Utc::now().format("%Y-%m-%d %H:%M:%S"),
+                peer,
+                pods,
+                format.name(),
+                suffix
+            );
+            format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\n\r\n{}",
+                format.content_type(),
+                body.len(),
+                body
+            )
+        } else {
+            let body = "not found; metrics are served on GET /metrics\n";
+            format!(
+                "HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+                body.len(),
+                body
+            )
+        };
+        reader.get_mut().write_all(response.as_bytes()).await?;
+    }
+    Ok(())
+}
+
+/// Live metrics as a Prometheus scrape target on `addr` (`host:port` or
+/// `:port`). Every running pod is refreshed once per second; `--rate` does not
+/// apply because a scrape always returns the whole fleet. `counters` as for
+/// [`super::run_live_metrics`].
+pub async fn run_live_metrics_serve(
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    addr: &str,
+    mut counters: CounterStore,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let addr = listen_addr(addr);
+    let listener = TcpListener::bind(&addr)
+        .await
+        .map_err(|e| format!("listen on '{}' failed: {}", addr, e))?;
+    let mut rng = seeded_rng(seed);
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    let mut anomaly_state = anomaly_type.map(AnomalyState::new);
+    let mut hist_totals = CumulativeHistograms::default();
+    let mut kube = kube_metrics_enabled().then(KubeMetrics::new);
+    let snapshot = Arc::new(Mutex::new(Snapshot::default()));
+
+    println!(
+        "Live metrics (Prometheus scrape) on http://{}/metrics",
+        addr
+    );
+    println!("Formats: text 0.0.4, or OpenMetrics 1.0 when the scraper asks for it");
+    print_anomaly_header(&anomaly_state);
+    let mut events = LiveEvents::new(seed)?;
+    println!("Press Ctrl+C to stop.\n");
+
+    let shared = snapshot.clone();
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let shared = shared.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection(stream, shared).await {
+                    eprintln!("scrape connection error: {}", e);
+                }
+            });
+        }
+    });
+
+    loop {
+        interval.tick().await;
+        if let Some(ref mut s) = anomaly_state {
+            s.tick(&mut rng);
+        }
+
+        let now_us = Utc::now().timestamp_micros();
+        let records: Vec<K8sMetricRecord> = pods_at(now_us, seed)
+            .iter()
+            .map(|pod| {
+                let mut r = generate_metric_record(pod, now_us, anomaly_state.as_ref(), &mut rng);
+                counters.apply(&mut r);
+                r
+            })
+            .collect();
+        if let Some(ref mut events) = events {
+            events.post(now_us, &records, &anomaly_state).await;
+        }
+        let kube_samples = kube
+            .as_mut()
+            .map(|k| k.samples(&records))
+            .unwrap_or_default();
+        let pods: Vec<PodSample> = records
+            .into_iter()
+            .map(|record| {
+                let window = &record.request_latency_histogram;
+                let histogram = (!window.bucket_counts.is_empty())
+                    .then(|| hist_totals.add(&record.pod, now_us * 1000, window));
+                PodSample { record, histogram }
+            })
+            .collect();
+
+        // Forget the state of pods that are gone, so a long run does not
+        // keep every replaced pod around.
+        let running: HashSet<&str> = pods.iter().map(|p| p.record.pod.as_str()).collect();
+        hist_totals.retain(|pod| running.contains(pod));
+        counters.retain(|pod| running.contains(pod));
+
+        let mut s = snapshot.lock().unwrap();
+        s.pods = pods;
+        s.kube = kube_samples;
+        s.anomaly_remaining = anomaly_state
+            .as_ref()
+            .filter(|a| a.is_active())
+            .map(|a| a.remaining_secs);
+    }
+}
@@ -1,6 +1,8 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct K8sMetricRecord {
     pub _timestamp: i64,
     pub cluster: String,
@@ -19,6 +21,45 @@ pub struct K8sMetricRecord {
     pub error_rate: f64,
     pub restarts: u32,
     pub container_cpu_time: f64, // cumulative CPU time in seconds
+    #[serde(default)]
+    pub pod_start_time: i64, // unix seconds; start of container_cpu_time
     pub container_id: String,
     pub total_payment_req: u64,
+    // Running totals since the pod started, like `container_cpu_time`.
+    #[serde(default)]
+    pub requests_total: u64,
+    #[serde(default)]
+    pub network_rx_bytes_total: u64,
+    #[serde(default)]
+    pub network_tx_bytes_total: u64,
+    #[serde(default)]
+    pub request_latency_histogram: LatencyHistogram,
+    /// `--cardinality` stress labels, written as top-level fields.
+    #[serde(flatten)]
+    pub labels: BTreeMap<String, String>,
+}
+
+impl K8sMetricRecord {
+    /// Top-level keys of a serialized record.
+    pub fn field_names() -> Vec<String> {
+        match serde_json::to_value(K8sMetricRecord::default()) {
+            Ok(serde_json::Value::Object(map)) => map.into_iter().map(|(k, _)| k).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Latencies of the requests served in the second before `_timestamp`, as
+/// explicit buckets (`LATENCY_BOUNDS_MS`, plus an overflow bucket) and as a
+/// base-2 exponential histogram at `exp_scale`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    pub count: u64,
+    pub sum: f64,
+    pub min: f64,
+    pub max: f64,
+    pub bucket_counts: Vec<u64>,
+    pub exp_scale: i32,
+    pub exp_offset: i32,
+    pub exp_counts: Vec<u64>,
 }
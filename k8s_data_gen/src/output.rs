@@ -0,0 +1,210 @@
+//! On-disk formats for historical files.
+//!
+//! Writers pick a layout (`--format json|ndjson`) and a codec
+//! (`--compress gzip|zstd`). Readers need neither flag: the codec is detected
+//! from the file's magic bytes and the layout from its first non-blank byte
+//! (`[` for a JSON array, anything else is NDJSON).
+//!
+//! Both gzip and zstd allow several compressed members/frames back to back in
+//! one file, which decode as their concatenation. Sharded runs rely on this:
+//! each shard is compressed in parallel and the merge step only copies bytes.
+
+use std::{
+    fs::File,
+    io::{self, BufRead, BufReader, BufWriter, Write},
+};
+
+use flate2::{read::MultiGzDecoder, write::GzEncoder};
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const ZSTD_LEVEL: i32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// One JSON array: `[{..},{..}]`.
+    Json,
+    /// One record per line, no enclosing array.
+    Ndjson,
+}
+
+impl OutputFormat {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
+            _ => Err(format!("unknown --format '{}' (use json or ndjson)", s)),
+        }
+    }
+
+    pub fn ext(self) -> &'static str {
+        match self {
+            OutputFormat::Json => ".json",
+            OutputFormat::Ndjson => ".ndjson",
+        }
+    }
+
+    pub fn open(self) -> &'static [u8] {
+        match self {
+            OutputFormat::Json => b"[",
+            OutputFormat::Ndjson => b"",
+        }
+    }
+
+    pub fn close(self) -> &'static [u8] {
+        match self {
+            OutputFormat::Json => b"]",
+            OutputFormat::Ndjson => b"",
+        }
+    }
+
+    /// Written before every record but the first.
+    pub fn separator(self) -> &'static [u8] {
+        match self {
+            OutputFormat::Json => b",",
+            OutputFormat::Ndjson => b"",
+        }
+    }
+
+    /// Written after every record.
+    pub fn terminator(self) -> &'static [u8] {
+        match self {
+            OutputFormat::Json => b"",
+            OutputFormat::Ndjson => b"\n",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "none" => Ok(Compression::None),
+            "gzip" | "gz" => Ok(Compression::Gzip),
+            "zstd" | "zst" => Ok(Compression::Zstd),
+            _ => Err(format!("unknown --compress '{}' (use gzip or zstd)", s)),
+        }
+    }
+
+    pub fn ext(self) -> &'static str {
+        match self {
+            Compression::None => "",
+            Compression::Gzip => ".gz",
+            Compression::Zstd => ".zst",
+        }
+    }
+}
+
+/// A file writer that compresses on the fly. Call [`Sink::finish`] to write
+/// the codec trailer; dropping a sink without it leaves a truncated file.
+pub enum Sink {
+    Plain(BufWriter<File>),
+    Gzip(GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+}
+
+impl Sink {
+    pub fn create(path: &str, compress: Compression) -> io::Result<Sink> {
+        Sink::new(BufWriter::new(File::create(path)?), compress)
+    }
+
+    /// Starts a new compressed member/frame on an open file.
+    pub fn new(file: BufWriter<File>, compress: Compression) -> io::Result<Sink> {
+        Ok(match compress {
+            Compression::None => Sink::Plain(file),
+            Compression::Gzip => Sink::Gzip(GzEncoder::new(file, flate2::Compression::default())),
+            Compression::Zstd => Sink::Zstd(zstd::Encoder::new(file, ZSTD_LEVEL)?),
+        })
+    }
+
+    /// Flushes and returns the underlying file so more frames can follow.
+    pub fn finish(self) -> io::Result<BufWriter<File>> {
+        let mut file = match self {
+            Sink::Plain(w) => w,
+            Sink::Gzip(w) => w.finish()?,
+            Sink::Zstd(w) => w.finish()?,
+        };
+        file.flush()?;
+        Ok(file)
+    }
+}
+
+impl Write for Sink {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Sink::Plain(w) => w.write(buf),
+            Sink::Gzip(w) => w.write(buf),
+            Sink::Zstd(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Sink::Plain(w) => w.flush(),
+            Sink::Gzip(w) => w.flush(),
+            Sink::Zstd(w) => w.flush(),
+        }
+    }
+}
+
+/// Opens a historical file for reading, decompressing gzip or zstd by magic
+/// bytes. Returns the reader and the layout found at the start of the data.
+pub fn open_input(path: &str) -> io::Result<(Box<dyn BufRead + Send>, OutputFormat)> {
+    let mut file = BufReader::new(File::open(path)?);
+    let head = file.fill_buf()?;
+    let mut reader: Box<dyn BufRead + Send> = if head.starts_with(&GZIP_MAGIC) {
+        Box::new(BufReader::new(MultiGzDecoder::new(file)))
+    } else if head.starts_with(&ZSTD_MAGIC) {
+        Box::new(BufReader::new(zstd::Decoder::with_buffer(file)?))
+    } else {
+        Box::new(file)
+    };
+
+    // Skip leading whitespace to find the first significant byte.
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok((reader, OutputFormat::Ndjson));
+        }
+        match buf.iter().position(|b| !b.is_ascii_whitespace()) {
+            Some(i) => {
+                let format = if buf[i] == b'[' {
+                    OutputFormat::Json
+                } else {
+                    OutputFormat::Ndjson
+                };
+                reader.consume(i);
+                return Ok((reader, format));
+            }
+            None => {
+                let n = buf.len();
+                reader.consume(n);
+            }
+        }
+    }
+}
+
+/// Writes `bytes` onto `file` as one complete member/frame of its own.
+pub fn write_frame(
+    file: BufWriter<File>,
+    bytes: &[u8],
+    compress: Compression,
+) -> io::Result<BufWriter<File>> {
+    if bytes.is_empty() {
+        return Ok(file);
+    }
+    let mut sink = Sink::new(file, compress)?;
+    sink.write_all(bytes)?;
+    sink.finish()
+}
+
+/// Copies a finished file (e.g. a shard's compressed body) onto `out` as is.
+pub fn copy_file(path: &str, out: &mut impl Write) -> io::Result<u64> {
+    io::copy(&mut File::open(path)?, out)
+}
@@ -0,0 +1,266 @@
+//! Target-rate control for live mode.
+//!
+//! A [`RateSchedule`] gives the target events/sec at each moment since start;
+//! a [`TokenBucket`] turns it into a per-tick event budget. The bucket holds at
+//! most one second of budget, so a slow POST is caught up without an unbounded
+//! burst. Events that overshoot the budget (a trace with more spans than tokens
+//! left) are paid back from the next refill.
+//!
+//! Spec syntax (`--rate`, `--logs-rate`, `--metrics-rate`, `--traces-rate`):
+//!
+//! ```text
+//! 500                      constant 500 eps
+//! ramp:100..2000/5m        linear 100 → 2000 eps over 5 min, then hold
+//! step:100/1m,500/1m,1000  100 eps for 1 min, 500 for 1 min, then 1000
+//! burst:100,5000/10s@1m    100 eps, 5000 eps for 10 s at the start of every minute
+//! ```
+
+use std::time::Instant;
+
+use rand::{seq::SliceRandom, Rng};
+
+use crate::utils::parse_duration_secs;
+
+#[derive(Debug, Clone)]
+pub enum RateSchedule {
+    Constant(f64),
+    Ramp {
+        from: f64,
+        to: f64,
+        over_secs: f64,
+    },
+    /// `(eps, duration_secs)` stages; the last stage holds forever.
+    Step(Vec<(f64, f64)>),
+    Burst {
+        base: f64,
+        peak: f64,
+        for_secs: f64,
+        every_secs: f64,
+    },
+}
+
+impl RateSchedule {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let bad = |why: &str| format!("invalid rate '{}': {}", spec, why);
+        let eps = |s: &str| -> Result<f64, String> {
+            match s.trim().parse::<f64>() {
+                Ok(v) if v >= 0.0 && v.is_finite() => Ok(v),
+                _ => Err(bad(&format!("'{}' is not a non-negative number", s))),
+            }
+        };
+        let secs = |s: &str| -> Result<f64, String> {
+            match parse_duration_secs(s) {
+                Some(v) if v > 0 => Ok(v as f64),
+                _ => Err(bad(&format!("'{}' is not a duration (30s, 5m, 1h)", s))),
+            }
+        };
+
+        let (kind, body) = spec.split_once(':').unwrap_or(("", spec));
+        match kind {
+            "" => Ok(RateSchedule::Constant(eps(body)?)),
+            "ramp" => {
+                let (range, over) = body
+                    .split_once('/')
+                    .ok_or_else(|| bad("expected ramp:FROM..TO/DURATION"))?;
+                let (from, to) = range
+                    .split_once("..")
+                    .ok_or_else(|| bad("expected ramp:FROM..TO/DURATION"))?;
+                Ok(RateSchedule::Ramp {
+                    from: eps(from)?,
+                    to: eps(to)?,
+                    over_secs: secs(over)?,
+                })
+            }
+            "step" => {
+                let mut stages = Vec::new();
+                let parts: Vec<&str> = body.split(',').collect();
+                for (i, part) in parts.iter().enumerate() {
+                    match part.split_once('/') {
+                        Some((r, d)) => stages.push((eps(r)?, secs(d)?)),
+                        None if i == parts.len() - 1 => stages.push((eps(part)?, f64::INFINITY)),
+                        None => return Err(bad("every stage but the last needs /DURATION")),
+                    }
+                }
+                Ok(RateSchedule::Step(stages))
+            }
+            "burst" => {
+                let (base, rest) = body
+                    .split_once(',')
+                    .ok_or_else(|| bad("expected burst:BASE,PEAK/FOR@EVERY"))?;
+                let (peak, rest) = rest
+                    .split_once('/')
+                    .ok_or_else(|| bad("expected burst:BASE,PEAK/FOR@EVERY"))?;
+                let (for_, every) = rest
+                    .split_once('@')
+                    .ok_or_else(|| bad("expected burst:BASE,PEAK/FOR@EVERY"))?;
+                let (for_secs, every_secs) = (secs(for_)?, secs(every)?);
+                if for_secs > every_secs {
+                    return Err(bad("burst length exceeds its period"));
+                }
+                Ok(RateSchedule::Burst {
+                    base: eps(base)?,
+                    peak: eps(peak)?,
+                    for_secs,
+                    every_secs,
+                })
+            }
+            other => Err(bad(&format!(
+                "unknown schedule '{}' (use ramp, step or burst)",
+                other
+            ))),
+        }
+    }
+
+    /// Target events/sec `t` seconds after start.
+    pub fn rate_at(&self, t: f64) -> f64 {
+        match self {
+            RateSchedule::Constant(r) => *r,
+            RateSchedule::Ramp {
+                from,
+                to,
+                over_secs,
+            } => from + (to - from) * (t / over_secs).min(1.0),
+            RateSchedule::Step(stages) => {
+                let mut until = 0.0;
+                for (r, d) in stages {
+                    until += d;
+                    if t < until {
+                        return *r;
+                    }
+                }
+                stages.last().map(|(r, _)| *r).unwrap_or(0.0)
+            }
+            RateSchedule::Burst {
+                base,
+                peak,
+                for_secs,
+                every_secs,
+            } => {
+                if t % every_secs < *for_secs {
+                    *peak
+                } else {
+                    *base
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            RateSchedule::Constant(r) => format!("{} eps", r),
+            RateSchedule::Ramp {
+                from,
+                to,
+                over_secs,
+            } => format!("ramp {} → {} eps over {}s", from, to, over_secs),
+            RateSchedule::Step(stages) => stages
+                .iter()
+                .map(|(r, d)| {
+                    if d.is_finite() {
+                        format!("{} eps for {}s", r, d)
+                    } else {
+                        format!("{} eps", r)
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" → "),
+            RateSchedule::Burst {
+                base,
+                peak,
+                for_secs,
+                every_secs,
+            } => format!(
+                "{} eps, bursting to {} eps for {}s every {}s",
+                base, peak, for_secs, every_secs
+            ),
+        }
+    }
+}
+
+pub struct TokenBucket {
+    schedule: RateSchedule,
+    started: Instant,
+    last: Instant,
+    tokens: f64,
+}
+
+impl TokenBucket {
+    pub fn new(schedule: RateSchedule) -> Self {
+        let now = Instant::now();
+        TokenBucket {
+            schedule,
+            started: now,
+            last: now,
+            tokens: 0.0,
+        }
+    }
+
+    /// Adds the budget accrued since the last refill and returns the whole
+    /// events available now.
+    pub fn refill(&mut self) -> usize {
+        let now = Instant::now();
+        let t0 = self.last.duration_since(self.started).as_secs_f64();
+        let t1 = now.duration_since(self.started).as_secs_f64();
+        self.last = now;
+
+        // Trapezoid over the interval keeps ramps accurate at a 1 s tick.
+        let r0 = self.schedule.rate_at(t0);
+        let r1 = self.schedule.rate_at(t1);
+        let cap = r1.max(1.0);
+        self.tokens = (self.tokens + (r0 + r1) / 2.0 * (t1 - t0)).min(cap);
+        self.tokens.max(0.0) as usize
+    }
+
+    pub fn consume(&mut self, n: usize) {
+        self.tokens -= n as f64;
+    }
+
+    pub fn describe(&self) -> String {
+        self.schedule.describe()
+    }
+}
+
+/// Live ticks are one second apart.
+const TICK_US: i64 = 1_000_000;
+
+/// Timestamp of event `i` of `n` in the tick ending at `now_us`, spaced
+/// evenly so no two events of a tick share one.
+pub fn spread_us(now_us: i64, i: usize, n: usize) -> i64 {
+    now_us - TICK_US + (i as i64 + 1) * TICK_US / n.max(1) as i64
+}
+
+/// Pods to emit one record each for this tick, with the record's timestamp:
+/// every running pod at `now_us` without a rate limit, otherwise the bucket's
+/// budget drawn without replacement, spread across the tick. A budget above
+/// the pod count takes further rounds over the pods, each at its own time.
+pub fn pick_pods<'a, T>(
+    bucket: Option<&mut TokenBucket>,
+    pods: &'a [T],
+    now_us: i64,
+    rng: &mut impl Rng,
+) -> Vec<(&'a T, i64)> {
+    match bucket {
+        None => pods.iter().map(|pod| (pod, now_us)).collect(),
+        Some(_) if pods.is_empty() => Vec::new(),
+        Some(b) => {
+            let n = b.refill();
+            b.consume(n);
+            let mut picked = Vec::with_capacity(n);
+            while picked.len() < n {
+                picked.extend(pods.choose_multiple(rng, (n - picked.len()).min(pods.len())));
+            }
+            picked
+                .into_iter()
+                .enumerate()
+                .map(|(i, pod)| (pod, spread_us(now_us, i, n)))
+                .collect()
+        }
+    }
+}
+
+pub fn print_rate_header(bucket: &Option<TokenBucket>, unlimited: &str) {
+    match bucket {
+        Some(b) => println!("Rate: {}", b.describe()),
+        None => println!("Rate: {}. Add --rate <eps> to control.", unlimited),
+    }
+}
@@ -0,0 +1,319 @@
+//! Prometheus remote_write stand-in — decodes and checks what the generator sends.
+//!
+//! Listens for `POST` requests, snappy-decodes and protobuf-decodes each body as
+//! a `prometheus.WriteRequest`, and checks it against the remote_write 1.0 rules
+//! the generator is meant to follow:
+//!
+//!   - headers: `Content-Encoding: snappy`, `Content-Type: application/x-protobuf`,
+//!     `X-Prometheus-Remote-Write-Version: 0.1.0`
+//!   - every series has a `__name__`, labels are sorted and unique, no empty values
+//!   - `cluster`, `namespace`, `node` and `pod` labels are present (for the
+//!     `--kube-metrics` families, the labels their exporter always sets)
+//!   - samples within a series are in timestamp order
+//!   - counters never go down between requests (a drop is reported as a reset)
+//!   - samples older than the series' last one are counted as out of order
+//!     (OpenObserve accepts them; Prometheus itself would reject them)
+//!   - histogram buckets grow with `le` and the `+Inf` bucket equals `_count`
+//!
+//! A passing request gets 204; a failing one gets 400 with the first error.
+//!
+//! USAGE:
+//!   cargo run --bin remote_write_sink -- [--listen 127.0.0.1:9201] [--requests N]
+//!
+//!   Then point the generator at it:
+//!   O2_API_BASE=http://127.0.0.1:9201 cargo run -- live --stream metrics --remote-write
+//!
+//!   With `--requests N` the sink exits after N requests: status 0 if all passed,
+//!   1 otherwise. Handy in scripts.
+
+mod remote_write_proto;
+
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+
+use prost::Message;
+use remote_write_proto::{MetricType, WriteRequest};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{mpsc, Mutex};
+
+/// Labels every series of a family must carry.
+fn required_labels(name: &str) -> &'static [&'static str] {
+    if name.starts_with("kube_deployment_") {
+        &["cluster", "namespace", "deployment"]
+    } else if name.starts_with("kube_node_") || name.starts_with("node_") {
+        &["cluster", "node"]
+    } else if name.starts_with("kube_pod_") {
+        &["cluster", "namespace", "pod", "uid"]
+    } else {
+        &["cluster", "namespace", "node", "pod"]
+    }
+}
+
+type Labels = Vec<(String, String)>;
+
+/// Counter state carried across requests.
+#[derive(Default)]
+struct Checker {
+    /// Last (timestamp ms, value) per counter series.
+    counters: HashMap<Labels, (i64, f64)>,
+    resets: usize,
+    /// Newest timestamp (ms) seen per series.
+    latest: HashMap<Labels, i64>,
+    out_of_order: usize,
+}
+
+struct Request {
+    headers: HashMap<String, String>,
+    body: Vec<u8>,
+}
+
+impl Checker {
+    /// Returns a one-line summary, or the first violation.
+    fn check(&mut self, req: &Request) -> Result<String, String> {
+        for (name, want) in [
+            ("content-encoding", "snappy"),
+            ("content-type", "application/x-protobuf"),
+            ("x-prometheus-remote-write-version", "0.1.0"),
+        ] {
+            match req.headers.get(name) {
+                Some(v) if v == want => {}
+                other => return Err(format!("header {}: want '{}', got {:?}", name, want, other)),
+            }
+        }
+
+        let raw = snap::raw::Decoder::new()
+            .decompress_vec(&req.body)
+            .map_err(|e| format!("snappy: {}", e))?;
+        let wr = WriteRequest::decode(&raw[..]).map_err(|e| format!("protobuf: {}", e))?;
+        if wr.timeseries.is_empty() {
+            return Err("empty WriteRequest".to_string());
+        }
+
+        let types: HashMap<&str, i32> = wr
+            .metadata
+            .iter()
+            .map(|m| (m.metric_family_name.as_str(), m.r#type))
+            .collect();
+
+        // (labels without `le`, timestamp) → [(le, count)] for histogram buckets,
+        // and the matching `_count` values.
+        let mut buckets: BTreeMap<(Labels, i64), Vec<(f64, f64)>> = BTreeMap::new();
+        let mut counts: HashMap<(Labels, i64), f64> = HashMap::new();
+        let mut samples = 0usize;
+
+        for ts in &wr.timeseries {
+            let labels: Labels = ts
+                .labels
+                .iter()
+                .map(|l| (l.name.clone(), l.value.clone()))
+                .collect();
+            let name = match labels.first() {
+                Some((k, v)) if k == "__name__" => v.clone(),
+                _ => return Err(format!("series without leading __name__: {:?}", labels)),
+            };
+            if labels.windows(2).any(|w| w[0].0 >= w[1].0) {
+                return Err(format!("{}: labels not sorted/unique", name));
+            }
+            if let Some((k, _)) = labels.iter().find(|(_, v)| v.is_empty()) {
+                return Err(format!("{}: empty label '{}'", name, k));
+            }
+            for want in required_labels(&name) {
+                if !labels.iter().any(|(k, _)| k == want) {
+                    return Err(format!("{}: missing label '{}'", name, want));
+                }
+            }
+            if ts.samples.is_empty() {
+                return Err(format!("{}: series without samples", name));
+            }
+            if ts
+                .samples
+                .windows(2)
+                .any(|w| w[0].timestamp > w[1].timestamp)
+            {
+                return Err(format!("{}: samples out of order", name));
+            }
+            samples += ts.samples.len();
+            let latest = self.latest.entry(labels.clone()).or_insert(i64::MIN);
+            for s in &ts.samples {
+                if s.timestamp < *latest {
+                    self.out_of_order += 1;
+                }
+                *latest = (*latest).max(s.timestamp);
+            }
+
+            let family = name
+                .strip_suffix("_bucket")
+                .or_else(|| name.strip_suffix("_count"))
+                .filter(|f| types.get(f) == Some(&(MetricType::Histogram as i32)))
+                .unwrap_or(&name);
+            match types.get(family).copied() {
+                Some(t) if t == MetricType::Counter as i32 => {
+                    for s in &ts.samples {
+                        if let Some((last_ts, last)) =
+                            self.counters.insert(labels.clone(), (s.timestamp, s.value))
+                        {
+                            if s.timestamp > last_ts && s.value < last {
+                                self.resets += 1;
+                            }
+                        }
+                    }
+                }
+                Some(t) if t == MetricType::Histogram as i32 => {
+                    let base: Vec<_> = labels
+                        .iter()
+                        .filter(|(k, _)| k != "le" && k != "__name__")
+                        .cloned()
+                        .collect();
+                    if name.ends_with("_bucket") {
+                        let le = labels
+                            .iter()
+                            .find(|(k, _)| k == "le")
+                            .ok_or_else(|| format!("{}: bucket without le", name))?;
+                        let le: f64 =
+                            le.1.parse()
+                                .map_err(|_| format!("{}: bad le '{}'", name, le.1))?;
+                        for s in &ts.samples {
+                            buckets
+                                .entry((base.clone(), s.timestamp))
+                                .or_default()
+                                .push((le, s.value));
+                        }
+                    } else if name.ends_with("_count") {
+                        for s in &ts.samples {
+                            counts.insert((base.clone(), s.timestamp), s.value);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for ((base, t), mut bs) in buckets {
+            bs.sort_by(|a, b| a.0.total_cmp(&b.0));
+            if bs.windows(2).any(|w| w[0].1 > w[1].1) {
+                return Err(format!(
+                    "histogram {:?}@{}: buckets not cumulative",
+                    base, t
+                ));
+            }
+            let inf = bs.last().map(|b| b.1).unwrap_or(0.0);
+            if bs.last().map(|b| b.0) != Some(f64::INFINITY) {
+                return Err(format!("histogram {:?}@{}: no +Inf bucket", base, t));
+            }
+            if counts.get(&(base.clone(), t)) != Some(&inf) {
+                return Err(format!("histogram {:?}@{}: +Inf bucket != _count", base, t));
+            }
+        }
+
+        Ok(format!(
+            "{} series, {} samples, {} metadata; so far {} counter resets, {} out-of-order samples",
+            wr.timeseries.len(),
+            samples,
+            wr.metadata.len(),
+            self.resets,
+            self.out_of_order
+        ))
+    }
+}
+
+/// Reads one HTTP/1.1 request; `None` when the client closed the connection.
+async fn read_request(reader: &mut BufReader<TcpStream>) -> std::io::Result<Option<Request>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        return Ok(None);
+    }
+    let mut headers = HashMap::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line).await?;
+        let l = line.trim_end();
+        if l.is_empty() {
+            break;
+        }
+        if let Some((k, v)) = l.split_once(':') {
+            headers.insert(k.trim().to_ascii_lowercase(), v.trim().to_string());
+        }
+    }
+    let len: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; len];
+    reader.read_exact(&mut body).await?;
+    Ok(Some(Request { headers, body }))
+}
+
+async fn serve(
+    stream: TcpStream,
+    checker: &Mutex<Checker>,
+    done: mpsc::UnboundedSender<bool>,
+) -> std::io::Result<()> {
+    let mut reader = BufReader::new(stream);
+    while let Some(req) = read_request(&mut reader).await? {
+        let result = checker.lock().await.check(&req);
+        let response = match &result {
+            Ok(summary) => {
+                println!("✓ {}", summary);
+                "HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n".to_string()
+            }
+            Err(e) => {
+                eprintln!("✗ {}", e);
+                format!(
+                    "HTTP/1.1 400 Bad Request\r\nContent-Length: {}\r\n\r\n{}",
+                    e.len(),
+                    e
+                )
+            }
+        };
+        reader.get_mut().write_all(response.as_bytes()).await?;
+        let _ = done.send(result.is_ok());
+    }
+    Ok(())
+}
+
+fn parse_flag(args: &[String], flag: &str) -> Option<String> {
+    args.iter()
+        .position(|a| a == flag)
+        .and_then(|i| args.get(i + 1))
+        .cloned()
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = env::args().collect();
+    let listen = parse_flag(&args, "--listen").unwrap_or_else(|| "127.0.0.1:9201".to_string());
+    let limit: Option<usize> = parse_flag(&args, "--requests").and_then(|s| s.parse().ok());
+
+    let listener = TcpListener::bind(&listen).await?;
+    println!("remote_write sink listening on http://{}", listen);
+    let checker: &'static Mutex<Checker> = Box::leak(Box::default());
+    let (done_tx, mut done_rx) = mpsc::unbounded_channel();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let done = done_tx.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve(stream, checker, done).await {
+                    eprintln!("connection error: {}", e);
+                }
+            });
+        }
+    });
+
+    let (mut seen, mut failed) = (0usize, 0usize);
+    while let Some(ok) = done_rx.recv().await {
+        seen += 1;
+        if !ok {
+            failed += 1;
+        }
+        if limit == Some(seen) {
+            break;
+        }
+    }
+    println!("{} requests, {} failed", seen, failed);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
@@ -20,7 +20,7 @@ use chrono::Utc;
 use reqwest::Client;
 use serde::Serialize;
 
-use crate::config::{api_base, password, username, DEFAULT_ORG};
+use crate::config::{api_base, org, password, username};
 
 const REPRO_NAMESPACE: &str = "ecs-prod";
 const REPRO_CLUSTER: &str = "ecs-cluster-1";
@@ -61,7 +61,7 @@ async fn send_ecs_logs(client: &Client) -> Result<(), Box<dyn std::error::Error>
     let url = format!(
         "{}/api/{}/{}/_json",
         api_base(),
-        DEFAULT_ORG,
+        org(),
         REPRO_LOG_STREAM
     );
 
@@ -101,7 +101,7 @@ async fn send_ecs_logs(client: &Client) -> Result<(), Box<dyn std::error::Error>
 }
 
 async fn send_repro_traces(client: &Client) -> Result<(), Box<dyn std::error::Error>> {
-    let url = format!("{}/api/{}/v1/traces", api_base(), DEFAULT_ORG);
+    let url = format!("{}/api/{}/v1/traces", api_base(), org());
 
     let now_ns = Utc::now().timestamp_nanos_opt().unwrap_or(0);
     let resource_spans: Vec<serde_json::Value> = (0..REPRO_RECORD_COUNT)
@@ -0,0 +1,123 @@
+/// `run` — backfill-then-live continuous mode.
+///
+/// Streams the last `--backfill` window oldest-first straight to the endpoint
+/// (no intermediate file), then hands each signal over to its live loop. The
+/// backfill cursor chases the wall clock and stops only once it has caught up,
+/// so the handover gap is at most one historical interval. Pod names and
+/// placement are functions of (seed, pod, time), and the metric counters are
+/// handed from the backfill to the live loop, so both continue unchanged
+/// across the switch.
+///
+/// The backfill always goes over HTTP (`/_json`, OTLP HTTP K8s spans); the
+/// live phase takes `live`'s transport flags.
+use crate::anomaly::AnomalyType;
+use crate::logs::LogsEncoding;
+use crate::metrics::CounterStore;
+use crate::rate::RateSchedule;
+use crate::{audit, logs, metrics, traces};
+
+pub struct LiveRates {
+    pub logs: Option<RateSchedule>,
+    pub metrics: Option<RateSchedule>,
+    pub traces: Option<RateSchedule>,
+    pub audit: Option<RateSchedule>,
+}
+
+/// `--grpc`, `--otlp`, `--remote-write` and `--serve`: how the live loops
+/// send (audit always posts to `/_json`).
+pub struct LiveTransport {
+    pub grpc: bool,
+    pub otlp: Option<LogsEncoding>,
+    pub remote_write: bool,
+    pub serve: Option<String>,
+}
+
+impl LiveTransport {
+    pub async fn logs(
+        &self,
+        anomaly_type: Option<AnomalyType>,
+        seed: u64,
+        rate: Option<RateSchedule>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.grpc {
+            logs::run_live_logs_grpc(anomaly_type, seed, rate).await
+        } else {
+            logs::run_live_logs(anomaly_type, seed, rate, self.otlp).await
+        }
+    }
+
+    /// `counters` as for [`metrics::run_live_metrics`].
+    pub async fn metrics(
+        &self,
+        anomaly_type: Option<AnomalyType>,
+        seed: u64,
+        rate: Option<RateSchedule>,
+        counters: CounterStore,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(ref addr) = self.serve {
+            metrics::run_live_metrics_serve(anomaly_type, seed, addr, counters).await
+        } else if self.remote_write {
+            metrics::run_live_metrics_remote_write(anomaly_type, seed, rate, counters).await
+        } else if self.grpc {
+            metrics::run_live_metrics_grpc(anomaly_type, seed, rate, counters).await
+        } else {
+            metrics::run_live_metrics(anomaly_type, seed, rate, counters).await
+        }
+    }
+
+    pub async fn traces(
+        &self,
+        anomaly_type: Option<AnomalyType>,
+        seed: u64,
+        rate: Option<RateSchedule>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        if self.grpc {
+            traces::run_live_traces_grpc(anomaly_type, seed, rate).await
+        } else {
+            traces::run_live_traces(anomaly_type, seed, rate).await
+        }
+    }
+}
+
+pub async fn run_continuous(
+    stream: &str,
+    start_us: i64,
+    anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rates: LiveRates,
+    transport: &LiveTransport,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let logs = async {
+        logs::run_backfill_logs(start_us, seed).await?;
+        transport.logs(anomaly_type.clone(), seed, rates.logs).await
+    };
+    let metrics = async {
+        let counters = metrics::run_backfill_metrics(start_us, seed).await?;
+        transport
+            .metrics(anomaly_type.clone(), seed, rates.metrics, counters)
+            .await
+    };
+    let traces = async {
+        traces::run_backfill_traces(start_us, seed).await?;
+        transport
+            .traces(anomaly_type.clone(), seed, rates.traces)
+            .await
+    };
+    let audit = async {
+        audit::run_backfill_audit(start_us, seed).await?;
+        audit::run_live_audit(anomaly_type.clone(), seed, rates.audit).await
+    };
+
+    match stream {
+        "logs" => logs.await,
+        "metrics" => metrics.await,
+        "traces" => traces.await,
+        "audit" => audit.await,
+        "all" => tokio::try_join!(logs, metrics, traces, audit).map(|_| ()),
+        other => Err(format!(
+            "Unknown stream '{}'. Valid: logs, metrics, traces, audit, all",
+            other
+        )
+        .into()),
+    }
+}
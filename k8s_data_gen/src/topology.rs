@@ -1,12 +1,42 @@
+use std::sync::OnceLock;
+
+use serde::Deserialize;
+
+/// Native format of a container's raw log lines (the `log` field).
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LogFormat {
+    /// nginx `combined` access log; warnings and errors in the error-log format.
+    Nginx,
+    /// ingress-nginx `upstreaminfo` access log; controller warnings and errors in klog.
+    IngressNginx,
+    /// PostgreSQL stderr log with `log_line_prefix = '%m [%p] '`.
+    Postgres,
+    /// Kubernetes klog (`I1018 10:54:21.123456       1 file.go:42] msg`).
+    Klog,
+    /// Go-kit style logfmt (`ts=… caller=… level=info msg="…"`).
+    Logfmt,
+    /// JSON lines from logback's JSON encoder; errors carry a Java stack trace.
+    JavaJson,
+    /// JSON lines from zap; errors carry a Go stack trace.
+    #[default]
+    GoJson,
+}
+
+#[derive(Clone)]
 pub struct Pod {
     pub namespace: &'static str,
     pub service: &'static str,
     pub container: &'static str,
+    pub log_format: LogFormat,
     pub base_cpu: u32,
     pub base_mem: u32,
     pub base_rps: u32,
     pub base_rt: f64,
     pub base_err: f64,
+    /// Deployment replica count; `base_rps` is split across replicas, the other
+    /// base values are per pod.
+    pub replicas: u32,
 }
 
 pub const PODS: &[Pod] = &[
@@ -14,106 +44,154 @@ pub const PODS: &[Pod] = &[
         namespace: "payments",
         service: "payments-api",
         container: "api",
+        log_format: LogFormat::JavaJson,
         base_cpu: 350,
         base_mem: 512,
         base_rps: 220,
         base_rt: 45.0,
         base_err: 0.005,
+        replicas: 3,
     },
     Pod {
         namespace: "payments",
         service: "payments-worker",
         container: "worker",
+        log_format: LogFormat::JavaJson,
         base_cpu: 180,
         base_mem: 256,
         base_rps: 80,
         base_rt: 30.0,
         base_err: 0.002,
+        replicas: 2,
     },
     Pod {
         namespace: "inventory",
         service: "inventory-service",
         container: "service",
+        log_format: LogFormat::GoJson,
         base_cpu: 280,
         base_mem: 384,
         base_rps: 150,
         base_rt: 60.0,
         base_err: 0.008,
+        replicas: 2,
     },
     Pod {
         namespace: "inventory",
         service: "inventory-db",
         container: "postgres",
+        log_format: LogFormat::Postgres,
         base_cpu: 420,
         base_mem: 768,
         base_rps: 50,
         base_rt: 12.0,
         base_err: 0.001,
+        replicas: 1,
     },
     Pod {
         namespace: "frontend",
         service: "web-server",
         container: "nginx",
+        log_format: LogFormat::Nginx,
         base_cpu: 120,
         base_mem: 128,
         base_rps: 800,
         base_rt: 8.0,
         base_err: 0.003,
+        replicas: 3,
     },
     Pod {
         namespace: "frontend",
         service: "static-cdn",
         container: "cdn",
+        log_format: LogFormat::Nginx,
         base_cpu: 90,
         base_mem: 96,
         base_rps: 600,
         base_rt: 5.0,
         base_err: 0.001,
+        replicas: 2,
     },
     Pod {
         namespace: "monitoring",
         service: "prometheus",
         container: "prometheus",
+        log_format: LogFormat::Logfmt,
         base_cpu: 460,
         base_mem: 900,
         base_rps: 20,
         base_rt: 25.0,
         base_err: 0.000,
+        replicas: 1,
     },
     Pod {
         namespace: "monitoring",
         service: "grafana",
         container: "grafana",
+        log_format: LogFormat::Logfmt,
         base_cpu: 200,
         base_mem: 320,
         base_rps: 40,
         base_rt: 120.0,
         base_err: 0.002,
+        replicas: 1,
     },
     Pod {
         namespace: "infra",
         service: "nginx-ingress",
         container: "controller",
+        log_format: LogFormat::IngressNginx,
         base_cpu: 310,
         base_mem: 256,
         base_rps: 1200,
         base_rt: 3.0,
         base_err: 0.004,
+        replicas: 2,
     },
     Pod {
         namespace: "infra",
         service: "coredns",
         container: "coredns",
+        log_format: LogFormat::Klog,
         base_cpu: 150,
         base_mem: 192,
         base_rps: 400,
         base_rt: 2.0,
         base_err: 0.000,
+        replicas: 2,
     },
 ];
 
-pub const CLUSTERS: &[&str] = &["prod-us-east-1", "prod-eu-west-1", "staging-us-west-2"];
-pub const NODES: &[&str] = &["node-1", "node-2", "node-3", "node-4", "node-5"];
+pub struct Cluster {
+    pub name: &'static str,
+    pub nodes: &'static [&'static str],
+    /// Namespaces deployed in this cluster; empty = all of them.
+    pub namespaces: &'static [&'static str],
+}
+
+pub const CLUSTERS: &[Cluster] = &[
+    Cluster {
+        name: "prod-us-east-1",
+        nodes: &[
+            "use1-node-1",
+            "use1-node-2",
+            "use1-node-3",
+            "use1-node-4",
+            "use1-node-5",
+        ],
+        namespaces: &[],
+    },
+    Cluster {
+        name: "prod-eu-west-1",
+        nodes: &["euw1-node-1", "euw1-node-2", "euw1-node-3"],
+        namespaces: &["frontend", "infra"],
+    },
+    Cluster {
+        name: "staging-us-west-2",
+        nodes: &["usw2-stg-node-1", "usw2-stg-node-2"],
+        namespaces: &["payments"],
+    },
+];
 
 pub const TRACE_OPS: &[(&str, &[&str])] = &[
     (
@@ -167,3 +245,300 @@ pub const TRACE_OPS: &[(&str, &[&str])] = &[
         &["resolve_internal", "resolve_external", "cache_hit"],
     ),
 ];
+
+/// Services whose root spans fan out to downstream calls in `generate_trace_spans`.
+pub const FAN_OUT_SERVICES: &[&str] = &[
+    "payments-api",
+    "web-server",
+    "inventory-service",
+    "nginx-ingress",
+];
+
+/// One cluster's node pool and the services deployed into it.
+pub struct ClusterTopology {
+    pub name: &'static str,
+    pub nodes: Vec<&'static str>,
+    /// Indices into `Topology::pods`.
+    pub services: Vec<usize>,
+}
+
+impl ClusterTopology {
+    fn new(
+        name: &'static str,
+        nodes: Vec<&'static str>,
+        namespaces: &[&str],
+        pods: &[Pod],
+    ) -> Self {
+        ClusterTopology {
+            name,
+            nodes,
+            services: (0..pods.len())
+                .filter(|&i| namespaces.is_empty() || namespaces.contains(&pods[i].namespace))
+                .collect(),
+        }
+    }
+}
+
+/// Runtime cluster model: the embedded consts above, or a `--topology` file.
+pub struct Topology {
+    /// Service specs; a service runs `replicas` pods in every cluster it is deployed to.
+    pub pods: Vec<Pod>,
+    pub clusters: Vec<ClusterTopology>,
+    pub trace_ops: Vec<(&'static str, &'static [&'static str])>,
+    pub fan_out: Vec<&'static str>,
+}
+
+impl Topology {
+    pub fn builtin() -> Self {
+        Topology {
+            pods: PODS.to_vec(),
+            clusters: CLUSTERS
+                .iter()
+                .map(|c| ClusterTopology::new(c.name, c.nodes.to_vec(), c.namespaces, PODS))
+                .collect(),
+            trace_ops: TRACE_OPS.to_vec(),
+            fan_out: FAN_OUT_SERVICES.to_vec(),
+        }
+    }
+
+    /// Load a topology file; the format is picked from the extension
+    /// (`.yaml`/`.yml`, `.toml`, anything else is parsed as JSON).
+    pub fn from_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("read topology '{}': {}", path, e))?;
+        let spec: TopologySpec = if path.ends_with(".yaml") || path.ends_with(".yml") {
+            serde_yaml::from_str(&text)?
+        } else if path.ends_with(".toml") {
+            toml::from_str(&text)?
+        } else {
+            serde_json::from_str(&text)?
+        };
+        spec.into_topology()
+            .map_err(|e| format!("invalid topology '{}': {}", path, e).into())
+    }
+
+    /// Every (cluster_idx, svc_idx) pair — one Deployment each.
+    pub fn deployments(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.clusters
+            .iter()
+            .enumerate()
+            .flat_map(|(c, cl)| cl.services.iter().map(move |&s| (c, s)))
+    }
+
+    /// Total running pods (sum of replicas across all deployments).
+    pub fn pod_count(&self) -> usize {
+        self.deployments()
+            .map(|(_, s)| self.pods[s].replicas as usize)
+            .sum()
+    }
+
+    pub fn ops_for(&self, service: &str) -> &'static [&'static str] {
+        self.trace_ops
+            .iter()
+            .find(|(svc, _)| *svc == service)
+            .map(|(_, ops)| *ops)
+            .unwrap_or(&["unknown"])
+    }
+
+    pub fn calls_downstream(&self, service: &str) -> bool {
+        self.fan_out.contains(&service)
+    }
+}
+
+static TOPOLOGY: OnceLock<Topology> = OnceLock::new();
+
+/// Install the topology for this run. Must be called before the first `topology()`.
+pub fn init_topology(path: Option<&str>) -> Result<(), Box<dyn std::error::Error>> {
+    let topo = match path {
+        Some(p) => Topology::from_file(p)?,
+        None => Topology::builtin(),
+    };
+    TOPOLOGY
+        .set(topo)
+        .map_err(|_| "topology already initialised".into())
+}
+
+pub fn topology() -> &'static Topology {
+    TOPOLOGY.get_or_init(Topology::builtin)
+}
+
+// ── Topology file schema ─────────────────────────────────────────────────────
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TopologySpec {
+    clusters: Vec<ClusterSpec>,
+    /// Node pool for clusters that do not list their own nodes.
+    #[serde(default)]
+    nodes: Vec<String>,
+    namespaces: Vec<NamespaceSpec>,
+}
+
+/// A bare cluster name (shared node pool, all namespaces) or a full entry.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ClusterSpec {
+    Name(String),
+    Full(ClusterEntry),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ClusterEntry {
+    name: String,
+    #[serde(default)]
+    nodes: Vec<String>,
+    #[serde(default)]
+    namespaces: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NamespaceSpec {
+    name: String,
+    services: Vec<ServiceSpec>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ServiceSpec {
+    name: String,
+    container: String,
+    base_cpu: u32,
+    base_mem: u32,
+    base_rps: u32,
+    base_rt: f64,
+    base_err: f64,
+    #[serde(default)]
+    log_format: LogFormat,
+    #[serde(default = "default_replicas")]
+    replicas: u32,
+    #[serde(default)]
+    operations: Vec<String>,
+    #[serde(default)]
+    calls_downstream: bool,
+}
+
+fn default_replicas() -> u32 {
+    1
+}
+
+/// Smallest `base_rt` (ms). Latency anomalies take spans straight from it
+/// (×10 at least), and a root span needs a few µs to place its child calls in.
+const MIN_BASE_RT_MS: f64 = 0.01;
+
+/// Topology strings live for the whole run, so leaking them keeps `Pod` and the
+/// span types on `&'static str` like the embedded consts.
+fn leak(s: String) -> &'static str {
+    Box::leak(s.into_boxed_str())
+}
+
+impl TopologySpec {
+    fn into_topology(self) -> Result<Topology, String> {
+        if self.clusters.is_empty() {
+            return Err("at least one cluster is required".to_string());
+        }
+
+        let mut topo = Topology {
+            pods: Vec::new(),
+            clusters: Vec::new(),
+            trace_ops: Vec::new(),
+            fan_out: Vec::new(),
+        };
+
+        for ns in self.namespaces {
+            let namespace = leak(ns.name);
+            for svc in ns.services {
+                // Services are looked up by name alone.
+                if topo.pods.iter().any(|p| p.service == svc.name) {
+                    return Err(format!("service '{}' is defined twice", svc.name));
+                }
+                if svc.base_cpu == 0 || svc.base_mem == 0 || svc.base_rps == 0 {
+                    return Err(format!(
+                        "service '{}': base_cpu, base_mem and base_rps must be at least 1",
+                        svc.name
+                    ));
+                }
+                if !(svc.base_rt.is_finite() && svc.base_rt >= MIN_BASE_RT_MS) {
+                    return Err(format!(
+                        "service '{}': base_rt must be at least {} (ms)",
+                        svc.name, MIN_BASE_RT_MS
+                    ));
+                }
+                if !(0.0..=1.0).contains(&svc.base_err) {
+                    return Err(format!(
+                        "service '{}': base_err must be within 0.0..=1.0",
+                        svc.name
+                    ));
+                }
+                if svc.replicas == 0 {
+                    return Err(format!(
+                        "service '{}': replicas must be at least 1",
+                        svc.name
+                    ));
+                }
+                let service = leak(svc.name);
+                if !svc.operations.is_empty() {
+                    let ops: Vec<&'static str> = svc.operations.into_iter().map(leak).collect();
+                    topo.trace_ops
+                        .push((service, Box::leak(ops.into_boxed_slice())));
+                }
+                if svc.calls_downstream {
+                    topo.fan_out.push(service);
+                }
+                topo.pods.push(Pod {
+                    namespace,
+                    service,
+                    container: leak(svc.container),
+                    log_format: svc.log_format,
+                    base_cpu: svc.base_cpu,
+                    base_mem: svc.base_mem,
+                    base_rps: svc.base_rps,
+                    base_rt: svc.base_rt,
+                    base_err: svc.base_err,
+                    replicas: svc.replicas,
+                });
+            }
+        }
+
+        if topo.pods.is_empty() {
+            return Err("at least one service is required".to_string());
+        }
+
+        let shared_nodes: Vec<&'static str> = self.nodes.into_iter().map(leak).collect();
+        for cluster in self.clusters {
+            let (name, nodes, namespaces) = match cluster {
+                ClusterSpec::Name(name) => (name, Vec::new(), Vec::new()),
+                ClusterSpec::Full(c) => (c.name, c.nodes, c.namespaces),
+            };
+            if topo.clusters.iter().any(|c| c.name == name) {
+                return Err(format!("cluster '{}' is defined twice", name));
+            }
+            let nodes: Vec<&'static str> = if nodes.is_empty() {
+                shared_nodes.clone()
+            } else {
+                nodes.into_iter().map(leak).collect()
+            };
+            if nodes.is_empty() {
+                return Err(format!(
+                    "cluster '{}': no nodes (set `nodes` on the cluster or at top level)",
+                    name
+                ));
+            }
+            if let Some(ns) = namespaces
+                .iter()
+                .find(|ns| !topo.pods.iter().any(|p| p.namespace == ns.as_str()))
+            {
+                return Err(format!("cluster '{}': unknown namespace '{}'", name, ns));
+            }
+            let namespaces: Vec<&str> = namespaces.iter().map(String::as_str).collect();
+            topo.clusters.push(ClusterTopology::new(
+                leak(name),
+                nodes,
+                &namespaces,
+                &topo.pods,
+            ));
+        }
+        Ok(topo)
+    }
+}
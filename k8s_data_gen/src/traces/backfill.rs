@@ -0,0 +1,123 @@
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::Client;
+
+use super::generate::generate_trace_spans;
+use super::logs::trace_logs_enabled;
+use super::otlp::traces_to_otlp_payload;
+use crate::client::http::post_batch;
+use crate::config::{
+    api_base, org, stream_logs, stream_traces, INGEST_BATCH_SIZE, TRACES_PER_INTERVAL,
+    TRACE_INTERVAL_SECONDS,
+};
+use crate::lifecycle::pod_at;
+use crate::topology::topology;
+use crate::utils::seeded_rng;
+
+/// Streams historical traces oldest-first from `start_us` as OTLP until the
+/// cursor passes the wall clock, then returns so live mode can take over.
+pub async fn run_backfill_traces(
+    start_us: i64,
+    seed: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let api_url = format!("{}/api/{}/v1/traces", api_base(), org());
+    let logs_url = format!("{}/api/{}/{}/_json", api_base(), org(), stream_logs());
+    let stream = stream_traces();
+    let client = Client::builder()
+        .danger_accept_invalid_certs(true)
+        .build()?;
+    let mut rng = seeded_rng(seed);
+    let interval_us = TRACE_INTERVAL_SECONDS * 1_000_000;
+    // Same as `ingest` for OTLP traces.
+    let batch_size = 200;
+
+    println!(
+        "Backfill traces (OTLP) → {} [stream-name: {}] from {}",
+        api_url,
+        stream,
+        DateTime::from_timestamp_micros(start_us).unwrap_or_default()
+    );
+
+    let mut base_ts_us = start_us;
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut sent = 0usize;
+    // `--trace-logs` lines, posted to the logs stream as `backfill logs` does.
+    let mut log_batch = Vec::new();
+    let mut logs_sent = 0usize;
+    // Only whole intervals, so no span is stamped in the future.
+    while base_ts_us + interval_us <= Utc::now().timestamp_micros() {
+        // Sorted so spans within an interval also go out oldest-first.
+        let mut interval_spans = Vec::new();
+        let mut interval_logs = Vec::new();
+        for (cluster_idx, svc_idx) in topology().deployments() {
+            let replicas = topology().pods[svc_idx].replicas;
+            for _ in 0..TRACES_PER_INTERVAL {
+                let ts_us = base_ts_us + rng.gen_range(0..interval_us);
+                let pod = pod_at(
+                    cluster_idx,
+                    svc_idx,
+                    rng.gen_range(0..replicas),
+                    ts_us,
+                    seed,
+                );
+                interval_spans.extend(generate_trace_spans(
+                    &pod,
+                    ts_us,
+                    None,
+                    seed,
+                    trace_logs_enabled().then_some(&mut interval_logs),
+                    &mut rng,
+                ));
+            }
+        }
+        interval_spans.sort_by_key(|s| s._timestamp);
+        batch.extend(interval_spans);
+        interval_logs.sort_by_key(|r| r._timestamp);
+        log_batch.extend(interval_logs);
+        if log_batch.len() >= INGEST_BATCH_SIZE {
+            post_batch(&client, &logs_url, None, &serde_json::to_value(&log_batch)?).await?;
+            logs_sent += log_batch.len();
+            log_batch.clear();
+        }
+
+        if batch.len() >= batch_size {
+            post_batch(
+                &client,
+                &api_url,
+                Some(&stream),
+                &traces_to_otlp_payload(&batch),
+            )
+            .await?;
+            sent += batch.len();
+            batch.clear();
+            if (sent / batch_size).is_multiple_of(500) {
+                println!(
+                    "[backfill traces] {} spans, up to {}",
+                    sent,
+                    DateTime::from_timestamp_micros(base_ts_us).unwrap_or_default()
+                );
+            }
+        }
+        base_ts_us += interval_us;
+    }
+    if !batch.is_empty() {
+        post_batch(
+            &client,
+            &api_url,
+            Some(&stream),
+            &traces_to_otlp_payload(&batch),
+        )
+        .await?;
+        sent += batch.len();
+    }
+
+    if !log_batch.is_empty() {
+        post_batch(&client, &logs_url, None, &serde_json::to_value(&log_batch)?).await?;
+        logs_sent += log_batch.len();
+    }
+    if logs_sent > 0 {
+        println!("[backfill traces] {} trace-correlated log lines", logs_sent);
+    }
+    println!("[backfill traces] done: {} spans — switching to live", sent);
+    Ok(())
+}
@@ -1,30 +1,34 @@
 use rand::{seq::SliceRandom, Rng};
-use uuid::Uuid;
 
+use super::logs::{trace_record_logs, trace_rng};
 use super::types::K8sTraceRecord;
 use crate::anomaly::{AnomalyState, AnomalyType};
-use crate::topology::{CLUSTERS, PODS, TRACE_OPS};
-use crate::utils::daily_seasonal;
+use crate::lifecycle::{pod_at, PodRef};
+use crate::logs::K8sLogRecord;
+use crate::topology::topology;
+use crate::utils::{daily_seasonal, rng_uuid};
 
-/// Generates a root span + optional child spans for one synthetic request.
+/// Generates a root span + optional child spans for one synthetic request
+/// served by `pod_ref`. Child calls land on a random replica of a callee
+/// deployed in the same cluster. With `logs`, the lines each serving pod
+/// writes for its span are appended to it (see `logs.rs`).
 pub fn generate_trace_spans(
-    pod_idx: usize,
+    pod_ref: &PodRef,
     timestamp_us: i64,
     anomaly: Option<&AnomalyState>,
+    seed: u64,
+    logs: Option<&mut Vec<K8sLogRecord>>,
     rng: &mut impl Rng,
 ) -> Vec<K8sTraceRecord> {
-    let pod = &PODS[pod_idx];
-    let cluster = CLUSTERS[rng.gen_range(0..CLUSTERS.len())];
+    let topo = topology();
+    let pod = &topo.pods[pod_ref.svc_idx];
+    let cluster = pod_ref.cluster;
 
     let is_anomaly = anomaly.map(|a| a.is_active()).unwrap_or(false);
     let anomaly_type = anomaly.map(|a| &a.anomaly_type);
     let season = daily_seasonal(timestamp_us, 0.20);
 
-    let ops = TRACE_OPS
-        .iter()
-        .find(|(svc, _)| *svc == pod.service)
-        .map(|(_, ops)| *ops)
-        .unwrap_or(&["unknown"]);
+    let ops = topo.ops_for(pod.service);
     let operation = ops[rng.gen_range(0..ops.len())];
 
     let base_duration = if is_anomaly && anomaly_type == Some(&AnomalyType::Latency) {
@@ -46,8 +50,8 @@ pub fn generate_trace_spans(
         *[200u16, 200, 200, 201, 204][..].choose(rng).unwrap()
     };
 
-    let trace_id = Uuid::new_v4().simple().to_string();
-    let root_span_id = Uuid::new_v4().simple().to_string()[..16].to_string();
+    let trace_id = rng_uuid(rng).simple().to_string();
+    let root_span_id = rng_uuid(rng).simple().to_string()[..16].to_string();
     let duration_us = (base_duration * 1000.0) as i64;
 
     let mut spans = vec![K8sTraceRecord {
@@ -57,6 +61,7 @@ pub fn generate_trace_spans(
         parent_span_id: String::new(),
         service_name: pod.service.to_string(),
         namespace: pod.namespace.to_string(),
+        pod: pod_ref.name.clone(),
         cluster: cluster.to_string(),
         operation_name: operation.to_string(),
         duration_us,
@@ -65,17 +70,23 @@ pub fn generate_trace_spans(
         http_status_code: http_status,
         is_root: true,
     }];
+    let mut served_by = vec![pod_ref.clone()];
 
-    let has_children = matches!(
-        pod.service,
-        "payments-api" | "web-server" | "inventory-service" | "nginx-ingress"
-    );
-    if has_children && rng.gen_bool(0.7) {
+    if topo.calls_downstream(pod.service) && rng.gen_bool(0.7) {
         for _ in 0..rng.gen_range(1..=2_usize) {
             let child_dur = base_duration * rng.gen_range(0.3..0.8);
             let child_offset_us = rng.gen_range(0..duration_us / 2);
-            let child_span_id = Uuid::new_v4().simple().to_string()[..16].to_string();
-            let downstream = &PODS[rng.gen_range(0..PODS.len())];
+            let child_span_id = rng_uuid(rng).simple().to_string()[..16].to_string();
+            let local = &topo.clusters[pod_ref.cluster_idx].services;
+            let ds_idx = local[rng.gen_range(0..local.len())];
+            let downstream = &topo.pods[ds_idx];
+            let ds_pod = pod_at(
+                pod_ref.cluster_idx,
+                ds_idx,
+                rng.gen_range(0..downstream.replicas),
+                timestamp_us,
+                seed,
+            );
 
             spans.push(K8sTraceRecord {
                 _timestamp: timestamp_us + child_offset_us,
@@ -84,6 +95,7 @@ pub fn generate_trace_spans(
                 parent_span_id: root_span_id.clone(),
                 service_name: downstream.service.to_string(),
                 namespace: downstream.namespace.to_string(),
+                pod: ds_pod.name.clone(),
                 cluster: cluster.to_string(),
                 operation_name: format!("call_{}", downstream.service.replace('-', "_")),
                 duration_us: (child_dur * 1000.0) as i64,
@@ -101,8 +113,15 @@ pub fn generate_trace_spans(
                 },
                 is_root: false,
             });
+            served_by.push(ds_pod);
         }
     }
 
+    if let Some(logs) = logs {
+        let mut log_rng = trace_rng(&trace_id);
+        for (span, pod) in spans.iter().zip(&served_by) {
+            logs.extend(trace_record_logs(span, pod, anomaly, &mut log_rng));
+        }
+    }
     spans
 }
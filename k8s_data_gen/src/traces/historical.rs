@@ -1,63 +1,95 @@
-use std::{
-    fs::File,
-    io::{BufWriter, Write},
-};
-
-use chrono::Utc;
+use rand::rngs::StdRng;
 use rand::Rng;
 
 use super::generate::generate_trace_spans;
-use crate::config::CHUNK_SIZE;
-use crate::topology::PODS;
+use super::logs::trace_logs_enabled;
+use super::types::K8sTraceRecord;
+use crate::config::{
+    HISTORICAL_OUT_TRACES, INGEST_STREAM_TRACES, TRACES_PER_INTERVAL, TRACE_INTERVAL_SECONDS,
+};
+use crate::historical::{companion_path, print_done, write_sharded, HistoricalOpts};
+use crate::lifecycle::pod_at;
+use crate::logs::K8sLogRecord;
+use crate::topology::topology;
 
-pub fn run_historical_traces(days: u32) -> Result<(), Box<dyn std::error::Error>> {
-    let output_path = "../output_k8s_traces.json";
-    let num_pods = PODS.len();
-    let trace_interval_secs: i64 = 30;
-    let traces_per_interval = 5usize;
-    let total_intervals = (days as i64 * 86_400) / trace_interval_secs;
-    let total_spans_approx = total_intervals as usize * num_pods * traces_per_interval * 2;
+/// One interval's traces; with `logs`, their correlated log lines too.
+fn interval_traces(
+    base_ts_us: i64,
+    interval_secs: i64,
+    seed: u64,
+    mut logs: Option<&mut Vec<K8sLogRecord>>,
+    rng: &mut StdRng,
+) -> Vec<K8sTraceRecord> {
+    let mut spans = Vec::new();
+    for (cluster_idx, svc_idx) in topology().deployments() {
+        let replicas = topology().pods[svc_idx].replicas;
+        for _ in 0..TRACES_PER_INTERVAL {
+            let jitter_us = rng.gen_range(0..(interval_secs * 1_000_000));
+            let ts_us = base_ts_us - jitter_us;
+            let pod = pod_at(
+                cluster_idx,
+                svc_idx,
+                rng.gen_range(0..replicas),
+                ts_us,
+                seed,
+            );
+            spans.extend(generate_trace_spans(
+                &pod,
+                ts_us,
+                None,
+                seed,
+                logs.as_deref_mut(),
+                rng,
+            ));
+        }
+    }
+    spans
+}
 
-    println!("Historical traces: {} days → {}", days, output_path);
-    println!("Approx spans: ~{}", total_spans_approx);
+pub fn run_historical_traces(
+    opts: &HistoricalOpts,
+    seed: u64,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_path = opts.out_or(HISTORICAL_OUT_TRACES);
+    let num_deployments = topology().deployments().count();
+    let trace_interval_secs = opts.interval_secs_or(TRACE_INTERVAL_SECONDS);
+    let total_intervals = opts.intervals(trace_interval_secs);
+    let total_spans_approx = total_intervals as usize * num_deployments * TRACES_PER_INTERVAL * 2;
 
-    let file = File::create(output_path)?;
-    let mut writer = BufWriter::new(file);
-    let mut rng = rand::thread_rng();
-    let now_us = Utc::now().timestamp_micros();
+    println!(
+        "Historical traces: {} every {}s → {}",
+        opts.describe(),
+        trace_interval_secs,
+        output_path
+    );
+    println!("Approx spans: ~{}", total_spans_approx);
 
-    writer.write_all(b"[")?;
-    let mut first = true;
-    let mut written = 0usize;
+    let (written, files) = write_sharded(
+        opts,
+        &output_path,
+        trace_interval_secs,
+        seed,
+        |base_ts_us, rng| interval_traces(base_ts_us, trace_interval_secs, seed, None, rng),
+    )?;
+    print_done(written, "spans", &files, Some(INGEST_STREAM_TRACES));
 
-    for interval_idx in 0..total_intervals as usize {
-        let base_ts_us = now_us - (interval_idx as i64 * trace_interval_secs * 1_000_000);
-        for pod_idx in 0..num_pods {
-            for _ in 0..traces_per_interval {
-                let jitter_us = rng.gen_range(0..(trace_interval_secs * 1_000_000));
-                let ts_us = base_ts_us - jitter_us;
-                let spans = generate_trace_spans(pod_idx, ts_us, None, &mut rng);
-                for span in spans {
-                    let json = serde_json::to_string(&span)?;
-                    if !first {
-                        writer.write_all(b",")?;
-                    }
-                    writer.write_all(json.as_bytes())?;
-                    first = false;
-                    written += 1;
-                }
-            }
-        }
-        if written % CHUNK_SIZE == 0 {
-            writer.flush()?;
-            let pct = interval_idx as f64 / total_intervals as f64 * 100.0;
-            println!("Progress: {:.1}% ({} spans written)", pct, written);
-        }
+    if trace_logs_enabled() {
+        // A second pass over the same interval RNGs regenerates the same traces;
+        // this time only their log lines are kept.
+        let logs_path = companion_path(&output_path, "logs");
+        println!("\nTrace-correlated logs → {}", logs_path);
+        let (written, files) = write_sharded(
+            opts,
+            &logs_path,
+            trace_interval_secs,
+            seed,
+            |base_ts_us, rng| {
+                let mut logs = Vec::new();
+                interval_traces(base_ts_us, trace_interval_secs, seed, Some(&mut logs), rng);
+                logs
+            },
+        )?;
+        print_done(written, "log lines", &files, None);
     }
-
-    writer.write_all(b"]")?;
-    writer.flush()?;
-    println!("\nDone! {} spans → '{}'", written, output_path);
-    println!("Ingest: cargo run -- ingest ../output_k8s_traces.json --stream k8s_traces");
     Ok(())
 }
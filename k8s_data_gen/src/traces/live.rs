@@ -1,32 +1,47 @@
 use std::time::Duration;
 
 use chrono::Utc;
+use rand::rngs::StdRng;
+use rand::{seq::SliceRandom, Rng};
 use reqwest::Client;
 
-use super::flows::generate_prod_trace;
-use super::generate::generate_trace_spans;
-use super::otlp::{prod_spans_to_resource_spans, traces_to_otlp_payload};
+use super::logs::{prod_trace_logs, trace_logs_enabled};
+use super::{
+    generate_prod_trace, generate_trace_spans, prod_spans_to_resource_spans, traces_to_otlp_payload,
+};
 use crate::anomaly::{AnomalyState, AnomalyType};
-use crate::client::grpc::{grpc_client, send_grpc_traces};
-use crate::client::http::post_otlp;
-use crate::config::{api_base, grpc_endpoint, DEFAULT_ORG, DEFAULT_STREAM_TRACES, PODS_PER_TICK};
-use crate::utils::print_anomaly_header;
+use crate::client::grpc::{
+    grpc_client, grpc_logs_client, report_export, send_grpc_logs, send_grpc_traces,
+};
+use crate::client::http::{post_live, post_otlp};
+use crate::config::{api_base, grpc_endpoint, org, stream_logs, stream_traces};
+use crate::lifecycle::pod_at;
+use crate::logs::{logs_to_resource_logs, K8sLogRecord};
+use crate::rate::{print_rate_header, spread_us, RateSchedule, TokenBucket};
+use crate::topology::topology;
+use crate::utils::{print_anomaly_header, seeded_rng};
 
 pub async fn run_live_traces(
     anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let api_url = format!("{}/api/{}/v1/traces", api_base(), DEFAULT_ORG);
+    let api_url = format!("{}/api/{}/v1/traces", api_base(), org());
+    let logs_url = format!("{}/api/{}/{}/_json", api_base(), org(), stream_logs());
+    let stream = stream_traces();
     let client = Client::builder()
         .danger_accept_invalid_certs(true)
         .build()?;
-    let mut rng = rand::thread_rng();
+    let mut rng = seeded_rng(seed);
     let mut interval = tokio::time::interval(Duration::from_secs(1));
     let mut anomaly_state = anomaly_type.map(AnomalyState::new);
+    let mut bucket = rate.map(TokenBucket::new);
 
-    println!(
-        "Live traces (OTLP) → {} [stream-name: {}]",
-        api_url, DEFAULT_STREAM_TRACES
-    );
+    println!("Live traces (OTLP) → {} [stream-name: {}]", api_url, stream);
+    if trace_logs_enabled() {
+        println!("Trace-correlated logs → {}", logs_url);
+    }
+    print_rate_header(&bucket, "3 traces per deployment per second");
     print_anomaly_header(&anomaly_state);
     println!("Press Ctrl+C to stop.\n");
 
@@ -37,47 +52,111 @@ pub async fn run_live_traces(
         }
 
         let now_us = Utc::now().timestamp_micros();
+        let deployments: Vec<_> = topology().deployments().collect();
+        let mut logs = Vec::new();
+        let trace_for = |(cluster_idx, svc_idx): (usize, usize),
+                         ts_us: i64,
+                         logs: &mut Vec<K8sLogRecord>,
+                         rng: &mut StdRng| {
+            let replicas = topology().pods[svc_idx].replicas;
+            let pod = pod_at(
+                cluster_idx,
+                svc_idx,
+                rng.gen_range(0..replicas),
+                ts_us,
+                seed,
+            );
+            generate_trace_spans(
+                &pod,
+                ts_us,
+                anomaly_state.as_ref(),
+                seed,
+                trace_logs_enabled().then_some(logs),
+                rng,
+            )
+        };
         let mut spans = Vec::new();
-        for pod_idx in 0..PODS_PER_TICK {
-            for _ in 0..3 {
-                spans.extend(generate_trace_spans(
-                    pod_idx,
-                    now_us,
-                    anomaly_state.as_ref(),
-                    &mut rng,
-                ));
+        match bucket.as_mut() {
+            None => {
+                for &deployment in &deployments {
+                    for _ in 0..3 {
+                        spans.extend(trace_for(deployment, now_us, &mut logs, &mut rng));
+                    }
+                }
+            }
+            Some(b) => {
+                // Budget is in spans; the last trace may overshoot and is
+                // paid back next tick. Deployments are drawn in shuffled
+                // rounds, and each trace starts at its own point in the tick.
+                let budget = b.refill();
+                let mut round = Vec::new();
+                let mut traces = 0;
+                while spans.len() < budget {
+                    if round.is_empty() {
+                        round = deployments.clone();
+                        round.shuffle(&mut rng);
+                    }
+                    let Some(deployment) = round.pop() else {
+                        break;
+                    };
+                    let ts_us = spread_us(now_us, traces, budget);
+                    spans.extend(trace_for(deployment, ts_us, &mut logs, &mut rng));
+                    traces += 1;
+                }
+                b.consume(spans.len());
             }
         }
+        if spans.is_empty() {
+            continue;
+        }
 
         let payload = traces_to_otlp_payload(&spans);
         post_otlp(
             &client,
             &api_url,
-            Some(DEFAULT_STREAM_TRACES),
+            Some(&stream),
             &payload,
             spans.len(),
             &anomaly_state,
         )
         .await;
+        if !logs.is_empty() {
+            post_live(&client, &logs_url, &logs, &anomaly_state).await;
+        }
     }
 }
 
 pub async fn run_live_traces_grpc(
     anomaly_type: Option<AnomalyType>,
+    seed: u64,
+    rate: Option<RateSchedule>,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let mut client = grpc_client(&grpc_endpoint()).await?;
-    let mut rng = rand::thread_rng();
+    let mut logs_client = if trace_logs_enabled() {
+        Some(grpc_logs_client(&grpc_endpoint()).await?)
+    } else {
+        None
+    };
+    let org = org();
+    let stream = stream_traces();
+    let logs_stream = stream_logs();
+    let mut rng = seeded_rng(seed);
     let mut interval = tokio::time::interval(Duration::from_secs(1));
     let mut anomaly_state = anomaly_type.map(AnomalyState::new);
+    let mut bucket = rate.map(TokenBucket::new);
 
     println!(
         "Live traces (gRPC OTLP) → {} [org: {}, stream: {}]",
-        &grpc_endpoint(), DEFAULT_ORG, DEFAULT_STREAM_TRACES
+        &grpc_endpoint(), org, stream
     );
     println!("Services: api-gateway, auth-service, cart-service, inventory-service,");
     println!("          payment-service, order-service, product-catalog, search-service,");
     println!("          notification-service, user-service, redis-cache, postgres-primary, postgres-replica");
     println!("Flows: checkout(35%) | product-search(30%) | login(15%) | browse(20%)");
+    if trace_logs_enabled() {
+        println!("Trace-correlated logs → stream: {}", logs_stream);
+    }
+    print_rate_header(&bucket, "10 traces per second");
     print_anomaly_header(&anomaly_state);
     println!("Press Ctrl+C to stop.\n");
 
@@ -89,54 +168,61 @@ pub async fn run_live_traces_grpc(
 
         let now_us = Utc::now().timestamp_micros() as u64;
         let mut all_spans = Vec::new();
-        for _ in 0..10 {
-            all_spans.extend(generate_prod_trace(
-                now_us,
-                anomaly_state.as_ref(),
-                &mut rng,
-            ));
+        match bucket.as_mut() {
+            None => {
+                for _ in 0..10 {
+                    all_spans.extend(generate_prod_trace(
+                        now_us,
+                        anomaly_state.as_ref(),
+                        &mut rng,
+                    ));
+                }
+            }
+            Some(b) => {
+                // Each trace starts at its own point in the tick, as in
+                // `run_live_traces`.
+                let budget = b.refill();
+                let mut traces = 0;
+                while all_spans.len() < budget {
+                    let ts_us = spread_us(now_us as i64, traces, budget) as u64;
+                    all_spans.extend(generate_prod_trace(
+                        ts_us,
+                        anomaly_state.as_ref(),
+                        &mut rng,
+                    ));
+                    traces += 1;
+                }
+                b.consume(all_spans.len());
+            }
+        }
+        if all_spans.is_empty() {
+            continue;
         }
 
         let span_count = all_spans.len();
+        let logs = match logs_client {
+            Some(_) => prod_trace_logs(&all_spans),
+            None => Vec::new(),
+        };
         let resource_spans = prod_spans_to_resource_spans(all_spans);
 
-        match send_grpc_traces(
-            &mut client,
-            resource_spans,
-            DEFAULT_ORG,
-            DEFAULT_STREAM_TRACES,
-        )
-        .await
-        {
-            Ok(_) => {
-                let anomaly_active = anomaly_state
-                    .as_ref()
-                    .map(|a| a.is_active())
-                    .unwrap_or(false);
-                let anomaly_remaining = anomaly_state
-                    .as_ref()
-                    .map(|a| a.remaining_secs)
-                    .unwrap_or(0);
-                let suffix = if anomaly_active {
-                    format!(" [ANOMALY ACTIVE: {}s remaining]", anomaly_remaining)
-                } else {
-                    String::new()
-                };
-                println!(
-                    "[{}] ✓ {} spans (gRPC){}",
-                    Utc::now().format("%Y-%m-%d %H:%M:%S"),
-                    span_count,
-                    suffix
-                );
+        let result = send_grpc_traces(&mut client, resource_spans, &org, &stream).await;
+        if !report_export(result, span_count, "spans", &anomaly_state) {
+            if let Ok(new_client) = grpc_client(&grpc_endpoint()).await {
+                client = new_client;
             }
-            Err(e) => {
-                eprintln!(
-                    "[{}] ✗ gRPC error: {}",
-                    Utc::now().format("%Y-%m-%d %H:%M:%S"),
-                    e
-                );
-                if let Ok(new_client) = grpc_client(&grpc_endpoint()).await {
-                    client = new_client;
+        }
+        if let Some(logs_client) = logs_client.as_mut() {
+            let result = send_grpc_logs(
+                logs_client,
+                logs_to_resource_logs(&logs),
+                &org,
+                &logs_stream,
+            )
+            .await;
+            if !report_export(result, logs.len(), "log lines", &anomaly_state) {
+                if let Ok(new_client) = grpc_logs_client(&grpc_endpoint()).await {
+                    *logs_client = new_client;
                 }
             }
         }
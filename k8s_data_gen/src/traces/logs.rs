@@ -0,0 +1,248 @@
+//! Trace-correlated logs (`--trace-logs`).
+//!
+//! Log lines written by the services while serving a span, carrying the span's
+//! `trace_id` and `span_id` so OpenObserve can jump from a trace to its logs
+//! and back. Every span gets a start and a finish line; error spans add an
+//! `exception` line with OpenTelemetry `exception.*` fields; database spans
+//! slower than `SLOW_QUERY_MS` add a slow-query warning.
+//!
+//! The lines of one trace are drawn from an RNG seeded by its trace ID, so
+//! turning them on leaves the traces themselves unchanged under `--seed`.
+
+use std::sync::OnceLock;
+
+use rand::rngs::StdRng;
+use rand::Rng;
+
+use super::{K8sTraceRecord, ProdSpan};
+use crate::anomaly::AnomalyState;
+use crate::config::SLOW_QUERY_MS;
+use crate::lifecycle::PodRef;
+use crate::logs::generate_log_record;
+use crate::logs::types::{K8sLogRecord, EXCEPTIONS};
+use crate::utils::{rng_uuid, seeded_rng, str_hash};
+
+static TRACE_LOGS: OnceLock<bool> = OnceLock::new();
+
+/// Turn trace-correlated logs on or off for this run (`--trace-logs`).
+pub fn init_trace_logs(enabled: bool) -> Result<(), Box<dyn std::error::Error>> {
+    TRACE_LOGS
+        .set(enabled)
+        .map_err(|_| "trace logs already initialised".into())
+}
+
+pub fn trace_logs_enabled() -> bool {
+    *TRACE_LOGS.get_or_init(|| false)
+}
+
+/// RNG for the log lines of the trace with hex ID `trace_id`.
+pub fn trace_rng(trace_id: &str) -> StdRng {
+    seeded_rng(str_hash(trace_id))
+}
+
+/// What the log lines of one span are built from.
+struct SpanInfo<'a> {
+    trace_id: String,
+    span_id: String,
+    service: &'a str,
+    operation: &'a str,
+    start_us: i64,
+    duration_us: i64,
+    error: bool,
+    http_status: u16,
+    /// Set for database spans.
+    db_statement: Option<&'a str>,
+}
+
+/// `POST /checkout` → `post_checkout`.
+fn ident(s: &str) -> String {
+    let mut out = String::new();
+    for c in s.chars() {
+        if c.is_ascii_alphanumeric() {
+            out.push(c.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('_') {
+            out.push('_');
+        }
+    }
+    out.trim_end_matches('_').to_string()
+}
+
+/// The span's lines, each a copy of `base` (the pod's fields) with its own
+/// time, level, event type and message.
+fn span_logs(span: &SpanInfo, base: &K8sLogRecord, rng: &mut StdRng) -> Vec<K8sLogRecord> {
+    let duration_ms = span.duration_us as f64 / 1000.0;
+    let end_us = span.start_us + span.duration_us;
+    let status = if span.error { "ERROR" } else { "OK" };
+    let line = |rng: &mut StdRng,
+                ts_us: i64,
+                level: &str,
+                event_type: &str,
+                template_id: &str,
+                message: String| {
+        K8sLogRecord {
+            _timestamp: ts_us,
+            log_level: level.to_string(),
+            event_type: event_type.to_string(),
+            message,
+            // The base record's raw line is about another request.
+            log: String::new(),
+            template_id: template_id.to_string(),
+            status_code: span.http_status,
+            response_time_ms: (duration_ms * 10.0).round() / 10.0,
+            unique_id: rng_uuid(rng).to_string(),
+            trace_id: span.trace_id.clone(),
+            span_id: span.span_id.clone(),
+            ..base.clone()
+        }
+    };
+
+    let mut out = vec![line(
+        rng,
+        span.start_us,
+        "INFO",
+        "span_start",
+        "span-start",
+        format!("Started {}", span.operation),
+    )];
+    if let Some(statement) = span.db_statement.filter(|_| duration_ms > SLOW_QUERY_MS) {
+        out.push(line(
+            rng,
+            end_us,
+            "WARN",
+            "slow_query",
+            "slow-query",
+            format!("Slow query took {:.1} ms: {}", duration_ms, statement),
+        ));
+    }
+    if span.error {
+        let idx = rng.gen_range(0..EXCEPTIONS.len());
+        let (ty, msg) = EXCEPTIONS[idx];
+        let ts_us = span.start_us + (span.duration_us as f64 * rng.gen_range(0.5..0.95)) as i64;
+        let module = ident(span.service);
+        let template_id = format!("exception-{:02}", idx + 1);
+        let mut r = line(
+            rng,
+            ts_us,
+            "ERROR",
+            "exception",
+            &template_id,
+            format!("{}: {}", ty, msg),
+        );
+        r.exception_type = ty.to_string();
+        r.exception_message = msg.to_string();
+        r.exception_stacktrace = format!(
+            "{}: {}\n    at {}::handlers::{} (src/handlers.rs:{})\n    at {}::server::dispatch (src/server.rs:{})",
+            ty,
+            msg,
+            module,
+            ident(span.operation),
+            rng.gen_range(40..400),
+            module,
+            rng.gen_range(80..160)
+        );
+        out.push(r);
+    }
+    out.push(line(
+        rng,
+        end_us,
+        if span.error { "ERROR" } else { "INFO" },
+        "span_end",
+        "span-end",
+        format!(
+            "Finished {} in {:.1} ms (status {})",
+            span.operation, duration_ms, status
+        ),
+    ));
+    out
+}
+
+/// Lines for a span of the K8s trace model, served by `pod`. The pod fields
+/// come from a regular log record of that pod at the span's start.
+pub fn trace_record_logs(
+    span: &K8sTraceRecord,
+    pod: &PodRef,
+    anomaly: Option<&AnomalyState>,
+    rng: &mut StdRng,
+) -> Vec<K8sLogRecord> {
+    let base = generate_log_record(pod, span._timestamp, anomaly, 0.0, rng);
+    // Spans of a database service are named after their statement.
+    let is_query = ["SELECT", "INSERT", "UPDATE", "DELETE"]
+        .iter()
+        .any(|verb| span.operation_name.starts_with(verb));
+    let info = SpanInfo {
+        trace_id: span.trace_id.clone(),
+        span_id: span.span_id.clone(),
+        service: &span.service_name,
+        operation: &span.operation_name,
+        start_us: span._timestamp,
+        duration_us: span.duration_us,
+        error: span.status == "ERROR",
+        http_status: span.http_status_code,
+        db_statement: is_query.then_some(span.operation_name.as_str()),
+    };
+    span_logs(&info, &base, rng)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Lines for prod flow spans (`generate_prod_trace`), one trace after
+/// another. Those services are outside the topology, so the lines carry no
+/// pod, node or cluster, and no resource usage.
+pub fn prod_trace_logs(spans: &[ProdSpan]) -> Vec<K8sLogRecord> {
+    let mut out = Vec::new();
+    for trace in spans.chunk_by(|a, b| a.trace_id == b.trace_id) {
+        let trace_id = hex(&trace[0].trace_id);
+        let mut rng = trace_rng(&trace_id);
+        for s in trace {
+            out.extend(prod_span_logs(s, &trace_id, &mut rng));
+        }
+    }
+    out
+}
+
+fn prod_span_logs(s: &ProdSpan, trace_id: &str, rng: &mut StdRng) -> Vec<K8sLogRecord> {
+    let base = K8sLogRecord {
+        _timestamp: 0,
+        cluster: String::new(),
+        namespace: s.namespace.to_string(),
+        pod: String::new(),
+        container: s.service_name.to_string(),
+        node: String::new(),
+        service: s.service_name.to_string(),
+        cpu_millicores: 0,
+        memory_mb: 0,
+        network_rx_bytes: 0,
+        network_tx_bytes: 0,
+        restarts: 0,
+        response_time_ms: 0.0,
+        error_rate: 0.0,
+        requests_per_second: 0,
+        log_level: String::new(),
+        event_type: String::new(),
+        status_code: 0,
+        message: String::new(),
+        log: String::new(),
+        template_id: String::new(),
+        unique_id: String::new(),
+        trace_id: String::new(),
+        span_id: String::new(),
+        exception_type: String::new(),
+        exception_message: String::new(),
+        exception_stacktrace: String::new(),
+        labels: Default::default(),
+    };
+    let info = SpanInfo {
+        trace_id: trace_id.to_string(),
+        span_id: hex(&s.span_id),
+        service: s.service_name,
+        operation: s.operation,
+        start_us: (s.start_ns / 1000) as i64,
+        duration_us: ((s.end_ns - s.start_ns) / 1000) as i64,
+        error: s.status_code == 2,
+        http_status: s.http_status as u16,
+        db_statement: s.db_statement,
+    };
+    span_logs(&info, &base, rng)
+}
@@ -1,13 +1,19 @@
+pub mod backfill;
 pub mod flows;
 pub mod generate;
 pub mod historical;
 pub mod live;
+pub mod logs;
 pub mod otlp;
 pub mod types;
 
+pub use backfill::run_backfill_traces;
 pub use flows::generate_prod_trace;
 pub use generate::generate_trace_spans;
 pub use historical::run_historical_traces;
 pub use live::{run_live_traces, run_live_traces_grpc};
-pub use otlp::{prod_spans_to_resource_spans, traces_to_otlp_payload};
+pub use logs::init_trace_logs;
+pub use otlp::{
+    prod_spans_to_resource_spans, trace_records_to_resource_spans, traces_to_otlp_payload,
+};
 pub use types::{K8sTraceRecord, ProdSpan};
@@ -26,6 +26,34 @@ pub fn kv_int(k: &str, v: i64) -> KeyValue {
     }
 }
 
+pub fn kv_double(k: &str, v: f64) -> KeyValue {
+    KeyValue {
+        key: k.to_string(),
+        value: Some(AnyValue {
+            value: Some(Value::DoubleValue(v)),
+        }),
+    }
+}
+
+/// OTLP/JSON form of an `AnyValue` (scalars only).
+pub fn any_value_json(v: Option<&AnyValue>) -> serde_json::Value {
+    match v.and_then(|v| v.value.as_ref()) {
+        Some(Value::StringValue(s)) => serde_json::json!({"stringValue": s}),
+        Some(Value::IntValue(i)) => serde_json::json!({"intValue": i}),
+        Some(Value::DoubleValue(d)) => serde_json::json!({"doubleValue": d}),
+        Some(Value::BoolValue(b)) => serde_json::json!({"boolValue": b}),
+        _ => serde_json::json!({}),
+    }
+}
+
+/// OTLP/JSON form of an attribute list.
+pub fn attributes_json(attrs: &[KeyValue]) -> serde_json::Value {
+    attrs
+        .iter()
+        .map(|kv| serde_json::json!({"key": kv.key, "value": any_value_json(kv.value.as_ref())}))
+        .collect()
+}
+
 pub fn trace_record_to_resource_spans(s: &K8sTraceRecord) -> serde_json::Value {
     let start = (s._timestamp * 1000).to_string();
     let end = ((s._timestamp + s.duration_us) * 1000).to_string();
@@ -36,6 +64,7 @@ pub fn trace_record_to_resource_spans(s: &K8sTraceRecord) -> serde_json::Value {
             "attributes": [
                 {"key": "service.name", "value": {"stringValue": s.service_name.as_str()}},
                 {"key": "namespace",    "value": {"stringValue": s.namespace.as_str()}},
+                {"key": "k8s.pod.name", "value": {"stringValue": s.pod.as_str()}},
                 {"key": "cluster",      "value": {"stringValue": s.cluster.as_str()}},
             ]
         },
@@ -66,6 +95,64 @@ pub fn traces_to_otlp_payload(spans: &[K8sTraceRecord]) -> serde_json::Value {
     })
 }
 
+/// Hex span/trace ID → bytes; malformed input yields an empty (unset) ID.
+pub fn hex_id(s: &str) -> Vec<u8> {
+    if !s.len().is_multiple_of(2) {
+        return Vec::new();
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+        .collect::<Result<_, _>>()
+        .unwrap_or_default()
+}
+
+/// Protobuf counterpart of [`traces_to_otlp_payload`], for gRPC ingest of
+/// historical files.
+pub fn trace_records_to_resource_spans(spans: &[K8sTraceRecord]) -> Vec<ResourceSpans> {
+    spans
+        .iter()
+        .map(|s| ResourceSpans {
+            resource: Some(Resource {
+                attributes: vec![
+                    kv_str("service.name", &s.service_name),
+                    kv_str("namespace", &s.namespace),
+                    kv_str("k8s.pod.name", &s.pod),
+                    kv_str("cluster", &s.cluster),
+                ],
+                dropped_attributes_count: 0,
+            }),
+            scope_spans: vec![ScopeSpans {
+                scope: Some(InstrumentationScope {
+                    name: "k8s-data-gen".to_string(),
+                    ..Default::default()
+                }),
+                spans: vec![Span {
+                    trace_id: hex_id(&s.trace_id),
+                    span_id: hex_id(&s.span_id),
+                    parent_span_id: hex_id(&s.parent_span_id),
+                    name: s.operation_name.clone(),
+                    kind: if s.is_root { 2 } else { 3 },
+                    start_time_unix_nano: (s._timestamp * 1000) as u64,
+                    end_time_unix_nano: ((s._timestamp + s.duration_us) * 1000) as u64,
+                    attributes: vec![
+                        kv_int("http.status_code", s.http_status_code as i64),
+                        kv_str("duration_ms", &s.duration_ms.to_string()),
+                        kv_str("status", &s.status),
+                    ],
+                    status: Some(Status {
+                        code: if s.status == "ERROR" { 2 } else { 1 },
+                        message: String::new(),
+                    }),
+                    ..Default::default()
+                }],
+                schema_url: String::new(),
+            }],
+            schema_url: String::new(),
+        })
+        .collect()
+}
+
 /// Convert ProdSpan vec to protobuf ResourceSpans grouped by service.
 pub fn prod_spans_to_resource_spans(spans: Vec<ProdSpan>) -> Vec<ResourceSpans> {
     let mut by_svc: HashMap<String, Vec<Span>> = HashMap::new();
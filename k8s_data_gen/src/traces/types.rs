@@ -9,6 +9,9 @@ pub struct K8sTraceRecord {
     pub parent_span_id: String,
     pub service_name: String,
     pub namespace: String,
+    /// Replica that served the span; absent in files written before replicas existed.
+    #[serde(default)]
+    pub pod: String,
     pub cluster: String,
     pub operation_name: String,
     pub duration_us: i64,
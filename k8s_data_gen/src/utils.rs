@@ -1,7 +1,7 @@
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use uuid::Uuid;
 
 use crate::anomaly::AnomalyState;
-use crate::topology::PODS;
 
 pub fn weighted_choice<'a>(choices: &[(&'a str, u32)], rng: &mut impl Rng) -> &'a str {
     let total: u32 = choices.iter().map(|(_, w)| w).sum();
@@ -23,8 +23,74 @@ pub fn daily_seasonal(timestamp_us: i64, amplitude: f64) -> f64 {
     1.0 + amplitude * (2.0 * PI * (hour_f - 6.0) / 24.0).sin()
 }
 
-pub fn pod_name(pod_idx: usize) -> String {
-    format!("{}-{:06x}", PODS[pod_idx].service, pod_idx * 0x1a3f + 0x4b7)
+/// Integral of `daily_seasonal` over `[t0_secs, t1_secs]`, in seconds — the
+/// "seasonally weighted" time elapsed between two instants.
+pub fn daily_seasonal_integral(t0_secs: i64, t1_secs: i64, amplitude: f64) -> f64 {
+    use std::f64::consts::PI;
+    // daily_seasonal(t) = 1 - A·cos(2πt/day), so its antiderivative is closed-form.
+    let day = 86_400.0;
+    let f = |t: i64| t as f64 - amplitude * day / (2.0 * PI) * (2.0 * PI * t as f64 / day).sin();
+    f(t1_secs) - f(t0_secs)
+}
+
+/// Fresh seed for runs started without `--seed`; printed so the run can be replayed.
+pub fn random_seed() -> u64 {
+    rand::thread_rng().gen()
+}
+
+pub fn seeded_rng(seed: u64) -> StdRng {
+    StdRng::seed_from_u64(seed)
+}
+
+/// UUIDv4 drawn from `rng` instead of the OS so IDs are reproducible under `--seed`.
+pub fn rng_uuid(rng: &mut impl Rng) -> Uuid {
+    uuid::Builder::from_random_bytes(rng.gen()).into_uuid()
+}
+
+/// SplitMix64 finaliser — cheap, well-mixed 64-bit hash for deterministic draws.
+pub fn mix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// FNV-1a hash of a name, for deterministic per-name draws.
+pub fn str_hash(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |acc, b| {
+        (acc ^ b as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// 64-hex container ID of a pod's container; `generation` tells the pod's
+/// successive containers apart.
+pub fn container_id(cluster: &str, pod: &str, generation: u64) -> String {
+    let h = mix64(mix64(str_hash(cluster) ^ str_hash(pod)) ^ generation);
+    format!(
+        "{:016x}{:016x}{:016x}{:016x}",
+        h,
+        mix64(h),
+        mix64(h ^ 1),
+        mix64(h ^ 2)
+    )
+}
+
+/// Parses `90`, `30s`, `5m`, `2h` or `7d` into seconds.
+pub fn parse_duration_secs(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (num, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, "s"),
+    };
+    let n: u64 = num.parse().ok()?;
+    let mult = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 3600,
+        "d" => 86400,
+        _ => return None,
+    };
+    n.checked_mul(mult)
 }
 
 pub fn print_anomaly_header(anomaly_state: &Option<AnomalyState>) {
@@ -1,4 +1,5 @@
 use chrono::Utc;
+use o2gen_profile::{load_profile, resolve};
 use rand::seq::SliceRandom;
 use rand::Rng;
 use reqwest::Client;
@@ -46,9 +47,11 @@ struct EnrichedRecord {
     _timestamp: i64,
 }
 
-const API_URL: &str = "http://localhost:5080/api/default/test1/_json";
+const DEFAULT_API_BASE: &str = "http://localhost:5080";
 // const API_URL: &str = "https://monitor.internal.zinclabs.dev/api/otlp-production/oly1/_json";
 // const API_URL: &str = "https://dev2.internal.zinclabs.dev/api/default/oly121/_json";
+const DEFAULT_ORG: &str = "default";
+const DEFAULT_STREAM: &str = "test1";
 const USERNAME: &str = "root@example.com";
 const PASSWORD: &str = "Complexpass#123";
 // const PASSWORD: &str = "ac0dcdf1c5a1183bd78a9bdb67e18406";
@@ -60,6 +63,7 @@ const RECORDS_PER_SECOND: usize = 2;
 // cargo r -- no-index
 // cargo r
 // cargo r -- new-fields
+// cargo r -- --profile dev
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Check for command line arguments
@@ -70,6 +74,26 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let include_body = !no_fts_mode;
     let include_continent = !no_index_mode;
 
+    // Endpoint, org, credentials and stream come from the selected profile;
+    // O2_* env vars override individual settings.
+    let profile_name = args
+        .windows(2)
+        .find(|w| w[0] == "--profile")
+        .map(|w| w[1].as_str());
+    let profile = load_profile(profile_name)?;
+    let api_url = format!(
+        "{}/api/{}/{}/_json",
+        resolve("O2_API_BASE", profile.endpoint.as_deref(), DEFAULT_API_BASE),
+        resolve("O2_ORG", profile.org.as_deref(), DEFAULT_ORG),
+        resolve(
+            "O2_STREAM_LIVE_INGEST",
+            profile.stream("live_ingest"),
+            DEFAULT_STREAM
+        )
+    );
+    let username = resolve("O2_USERNAME", profile.username.as_deref(), USERNAME);
+    let password = resolve("O2_PASSWORD", profile.password.as_deref(), PASSWORD);
+
     // Read olympics.json from parent directory
     let olympics_path = "../olympics.json";
     println!("Reading olympics data from {}...", olympics_path);
@@ -82,7 +106,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "Starting live ingestion: {} records per second",
         RECORDS_PER_SECOND
     );
-    println!("API: {}", API_URL);
+    println!("API: {}", api_url);
 
     if new_fields_mode {
         println!("Mode: new-fields (includes body + sport_category, athlete_highlight, participation_year)");
@@ -123,6 +147,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         "Taekwondo",
     ];
 
+    #[allow(clippy::useless_vec)]
     let athlete_highlights = vec![
         "Record-breaking performance in finals",
         "Youngest medalist in team history",
@@ -182,8 +207,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
         // Send to API
         match client
-            .post(API_URL)
-            .basic_auth(USERNAME, Some(PASSWORD))
+            .post(&api_url)
+            .basic_auth(&username, Some(&password))
             .json(&random_records)
             .send()
             .await
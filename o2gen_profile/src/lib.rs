@@ -0,0 +1,121 @@
+//! Named connection profiles shared by every binary in this repo
+//! (`k8s_data_gen`, `histogram_edge_sim`, `live_ingest`).
+//!
+//! Profiles live in `~/.config/o2gen/profiles.toml` (override with
+//! `$O2GEN_PROFILES`):
+//!
+//! ```toml
+//! default = "local"
+//!
+//! [profiles.local]
+//! endpoint = "http://localhost:5080"
+//!
+//! [profiles.team1]
+//! endpoint      = "https://o2.example.com"
+//! grpc_endpoint = "https://o2.example.com:5081"
+//! org           = "team1"
+//! username      = "me@example.com"
+//! password      = "secret"
+//! streams       = { logs = "k8s_logs", traces = "k8s_traces_grpc" }
+//! ```
+//!
+//! The profile is picked by `--profile NAME`, then `$O2_PROFILE`, then the
+//! file's `default`. Each setting resolves as `O2_*` env var > profile >
+//! the binary's built-in default.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+#[derive(Deserialize, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Profile {
+    /// HTTP base URL, e.g. `http://localhost:5080`.
+    pub endpoint: Option<String>,
+    pub grpc_endpoint: Option<String>,
+    pub org: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Stream name per role: `logs`, `traces`, `histogram_edge_sim`, `live_ingest`.
+    #[serde(default)]
+    pub streams: HashMap<String, String>,
+}
+
+impl Profile {
+    pub fn stream(&self, role: &str) -> Option<&str> {
+        self.streams.get(role).map(String::as_str)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ProfilesFile {
+    default: Option<String>,
+    #[serde(default)]
+    profiles: HashMap<String, Profile>,
+}
+
+pub fn profiles_path() -> Option<PathBuf> {
+    if let Some(p) = std::env::var_os("O2GEN_PROFILES") {
+        return Some(PathBuf::from(p));
+    }
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))?;
+    Some(config_dir.join("o2gen").join("profiles.toml"))
+}
+
+/// Load the selected profile. Without a profiles file, or with no profile
+/// selected, an empty profile is returned and built-in defaults apply.
+pub fn load_profile(name: Option<&str>) -> Result<Profile, Box<dyn std::error::Error>> {
+    let name = name
+        .map(str::to_string)
+        .or_else(|| std::env::var("O2_PROFILE").ok());
+    let path = profiles_path();
+
+    let text = match path.as_ref().map(std::fs::read_to_string) {
+        Some(Ok(text)) => text,
+        Some(Err(e)) if e.kind() != std::io::ErrorKind::NotFound => {
+            return Err(format!("read {}: {}", path.unwrap().display(), e).into());
+        }
+        _ => {
+            return match name {
+                Some(n) => Err(format!(
+                    "profile '{}' requested but no profiles file found at {}",
+                    n,
+                    path.map(|p| p.display().to_string())
+                        .unwrap_or_else(|| "~/.config/o2gen/profiles.toml".into())
+                )
+                .into()),
+                None => Ok(Profile::default()),
+            };
+        }
+    };
+    let path = path.unwrap();
+
+    let file: ProfilesFile =
+        toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let Some(name) = name.or(file.default) else {
+        return Ok(Profile::default());
+    };
+    file.profiles.get(&name).cloned().ok_or_else(|| {
+        let mut known: Vec<_> = file.profiles.keys().map(String::as_str).collect();
+        known.sort_unstable();
+        format!(
+            "profile '{}' not found in {} (available: {})",
+            name,
+            path.display(),
+            known.join(", ")
+        )
+        .into()
+    })
+}
+
+/// `env_var` if set, else the profile's value, else `default`.
+pub fn resolve(env_var: &str, from_profile: Option<&str>, default: &str) -> String {
+    std::env::var(env_var)
+        .ok()
+        .or_else(|| from_profile.map(str::to_string))
+        .unwrap_or_else(|| default.to_string())
+}